@@ -1,11 +1,12 @@
-use std::sync::Arc;
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
 
 use r2d2::{Pool, PooledConnection};
 use r2d2_sqlite::SqliteConnectionManager;
 use tauri::{AppHandle, Manager};
 
-use crate::db::migrations;
+use crate::db::{migrations, repository};
 
 pub type DbPool = Arc<Pool<SqliteConnectionManager>>;
 pub type DbConnection = PooledConnection<SqliteConnectionManager>;
@@ -13,6 +14,9 @@ pub type DbConnection = PooledConnection<SqliteConnectionManager>;
 pub struct AppState {
   pub db_pool: DbPool,
   pub media_root: PathBuf,
+  pub thumbnail_root: PathBuf,
+  /// Canonical paths the user picked in the native file dialog this session.
+  pub approved_sources: Mutex<HashSet<PathBuf>>,
 }
 
 impl AppState {
@@ -21,12 +25,19 @@ impl AppState {
       .path()
       .app_data_dir()
       .map_err(|error| format!("failed to resolve app data dir: {error}"))?;
+    Self::open(&app_data_dir)
+  }
 
-    std::fs::create_dir_all(&app_data_dir)
+  /// Opens (creating and migrating as needed) the database and media folders under `app_data_dir`.
+  pub fn open(app_data_dir: &Path) -> Result<Self, String> {
+    std::fs::create_dir_all(app_data_dir)
       .map_err(|error| format!("failed to create app data directory: {error}"))?;
     let media_root = app_data_dir.join("media");
     std::fs::create_dir_all(&media_root)
       .map_err(|error| format!("failed to create media directory: {error}"))?;
+    let thumbnail_root = app_data_dir.join("thumbnails");
+    std::fs::create_dir_all(&thumbnail_root)
+      .map_err(|error| format!("failed to create thumbnail directory: {error}"))?;
 
     let db_path = app_data_dir.join("obsidian_ai_agent.db");
     let manager = SqliteConnectionManager::file(db_path).with_init(|conn| {
@@ -50,11 +61,14 @@ impl AppState {
         .get()
         .map_err(|error| format!("failed to get sqlite connection for migration: {error}"))?;
       migrations::run(&conn)?;
+      repository::seed_prompt_templates(&conn, crate::time_now_ms())?;
     }
 
     Ok(Self {
       db_pool: Arc::new(pool),
       media_root,
+      thumbnail_root,
+      approved_sources: Mutex::new(HashSet::new()),
     })
   }
 
@@ -64,4 +78,18 @@ impl AppState {
       .get()
       .map_err(|error| format!("failed to borrow sqlite connection: {error}"))
   }
+
+  pub fn approved_sources(&self) -> HashSet<PathBuf> {
+    self
+      .approved_sources
+      .lock()
+      .map(|approved| approved.clone())
+      .unwrap_or_default()
+  }
+
+  pub fn approve_sources(&self, paths: impl IntoIterator<Item = PathBuf>) {
+    if let Ok(mut approved) = self.approved_sources.lock() {
+      approved.extend(paths);
+    }
+  }
 }
@@ -0,0 +1,39 @@
+use chrono::{DateTime, Datelike, Utc};
+
+use crate::{
+  app_state::DbConnection,
+  db::repository,
+  models::{BudgetStatus, SettingsPayload},
+};
+
+/// Start (ms) of the UTC day and the UTC month containing `now`.
+fn window_starts(now: i64) -> (i64, i64) {
+  let date = DateTime::from_timestamp_millis(now).unwrap_or_else(Utc::now).date_naive();
+  let start_of = |date: chrono::NaiveDate| date.and_time(chrono::NaiveTime::MIN).and_utc().timestamp_millis();
+  (start_of(date), start_of(date.with_day(1).unwrap_or(date)))
+}
+
+fn token_cap_reached(window: &str, used: i64, cap: i64) -> Option<String> {
+  (cap > 0 && used >= cap).then(|| format!("{window} token budget reached ({used} of {cap} tokens)"))
+}
+
+fn cost_cap_reached(window: &str, used: f64, cap: f64) -> Option<String> {
+  (cap > 0.0 && used >= cap).then(|| format!("{window} cost budget reached (${used:.4} of ${cap:.2})"))
+}
+
+/// Usage so far today and this month against the caps in `settings`. Windows reset at UTC midnight and
+/// on the first of the month, matching the monthly usage report.
+pub fn status(conn: &DbConnection, settings: &SettingsPayload, now: i64) -> Result<BudgetStatus, String> {
+  let (day_start, month_start) = window_starts(now);
+  let today = repository::token_usage_since(conn, day_start)?;
+  let this_month = repository::token_usage_since(conn, month_start)?;
+  let exceeded = token_cap_reached("daily", today.total_tokens, settings.daily_token_budget)
+    .or_else(|| cost_cap_reached("daily", today.cost_usd, settings.daily_cost_budget_usd))
+    .or_else(|| token_cap_reached("monthly", this_month.total_tokens, settings.monthly_token_budget))
+    .or_else(|| cost_cap_reached("monthly", this_month.cost_usd, settings.monthly_cost_budget_usd));
+  Ok(BudgetStatus {
+    today,
+    this_month,
+    exceeded,
+  })
+}
@@ -123,6 +123,276 @@ fn migrations() -> Vec<Migration> {
         END;
       ",
     },
+    Migration {
+      version: 4,
+      name: "add_storage_accounting",
+      sql: "
+        ALTER TABLE settings ADD COLUMN media_quota_bytes INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE settings ADD COLUMN media_retention_days INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE media_asset ADD COLUMN evicted_at INTEGER;
+
+        CREATE INDEX IF NOT EXISTS idx_media_asset_created ON media_asset(created_at);
+      ",
+    },
+    Migration {
+      version: 5,
+      name: "add_ingest_modes",
+      sql: "
+        ALTER TABLE settings ADD COLUMN ingest_mode TEXT NOT NULL DEFAULT 'copy';
+        ALTER TABLE media_asset ADD COLUMN ingest_mode TEXT NOT NULL DEFAULT 'copy';
+      ",
+    },
+    Migration {
+      version: 6,
+      name: "add_media_encryption",
+      sql: "
+        ALTER TABLE settings ADD COLUMN encrypt_media INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE media_asset ADD COLUMN encrypted INTEGER NOT NULL DEFAULT 0;
+      ",
+    },
+    Migration {
+      version: 7,
+      name: "add_archive_origin",
+      sql: "
+        ALTER TABLE media_asset ADD COLUMN archive_path TEXT;
+        ALTER TABLE media_asset ADD COLUMN archive_entry TEXT;
+      ",
+    },
+    Migration {
+      version: 8,
+      name: "add_image_derivatives",
+      sql: "
+        ALTER TABLE settings ADD COLUMN image_derivatives_enabled INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE settings ADD COLUMN image_max_dimension INTEGER NOT NULL DEFAULT 2048;
+        ALTER TABLE settings ADD COLUMN image_jpeg_quality INTEGER NOT NULL DEFAULT 82;
+        ALTER TABLE media_asset ADD COLUMN derivative_path TEXT;
+        ALTER TABLE media_asset ADD COLUMN derivative_mime_type TEXT;
+        ALTER TABLE media_asset ADD COLUMN derivative_size_bytes INTEGER;
+      ",
+    },
+    Migration {
+      version: 9,
+      name: "add_asset_thumbnails",
+      sql: "
+        ALTER TABLE media_asset ADD COLUMN thumbnail_path TEXT;
+      ",
+    },
+    Migration {
+      version: 10,
+      name: "add_perceptual_hashes",
+      sql: "
+        ALTER TABLE media_asset ADD COLUMN perceptual_hash INTEGER;
+        ALTER TABLE media_asset ADD COLUMN near_duplicate_of INTEGER REFERENCES media_asset(id) ON DELETE SET NULL;
+      ",
+    },
+    Migration {
+      version: 11,
+      name: "add_media_chunks",
+      sql: "
+        CREATE TABLE IF NOT EXISTS media_chunk (
+          id INTEGER PRIMARY KEY AUTOINCREMENT,
+          asset_id INTEGER NOT NULL REFERENCES media_asset(id) ON DELETE CASCADE,
+          chunk_index INTEGER NOT NULL,
+          start_ms INTEGER NOT NULL,
+          end_ms INTEGER NOT NULL,
+          storage_path TEXT NOT NULL,
+          size_bytes INTEGER NOT NULL,
+          created_at INTEGER NOT NULL,
+          UNIQUE (asset_id, chunk_index)
+        );
+        CREATE INDEX IF NOT EXISTS idx_media_chunk_asset ON media_chunk(asset_id);
+      ",
+    },
+    Migration {
+      version: 12,
+      name: "add_subtitle_sidecars",
+      sql: "
+        ALTER TABLE media_asset ADD COLUMN parent_asset_id INTEGER REFERENCES media_asset(id) ON DELETE CASCADE;
+        CREATE TABLE IF NOT EXISTS subtitle_cue (
+          id INTEGER PRIMARY KEY AUTOINCREMENT,
+          asset_id INTEGER NOT NULL REFERENCES media_asset(id) ON DELETE CASCADE,
+          cue_index INTEGER NOT NULL,
+          start_ms INTEGER NOT NULL,
+          end_ms INTEGER NOT NULL,
+          text TEXT NOT NULL,
+          UNIQUE (asset_id, cue_index)
+        );
+        CREATE INDEX IF NOT EXISTS idx_subtitle_cue_asset ON subtitle_cue(asset_id);
+      ",
+    },
+    Migration {
+      version: 13,
+      name: "add_quarantine",
+      sql: "
+        CREATE TABLE IF NOT EXISTS quarantine_item (
+          id INTEGER PRIMARY KEY AUTOINCREMENT,
+          job_id TEXT NOT NULL REFERENCES ingestion_job(id) ON DELETE CASCADE,
+          original_path TEXT NOT NULL,
+          storage_path TEXT NOT NULL,
+          media_type TEXT NOT NULL,
+          mime_type TEXT NOT NULL,
+          size_bytes INTEGER NOT NULL,
+          sha256 TEXT NOT NULL,
+          ingest_mode TEXT NOT NULL,
+          encrypted INTEGER NOT NULL DEFAULT 0,
+          reason TEXT NOT NULL,
+          status TEXT NOT NULL DEFAULT 'pending',
+          created_at INTEGER NOT NULL,
+          resolved_at INTEGER
+        );
+        CREATE INDEX IF NOT EXISTS idx_quarantine_item_status ON quarantine_item(status);
+      ",
+    },
+    Migration {
+      version: 14,
+      name: "add_allowed_source_roots",
+      sql: "
+        ALTER TABLE settings ADD COLUMN allowed_source_roots TEXT NOT NULL DEFAULT '[]';
+      ",
+    },
+    Migration {
+      version: 15,
+      name: "add_extraction_cache_key",
+      sql: "
+        ALTER TABLE extraction_result ADD COLUMN cache_key TEXT;
+        CREATE INDEX IF NOT EXISTS idx_extraction_result_cache_key ON extraction_result(cache_key, created_at DESC);
+      ",
+    },
+    Migration {
+      version: 16,
+      name: "add_prompt_template_selection",
+      sql: "
+        ALTER TABLE settings ADD COLUMN prompt_template_key TEXT NOT NULL DEFAULT 'default_extraction';
+      ",
+    },
+    Migration {
+      version: 17,
+      name: "extend_prompt_run_audit",
+      sql: "
+        ALTER TABLE prompt_run ADD COLUMN model TEXT NOT NULL DEFAULT '';
+        ALTER TABLE prompt_run ADD COLUMN latency_ms INTEGER;
+        ALTER TABLE prompt_run ADD COLUMN prompt_tokens INTEGER;
+        ALTER TABLE prompt_run ADD COLUMN response_tokens INTEGER;
+        ALTER TABLE prompt_run ADD COLUMN total_tokens INTEGER;
+        ALTER TABLE prompt_run ADD COLUMN error TEXT;
+        CREATE INDEX IF NOT EXISTS idx_prompt_run_job ON prompt_run(job_id, created_at DESC);
+      ",
+    },
+    Migration {
+      version: 18,
+      name: "add_note_language",
+      sql: "
+        ALTER TABLE settings ADD COLUMN note_language TEXT NOT NULL DEFAULT 'pt';
+        ALTER TABLE ingestion_job ADD COLUMN note_language TEXT;
+      ",
+    },
+    Migration {
+      version: 19,
+      name: "add_llm_provider",
+      sql: "
+        ALTER TABLE settings ADD COLUMN llm_provider TEXT NOT NULL DEFAULT 'gemini';
+        ALTER TABLE settings ADD COLUMN openai_base_url TEXT NOT NULL DEFAULT 'http://localhost:11434/v1';
+        ALTER TABLE settings ADD COLUMN openai_model TEXT NOT NULL DEFAULT 'llama3.2';
+      ",
+    },
+    Migration {
+      version: 20,
+      name: "add_gemini_base_url",
+      sql: "
+        ALTER TABLE settings ADD COLUMN gemini_base_url TEXT NOT NULL
+          DEFAULT 'https://generativelanguage.googleapis.com';
+      ",
+    },
+    Migration {
+      version: 21,
+      name: "add_token_cost_tracking",
+      sql: "
+        ALTER TABLE prompt_run ADD COLUMN cached_tokens INTEGER;
+        ALTER TABLE prompt_run ADD COLUMN cost_usd REAL;
+        CREATE INDEX IF NOT EXISTS idx_prompt_run_created_at ON prompt_run(created_at);
+
+        CREATE TABLE IF NOT EXISTS model_price (
+          model TEXT PRIMARY KEY,
+          input_usd_per_mtok REAL NOT NULL,
+          output_usd_per_mtok REAL NOT NULL,
+          cached_input_usd_per_mtok REAL NOT NULL,
+          updated_at INTEGER NOT NULL
+        );
+
+        INSERT OR IGNORE INTO model_price
+          (model, input_usd_per_mtok, output_usd_per_mtok, cached_input_usd_per_mtok, updated_at)
+        VALUES
+          ('gemini/gemini-2.5-pro', 1.25, 10.0, 0.31, 0),
+          ('gemini/gemini-2.5-flash', 0.30, 2.50, 0.075, 0),
+          ('gemini/gemini-2.5-flash-lite', 0.10, 0.40, 0.025, 0),
+          ('gemini/gemini-2.0-flash', 0.10, 0.40, 0.025, 0);
+      ",
+    },
+    Migration {
+      version: 22,
+      name: "add_usage_budgets",
+      sql: "
+        ALTER TABLE settings ADD COLUMN daily_token_budget INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE settings ADD COLUMN monthly_token_budget INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE settings ADD COLUMN daily_cost_budget_usd REAL NOT NULL DEFAULT 0;
+        ALTER TABLE settings ADD COLUMN monthly_cost_budget_usd REAL NOT NULL DEFAULT 0;
+      ",
+    },
+    Migration {
+      version: 23,
+      name: "add_rate_limits_and_job_retries",
+      sql: "
+        ALTER TABLE settings ADD COLUMN gemini_requests_per_minute INTEGER NOT NULL DEFAULT 10;
+        ALTER TABLE ingestion_job ADD COLUMN attempt_count INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE ingestion_job ADD COLUMN next_attempt_at INTEGER;
+      ",
+    },
+    Migration {
+      version: 24,
+      name: "add_api_key_validation_and_gemini_models",
+      sql: "
+        CREATE TABLE IF NOT EXISTS api_key_validation (
+          provider TEXT PRIMARY KEY,
+          ok INTEGER NOT NULL,
+          message TEXT,
+          validated_at INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS gemini_model (
+          id TEXT PRIMARY KEY,
+          display_name TEXT NOT NULL,
+          description TEXT,
+          input_token_limit INTEGER,
+          output_token_limit INTEGER,
+          input_modalities TEXT NOT NULL,
+          fetched_at INTEGER NOT NULL
+        );
+      ",
+    },
+    Migration {
+      version: 25,
+      name: "add_job_auto_extract",
+      // Jobs from before the runner existed are left for the user to run, not billed on the first start.
+      sql: "
+        ALTER TABLE ingestion_job ADD COLUMN auto_extract INTEGER NOT NULL DEFAULT 0;
+      ",
+    },
+    Migration {
+      version: 26,
+      name: "add_job_claimed_from_status",
+      // The status a UI extraction borrowed the job from, so a crash mid-call puts it back instead of requeueing it.
+      sql: "
+        ALTER TABLE ingestion_job ADD COLUMN claimed_from_status TEXT;
+      ",
+    },
+    Migration {
+      version: 27,
+      name: "rename_gemini_model_modalities",
+      // The modalities are guessed from the model name, not reported by the API.
+      sql: "
+        ALTER TABLE gemini_model RENAME COLUMN input_modalities TO assumed_input_modalities;
+      ",
+    },
   ]
 }
 
@@ -1,16 +1,23 @@
-use rusqlite::{params, OptionalExtension};
+use rusqlite::{params, OptionalExtension, Row, Transaction};
 
 use crate::{
-  ingestion::PreparedAsset,
-  models::{JobAsset, JobDetails, JobSummary, SettingsPayload},
+  ingestion::{policy, PreparedAsset, PreparedBatch},
+  language,
+  llm::{self, prompt, ModelCall},
+  models::{
+    Extraction, ExtractionResult, GeminiModelInfo, JobAsset, JobDetails, JobEvent, JobStorageUsage, JobSummary,
+    JobTokenUsage, KeyValidation, MediaChunk, ModelPrice, MonthlyStorageUsage, MonthlyTokenUsage, PromptRun,
+    PromptTemplate, QuarantineItem, SettingsPayload, SubtitleCue, TokenTotals,
+  },
 };
 
 use super::super::app_state::DbConnection;
 
 fn can_transition(current_status: &str, next_status: &str) -> bool {
   match current_status {
-    "queued" => matches!(next_status, "processing" | "cancelled" | "failed"),
-    "processing" => matches!(next_status, "completed" | "failed" | "cancelled"),
+    "queued" => matches!(next_status, "processing" | "cancelled" | "failed" | "waiting_budget"),
+    "processing" => matches!(next_status, "completed" | "failed" | "cancelled" | "waiting_budget"),
+    "waiting_budget" => matches!(next_status, "queued" | "cancelled"),
     "failed" => next_status == "queued",
     "cancelled" => next_status == "queued",
     "completed" => false,
@@ -18,12 +25,127 @@ fn can_transition(current_status: &str, next_status: &str) -> bool {
   }
 }
 
+/// Maps a row selected as `id, job_id, original_path, storage_path, media_type, mime_type, size_bytes,
+/// sha256, ingest_mode, encrypted, archive_path, archive_entry, derivative_path, derivative_mime_type,
+/// derivative_size_bytes, thumbnail_path, perceptual_hash, near_duplicate_of, duration_ms, parent_asset_id`.
+fn job_asset_from_row(row: &Row<'_>) -> rusqlite::Result<JobAsset> {
+  Ok(JobAsset {
+    id: row.get(0)?,
+    job_id: row.get(1)?,
+    original_path: row.get(2)?,
+    storage_path: row.get(3)?,
+    media_type: row.get(4)?,
+    mime_type: row.get(5)?,
+    size_bytes: row.get(6)?,
+    sha256: row.get(7)?,
+    ingest_mode: row.get(8)?,
+    encrypted: row.get(9)?,
+    archive_path: row.get(10)?,
+    archive_entry: row.get(11)?,
+    derivative_path: row.get(12)?,
+    derivative_mime_type: row.get(13)?,
+    derivative_size_bytes: row.get(14)?,
+    thumbnail_path: row.get(15)?,
+    perceptual_hash: row.get(16)?,
+    near_duplicate_of: row.get(17)?,
+    duration_ms: row.get(18)?,
+    parent_asset_id: row.get(19)?,
+  })
+}
+
+/// Inserts one asset row plus its chunk and subtitle cue rows, returning the new asset id.
+fn insert_asset(tx: &Transaction<'_>, job_id: &str, asset: &PreparedAsset, now: i64) -> Result<i64, String> {
+  tx
+    .execute(
+      "
+      INSERT INTO media_asset (
+        job_id,
+        original_path,
+        storage_path,
+        media_type,
+        mime_type,
+        size_bytes,
+        sha256,
+        duration_ms,
+        ingest_mode,
+        encrypted,
+        archive_path,
+        archive_entry,
+        derivative_path,
+        derivative_mime_type,
+        derivative_size_bytes,
+        thumbnail_path,
+        perceptual_hash,
+        created_at
+      )
+      VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)
+      ",
+      params![
+        job_id,
+        asset.original_path,
+        asset.storage_path,
+        asset.media_type,
+        asset.mime_type,
+        asset.size_bytes,
+        asset.sha256,
+        asset.duration_ms,
+        asset.ingest_mode,
+        asset.encrypted,
+        asset.archive_origin.as_ref().map(|origin| origin.archive_path.as_str()),
+        asset.archive_origin.as_ref().map(|origin| origin.entry_name.as_str()),
+        asset.derivative.as_ref().map(|derivative| derivative.path.as_str()),
+        asset.derivative.as_ref().map(|derivative| derivative.mime_type.as_str()),
+        asset.derivative.as_ref().map(|derivative| derivative.size_bytes),
+        asset.thumbnail_path,
+        asset.perceptual_hash,
+        now
+      ],
+    )
+    .map_err(|error| format!("failed to insert media asset: {error}"))?;
+
+  let asset_id = tx.last_insert_rowid();
+  for chunk in &asset.chunks {
+    tx
+      .execute(
+        "
+        INSERT INTO media_chunk (asset_id, chunk_index, start_ms, end_ms, storage_path, size_bytes, created_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        ",
+        params![
+          asset_id,
+          chunk.chunk_index,
+          chunk.start_ms,
+          chunk.end_ms,
+          chunk.storage_path,
+          chunk.size_bytes,
+          now
+        ],
+      )
+      .map_err(|error| format!("failed to insert media chunk: {error}"))?;
+  }
+
+  for (cue_index, cue) in asset.subtitle_cues.iter().enumerate() {
+    tx
+      .execute(
+        "
+        INSERT INTO subtitle_cue (asset_id, cue_index, start_ms, end_ms, text)
+        VALUES (?1, ?2, ?3, ?4, ?5)
+        ",
+        params![asset_id, cue_index as i64, cue.start_ms, cue.end_ms, cue.text],
+      )
+      .map_err(|error| format!("failed to insert subtitle cue: {error}"))?;
+  }
+
+  Ok(asset_id)
+}
+
 pub fn insert_job_with_assets(
   conn: &mut DbConnection,
   job_id: &str,
   title: &str,
   status: &str,
-  assets: &[PreparedAsset],
+  note_language: Option<&str>,
+  batch: &PreparedBatch,
   now: i64,
 ) -> Result<(), String> {
   let tx = conn
@@ -33,18 +155,36 @@ pub fn insert_job_with_assets(
   tx
     .execute(
       "
-      INSERT INTO ingestion_job (id, title, status, created_at, updated_at)
-      VALUES (?1, ?2, ?3, ?4, ?5)
+      INSERT INTO ingestion_job (id, title, status, created_at, updated_at, note_language, auto_extract)
+      VALUES (?1, ?2, ?3, ?4, ?5, ?6, 1)
       ",
-      params![job_id, title, status, now, now],
+      params![job_id, title, status, now, now, note_language],
     )
     .map_err(|error| format!("failed to insert ingestion job: {error}"))?;
 
-  for asset in assets {
+  let mut asset_ids = Vec::with_capacity(batch.assets.len());
+  for asset in &batch.assets {
+    asset_ids.push(insert_asset(&tx, job_id, asset, now)?);
+  }
+
+  // Parents may come later in the batch than their sidecars, so links are set once all ids exist.
+  for (asset, asset_id) in batch.assets.iter().zip(&asset_ids) {
+    if let Some(parent_index) = asset.parent_index {
+      tx
+        .execute(
+          "UPDATE media_asset SET parent_asset_id = ?1 WHERE id = ?2",
+          params![asset_ids[parent_index], asset_id],
+        )
+        .map_err(|error| format!("failed to link subtitle sidecar: {error}"))?;
+    }
+  }
+
+  for file in &batch.quarantined {
+    let asset = &file.asset;
     tx
       .execute(
         "
-        INSERT INTO media_asset (
+        INSERT INTO quarantine_item (
           job_id,
           original_path,
           storage_path,
@@ -52,10 +192,13 @@ pub fn insert_job_with_assets(
           mime_type,
           size_bytes,
           sha256,
-          duration_ms,
+          ingest_mode,
+          encrypted,
+          reason,
+          status,
           created_at
         )
-        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, NULL, ?8)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, 'pending', ?11)
         ",
         params![
           job_id,
@@ -65,10 +208,13 @@ pub fn insert_job_with_assets(
           asset.mime_type,
           asset.size_bytes,
           asset.sha256,
+          asset.ingest_mode,
+          asset.encrypted,
+          file.reason,
           now
         ],
       )
-      .map_err(|error| format!("failed to insert media asset: {error}"))?;
+      .map_err(|error| format!("failed to insert quarantine item: {error}"))?;
   }
 
   tx
@@ -76,6 +222,108 @@ pub fn insert_job_with_assets(
     .map_err(|error| format!("failed to commit insert_job_with_assets transaction: {error}"))
 }
 
+fn quarantine_item_from_row(row: &Row<'_>) -> rusqlite::Result<QuarantineItem> {
+  Ok(QuarantineItem {
+    id: row.get(0)?,
+    job_id: row.get(1)?,
+    original_path: row.get(2)?,
+    storage_path: row.get(3)?,
+    media_type: row.get(4)?,
+    mime_type: row.get(5)?,
+    size_bytes: row.get(6)?,
+    sha256: row.get(7)?,
+    ingest_mode: row.get(8)?,
+    encrypted: row.get(9)?,
+    reason: row.get(10)?,
+    status: row.get(11)?,
+    created_at: row.get(12)?,
+    resolved_at: row.get(13)?,
+  })
+}
+
+/// Lists quarantined files, optionally only those in `status` (`pending`, `approved`, `discarded`).
+pub fn list_quarantine_items(conn: &DbConnection, status: Option<&str>) -> Result<Vec<QuarantineItem>, String> {
+  let mut stmt = conn
+    .prepare(
+      "
+      SELECT id, job_id, original_path, storage_path, media_type, mime_type, size_bytes, sha256, ingest_mode,
+        encrypted, reason, status, created_at, resolved_at
+      FROM quarantine_item
+      WHERE ?1 IS NULL OR status = ?1
+      ORDER BY created_at DESC, id ASC
+      ",
+    )
+    .map_err(|error| format!("failed to prepare list_quarantine_items query: {error}"))?;
+
+  let rows = stmt
+    .query_map([status], quarantine_item_from_row)
+    .map_err(|error| format!("failed to run list_quarantine_items query: {error}"))?;
+
+  let mut items = Vec::new();
+  for row in rows {
+    items.push(row.map_err(|error| format!("failed to parse quarantine item row: {error}"))?);
+  }
+
+  Ok(items)
+}
+
+pub fn find_quarantine_item(conn: &DbConnection, item_id: i64) -> Result<Option<QuarantineItem>, String> {
+  conn
+    .query_row(
+      "
+      SELECT id, job_id, original_path, storage_path, media_type, mime_type, size_bytes, sha256, ingest_mode,
+        encrypted, reason, status, created_at, resolved_at
+      FROM quarantine_item
+      WHERE id = ?1
+      ",
+      [item_id],
+      quarantine_item_from_row,
+    )
+    .optional()
+    .map_err(|error| format!("failed to query quarantine item: {error}"))
+}
+
+/// Records the approval of a quarantined file and adds it to its job as a regular asset.
+pub fn approve_quarantine_item(
+  conn: &mut DbConnection,
+  item: &QuarantineItem,
+  asset: &PreparedAsset,
+  now: i64,
+) -> Result<i64, String> {
+  let tx = conn
+    .transaction()
+    .map_err(|error| format!("failed to start approve_quarantine_item transaction: {error}"))?;
+
+  let asset_id = insert_asset(&tx, &item.job_id, asset, now)?;
+  tx
+    .execute(
+      "UPDATE quarantine_item SET status = 'approved', storage_path = ?1, resolved_at = ?2 WHERE id = ?3",
+      params![asset.storage_path, now, item.id],
+    )
+    .map_err(|error| format!("failed to update quarantine item: {error}"))?;
+  tx
+    .execute(
+      "UPDATE ingestion_job SET updated_at = ?1 WHERE id = ?2",
+      params![now, item.job_id],
+    )
+    .map_err(|error| format!("failed to touch ingestion job: {error}"))?;
+
+  tx
+    .commit()
+    .map_err(|error| format!("failed to commit approve_quarantine_item transaction: {error}"))?;
+  Ok(asset_id)
+}
+
+pub fn discard_quarantine_item(conn: &DbConnection, item_id: i64, now: i64) -> Result<(), String> {
+  conn
+    .execute(
+      "UPDATE quarantine_item SET status = 'discarded', resolved_at = ?1 WHERE id = ?2",
+      params![now, item_id],
+    )
+    .map_err(|error| format!("failed to discard quarantine item: {error}"))?;
+  Ok(())
+}
+
 pub fn list_jobs(conn: &DbConnection) -> Result<Vec<JobSummary>, String> {
   let mut stmt = conn
     .prepare(
@@ -86,10 +334,17 @@ pub fn list_jobs(conn: &DbConnection) -> Result<Vec<JobSummary>, String> {
         j.status,
         j.created_at,
         j.updated_at,
-        COALESCE(COUNT(a.id), 0) AS asset_count
+        COALESCE(COUNT(a.id), 0) AS asset_count,
+        (
+          SELECT t.id FROM media_asset t
+          WHERE t.job_id = j.id AND t.thumbnail_path IS NOT NULL
+          ORDER BY t.id ASC
+          LIMIT 1
+        ) AS thumbnail_asset_id,
+        j.note_language
       FROM ingestion_job j
       LEFT JOIN media_asset a ON a.job_id = j.id
-      GROUP BY j.id, j.title, j.status, j.created_at, j.updated_at
+      GROUP BY j.id, j.title, j.status, j.created_at, j.updated_at, j.note_language
       ORDER BY j.updated_at DESC
       ",
     )
@@ -104,6 +359,8 @@ pub fn list_jobs(conn: &DbConnection) -> Result<Vec<JobSummary>, String> {
         created_at: row.get(3)?,
         updated_at: row.get(4)?,
         asset_count: row.get(5)?,
+        thumbnail_asset_id: row.get(6)?,
+        note_language: row.get(7)?,
       })
     })
     .map_err(|error| format!("failed to run list_jobs query: {error}"))?;
@@ -126,11 +383,18 @@ pub fn find_job_with_assets(conn: &DbConnection, job_id: &str) -> Result<Option<
         j.status,
         j.created_at,
         j.updated_at,
-        COALESCE(COUNT(a.id), 0) AS asset_count
+        COALESCE(COUNT(a.id), 0) AS asset_count,
+        (
+          SELECT t.id FROM media_asset t
+          WHERE t.job_id = j.id AND t.thumbnail_path IS NOT NULL
+          ORDER BY t.id ASC
+          LIMIT 1
+        ) AS thumbnail_asset_id,
+        j.note_language
       FROM ingestion_job j
       LEFT JOIN media_asset a ON a.job_id = j.id
       WHERE j.id = ?1
-      GROUP BY j.id, j.title, j.status, j.created_at, j.updated_at
+      GROUP BY j.id, j.title, j.status, j.created_at, j.updated_at, j.note_language
       ",
     )
     .map_err(|error| format!("failed to prepare get_job query: {error}"))?;
@@ -144,6 +408,8 @@ pub fn find_job_with_assets(conn: &DbConnection, job_id: &str) -> Result<Option<
         created_at: row.get(3)?,
         updated_at: row.get(4)?,
         asset_count: row.get(5)?,
+        thumbnail_asset_id: row.get(6)?,
+        note_language: row.get(7)?,
       })
     })
     .optional()
@@ -156,7 +422,9 @@ pub fn find_job_with_assets(conn: &DbConnection, job_id: &str) -> Result<Option<
   let mut assets_stmt = conn
     .prepare(
       "
-      SELECT id, job_id, original_path, storage_path, media_type, mime_type, size_bytes, sha256
+      SELECT id, job_id, original_path, storage_path, media_type, mime_type, size_bytes, sha256, ingest_mode,
+        encrypted, archive_path, archive_entry, derivative_path, derivative_mime_type, derivative_size_bytes,
+        thumbnail_path, perceptual_hash, near_duplicate_of, duration_ms, parent_asset_id
       FROM media_asset
       WHERE job_id = ?1
       ORDER BY id ASC
@@ -165,97 +433,1310 @@ pub fn find_job_with_assets(conn: &DbConnection, job_id: &str) -> Result<Option<
     .map_err(|error| format!("failed to prepare job assets query: {error}"))?;
 
   let rows = assets_stmt
-    .query_map([job_id], |row| {
-      Ok(JobAsset {
+    .query_map([job_id], job_asset_from_row)
+    .map_err(|error| format!("failed to run job assets query: {error}"))?;
+
+  let mut assets = Vec::new();
+  for row in rows {
+    assets.push(row.map_err(|error| format!("failed to parse media asset row: {error}"))?);
+  }
+
+  let chunks = list_job_chunks(conn, job_id)?;
+
+  Ok(Some(JobDetails { job, assets, chunks }))
+}
+
+fn media_chunk_from_row(row: &Row<'_>) -> rusqlite::Result<MediaChunk> {
+  Ok(MediaChunk {
+    id: row.get(0)?,
+    asset_id: row.get(1)?,
+    chunk_index: row.get(2)?,
+    start_ms: row.get(3)?,
+    end_ms: row.get(4)?,
+    storage_path: row.get(5)?,
+    size_bytes: row.get(6)?,
+  })
+}
+
+fn list_job_chunks(conn: &DbConnection, job_id: &str) -> Result<Vec<MediaChunk>, String> {
+  let mut stmt = conn
+    .prepare(
+      "
+      SELECT c.id, c.asset_id, c.chunk_index, c.start_ms, c.end_ms, c.storage_path, c.size_bytes
+      FROM media_chunk c
+      JOIN media_asset a ON a.id = c.asset_id
+      WHERE a.job_id = ?1
+      ORDER BY c.asset_id ASC, c.chunk_index ASC
+      ",
+    )
+    .map_err(|error| format!("failed to prepare job chunks query: {error}"))?;
+
+  let rows = stmt
+    .query_map([job_id], media_chunk_from_row)
+    .map_err(|error| format!("failed to run job chunks query: {error}"))?;
+
+  let mut chunks = Vec::new();
+  for row in rows {
+    chunks.push(row.map_err(|error| format!("failed to parse media chunk row: {error}"))?);
+  }
+
+  Ok(chunks)
+}
+
+pub fn list_subtitle_cues(conn: &DbConnection, asset_id: i64) -> Result<Vec<SubtitleCue>, String> {
+  let mut stmt = conn
+    .prepare(
+      "
+      SELECT id, asset_id, cue_index, start_ms, end_ms, text
+      FROM subtitle_cue
+      WHERE asset_id = ?1
+      ORDER BY cue_index ASC
+      ",
+    )
+    .map_err(|error| format!("failed to prepare list_subtitle_cues query: {error}"))?;
+
+  let rows = stmt
+    .query_map([asset_id], |row| {
+      Ok(SubtitleCue {
         id: row.get(0)?,
-        job_id: row.get(1)?,
-        original_path: row.get(2)?,
-        storage_path: row.get(3)?,
-        media_type: row.get(4)?,
-        mime_type: row.get(5)?,
-        size_bytes: row.get(6)?,
-        sha256: row.get(7)?,
+        asset_id: row.get(1)?,
+        cue_index: row.get(2)?,
+        start_ms: row.get(3)?,
+        end_ms: row.get(4)?,
+        text: row.get(5)?,
       })
     })
-    .map_err(|error| format!("failed to run job assets query: {error}"))?;
+    .map_err(|error| format!("failed to run list_subtitle_cues query: {error}"))?;
+
+  let mut cues = Vec::new();
+  for row in rows {
+    cues.push(row.map_err(|error| format!("failed to parse subtitle cue row: {error}"))?);
+  }
+
+  Ok(cues)
+}
+
+/// Lists the chunks of one asset, or of every asset when `asset_id` is `None`.
+pub fn list_media_chunks(conn: &DbConnection, asset_id: Option<i64>) -> Result<Vec<MediaChunk>, String> {
+  let mut stmt = conn
+    .prepare(
+      "
+      SELECT id, asset_id, chunk_index, start_ms, end_ms, storage_path, size_bytes
+      FROM media_chunk
+      WHERE ?1 IS NULL OR asset_id = ?1
+      ORDER BY asset_id ASC, chunk_index ASC
+      ",
+    )
+    .map_err(|error| format!("failed to prepare list_media_chunks query: {error}"))?;
+
+  let rows = stmt
+    .query_map([asset_id], media_chunk_from_row)
+    .map_err(|error| format!("failed to run list_media_chunks query: {error}"))?;
+
+  let mut chunks = Vec::new();
+  for row in rows {
+    chunks.push(row.map_err(|error| format!("failed to parse media chunk row: {error}"))?);
+  }
+
+  Ok(chunks)
+}
+
+pub fn list_media_assets(conn: &DbConnection) -> Result<Vec<JobAsset>, String> {
+  let mut stmt = conn
+    .prepare(
+      "
+      SELECT id, job_id, original_path, storage_path, media_type, mime_type, size_bytes, sha256, ingest_mode,
+        encrypted, archive_path, archive_entry, derivative_path, derivative_mime_type, derivative_size_bytes,
+        thumbnail_path, perceptual_hash, near_duplicate_of, duration_ms, parent_asset_id
+      FROM media_asset
+      WHERE evicted_at IS NULL
+      ORDER BY id ASC
+      ",
+    )
+    .map_err(|error| format!("failed to prepare list_media_assets query: {error}"))?;
+
+  let rows = stmt
+    .query_map([], job_asset_from_row)
+    .map_err(|error| format!("failed to run list_media_assets query: {error}"))?;
 
   let mut assets = Vec::new();
   for row in rows {
     assets.push(row.map_err(|error| format!("failed to parse media asset row: {error}"))?);
   }
 
-  Ok(Some(JobDetails { job, assets }))
+  Ok(assets)
 }
 
-pub fn update_job_status(conn: &DbConnection, job_id: &str, next_status: &str, now: i64) -> Result<bool, String> {
-  let current_status = conn
+pub fn find_media_asset(conn: &DbConnection, asset_id: i64) -> Result<Option<JobAsset>, String> {
+  conn
     .query_row(
-      "SELECT status FROM ingestion_job WHERE id = ?1",
-      [job_id],
-      |row| row.get::<_, String>(0),
+      "
+      SELECT id, job_id, original_path, storage_path, media_type, mime_type, size_bytes, sha256, ingest_mode,
+        encrypted, archive_path, archive_entry, derivative_path, derivative_mime_type, derivative_size_bytes,
+        thumbnail_path, perceptual_hash, near_duplicate_of, duration_ms, parent_asset_id
+      FROM media_asset
+      WHERE id = ?1
+      ",
+      [asset_id],
+      job_asset_from_row,
     )
     .optional()
-    .map_err(|error| format!("failed to load current job status: {error}"))?;
+    .map_err(|error| format!("failed to query media asset: {error}"))
+}
 
-  let Some(current_status) = current_status else {
-    return Ok(false);
-  };
+/// Assets with a stored copy whose job was last published to the vault before `published_before`.
+pub fn list_evictable_assets(conn: &DbConnection, published_before: i64) -> Result<Vec<JobAsset>, String> {
+  let mut stmt = conn
+    .prepare(
+      "
+      SELECT a.id, a.job_id, a.original_path, a.storage_path, a.media_type, a.mime_type, a.size_bytes, a.sha256,
+        a.ingest_mode, a.encrypted, a.archive_path, a.archive_entry, a.derivative_path, a.derivative_mime_type,
+        a.derivative_size_bytes, a.thumbnail_path, a.perceptual_hash, a.near_duplicate_of,
+        a.duration_ms, a.parent_asset_id
+      FROM media_asset a
+      WHERE a.evicted_at IS NULL
+        AND (a.ingest_mode != 'reference' OR a.derivative_path IS NOT NULL)
+        AND (SELECT MAX(n.created_at) FROM obsidian_note n WHERE n.job_id = a.job_id) < ?1
+      ORDER BY a.id ASC
+      ",
+    )
+    .map_err(|error| format!("failed to prepare list_evictable_assets query: {error}"))?;
 
-  if !can_transition(&current_status, next_status) {
-    return Err(format!("invalid status transition: {current_status} -> {next_status}"));
+  let rows = stmt
+    .query_map([published_before], job_asset_from_row)
+    .map_err(|error| format!("failed to run list_evictable_assets query: {error}"))?;
+
+  let mut assets = Vec::new();
+  for row in rows {
+    assets.push(row.map_err(|error| format!("failed to parse media asset row: {error}"))?);
   }
 
-  let changed = conn
+  Ok(assets)
+}
+
+/// Returns `(id, perceptual_hash)` for every hashed asset, including evicted ones so that new captures
+/// are still compared against history.
+pub fn list_perceptual_hashes(conn: &DbConnection) -> Result<Vec<(i64, i64)>, String> {
+  let mut stmt = conn
+    .prepare("SELECT id, perceptual_hash FROM media_asset WHERE perceptual_hash IS NOT NULL ORDER BY id ASC")
+    .map_err(|error| format!("failed to prepare list_perceptual_hashes query: {error}"))?;
+
+  let rows = stmt
+    .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+    .map_err(|error| format!("failed to run list_perceptual_hashes query: {error}"))?;
+
+  let mut hashes = Vec::new();
+  for row in rows {
+    hashes.push(row.map_err(|error| format!("failed to parse perceptual hash row: {error}"))?);
+  }
+
+  Ok(hashes)
+}
+
+pub fn mark_near_duplicate(conn: &DbConnection, asset_id: i64, duplicate_of: i64) -> Result<(), String> {
+  conn
     .execute(
-      "UPDATE ingestion_job SET status = ?1, updated_at = ?2 WHERE id = ?3",
-      params![next_status, now, job_id],
+      "UPDATE media_asset SET near_duplicate_of = ?2 WHERE id = ?1",
+      params![asset_id, duplicate_of],
     )
-    .map_err(|error| format!("failed to update job status: {error}"))?;
+    .map_err(|error| format!("failed to flag near-duplicate media asset: {error}"))?;
+  Ok(())
+}
 
-  Ok(changed > 0)
+/// Marks an asset evicted and drops its chunk rows; chunks are derived data with no value once the
+/// files are gone.
+pub fn mark_asset_evicted(conn: &DbConnection, asset_id: i64, now: i64) -> Result<(), String> {
+  conn
+    .execute(
+      "UPDATE media_asset SET evicted_at = ?1, thumbnail_path = NULL WHERE id = ?2",
+      params![now, asset_id],
+    )
+    .map_err(|error| format!("failed to mark media asset as evicted: {error}"))?;
+  conn
+    .execute("DELETE FROM media_chunk WHERE asset_id = ?1", [asset_id])
+    .map_err(|error| format!("failed to remove evicted media chunks: {error}"))?;
+  Ok(())
 }
 
-pub fn get_settings(conn: &DbConnection) -> Result<SettingsPayload, String> {
+pub fn stored_media_bytes(conn: &DbConnection) -> Result<i64, String> {
   conn
     .query_row(
       "
-      SELECT vault_path, obsidian_cli_path, gemini_model, write_mode
-      FROM settings
-      WHERE id = 1
+      SELECT COALESCE(SUM(
+        CASE WHEN a.ingest_mode = 'reference' THEN 0 ELSE a.size_bytes END + COALESCE(a.derivative_size_bytes, 0)
+          + COALESCE((SELECT SUM(c.size_bytes) FROM media_chunk c WHERE c.asset_id = a.id), 0)
+      ), 0)
+      FROM media_asset a
+      WHERE a.evicted_at IS NULL
       ",
       [],
-      |row| {
-        Ok(SettingsPayload {
-          vault_path: row.get(0)?,
-          obsidian_cli_path: row.get(1)?,
-          gemini_model: row.get(2)?,
-          write_mode: row.get(3)?,
-        })
-      },
+      |row| row.get(0),
     )
-    .map_err(|error| format!("failed to load settings: {error}"))
+    .map_err(|error| format!("failed to sum stored media bytes: {error}"))
 }
 
-pub fn save_settings(conn: &DbConnection, payload: &SettingsPayload) -> Result<(), String> {
-  let write_mode = match payload.write_mode.trim() {
-    "cli_only" | "filesystem_only" | "cli_fallback" => payload.write_mode.trim(),
-    _ => return Err("write_mode must be cli_only, filesystem_only, or cli_fallback".to_string()),
-  };
+pub fn storage_usage_by_job(conn: &DbConnection) -> Result<Vec<JobStorageUsage>, String> {
+  let mut stmt = conn
+    .prepare(
+      "
+      SELECT
+        j.id,
+        j.title,
+        COALESCE(SUM(
+          CASE WHEN a.ingest_mode = 'reference' THEN 0 ELSE a.size_bytes END + COALESCE(a.derivative_size_bytes, 0)
+            + COALESCE((SELECT SUM(c.size_bytes) FROM media_chunk c WHERE c.asset_id = a.id), 0)
+        ), 0) AS bytes
+      FROM ingestion_job j
+      JOIN media_asset a ON a.job_id = j.id AND a.evicted_at IS NULL
+      GROUP BY j.id, j.title
+      ORDER BY bytes DESC
+      ",
+    )
+    .map_err(|error| format!("failed to prepare storage_usage_by_job query: {error}"))?;
+
+  let rows = stmt
+    .query_map([], |row| {
+      Ok(JobStorageUsage {
+        job_id: row.get(0)?,
+        title: row.get(1)?,
+        bytes: row.get(2)?,
+      })
+    })
+    .map_err(|error| format!("failed to run storage_usage_by_job query: {error}"))?;
+
+  let mut usage = Vec::new();
+  for row in rows {
+    usage.push(row.map_err(|error| format!("failed to parse storage usage row: {error}"))?);
+  }
+
+  Ok(usage)
+}
+
+pub fn storage_usage_by_month(conn: &DbConnection) -> Result<Vec<MonthlyStorageUsage>, String> {
+  let mut stmt = conn
+    .prepare(
+      "
+      SELECT
+        strftime('%Y-%m', a.created_at / 1000, 'unixepoch') AS month,
+        COALESCE(SUM(
+          CASE WHEN a.ingest_mode = 'reference' THEN 0 ELSE a.size_bytes END + COALESCE(a.derivative_size_bytes, 0)
+            + COALESCE((SELECT SUM(c.size_bytes) FROM media_chunk c WHERE c.asset_id = a.id), 0)
+        ), 0)
+      FROM media_asset a
+      WHERE a.evicted_at IS NULL
+      GROUP BY month
+      ORDER BY month DESC
+      ",
+    )
+    .map_err(|error| format!("failed to prepare storage_usage_by_month query: {error}"))?;
+
+  let rows = stmt
+    .query_map([], |row| {
+      Ok(MonthlyStorageUsage {
+        month: row.get(0)?,
+        bytes: row.get(1)?,
+      })
+    })
+    .map_err(|error| format!("failed to run storage_usage_by_month query: {error}"))?;
+
+  let mut usage = Vec::new();
+  for row in rows {
+    usage.push(row.map_err(|error| format!("failed to parse monthly storage row: {error}"))?);
+  }
+
+  Ok(usage)
+}
 
+pub fn insert_obsidian_note(
+  conn: &DbConnection,
+  job_id: &str,
+  note_path: &str,
+  method: &str,
+  now: i64,
+) -> Result<(), String> {
   conn
     .execute(
       "
-      UPDATE settings
-      SET vault_path = ?1, obsidian_cli_path = ?2, gemini_model = ?3, write_mode = ?4
-      WHERE id = 1
+      INSERT INTO obsidian_note (job_id, note_path, method, created_at)
+      VALUES (?1, ?2, ?3, ?4)
       ",
-      params![
-        payload.vault_path.trim(),
-        payload.obsidian_cli_path.trim(),
-        payload.gemini_model.trim(),
+      params![job_id, note_path, method, now],
+    )
+    .map_err(|error| format!("failed to record published note: {error}"))?;
+  Ok(())
+}
+
+/// Stores an extraction and indexes its topics as keywords; the FTS trigger indexes `normalized_output`.
+pub fn insert_extraction_result(
+  conn: &mut DbConnection,
+  job_id: &str,
+  model: &str,
+  cache_key: &str,
+  raw_output: &str,
+  extraction: &Extraction,
+  now: i64,
+) -> Result<ExtractionResult, String> {
+  let normalized_output = serde_json::to_string(extraction)
+    .map_err(|error| format!("failed to encode extraction: {error}"))?;
+  let tx = conn
+    .transaction()
+    .map_err(|error| format!("failed to start extraction transaction: {error}"))?;
+  tx.execute(
+    "
+    INSERT INTO extraction_result (job_id, model, raw_output, normalized_output, cache_key, created_at)
+    VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+    ",
+    params![job_id, model, raw_output, normalized_output, cache_key, now],
+  )
+  .map_err(|error| format!("failed to insert extraction result: {error}"))?;
+  let extraction_id = tx.last_insert_rowid();
+
+  for topic in &extraction.topics {
+    tx.execute("INSERT OR IGNORE INTO keyword (value) VALUES (?1)", params![topic])
+      .map_err(|error| format!("failed to insert keyword: {error}"))?;
+    tx.execute(
+      "
+      INSERT OR IGNORE INTO extraction_keyword (extraction_id, keyword_id)
+      SELECT ?1, id FROM keyword WHERE value = ?2
+      ",
+      params![extraction_id, topic],
+    )
+    .map_err(|error| format!("failed to link keyword: {error}"))?;
+  }
+
+  tx.commit()
+    .map_err(|error| format!("failed to commit extraction result: {error}"))?;
+  Ok(ExtractionResult {
+    id: extraction_id,
+    job_id: job_id.to_string(),
+    model: model.to_string(),
+    extraction: extraction.clone(),
+    created_at: now,
+  })
+}
+
+/// Looks up a stored extraction for `cache_key`, preferring one recorded for `job_id` itself. Returns the
+/// raw output too so a hit from another job can be copied over.
+pub fn find_cached_extraction(
+  conn: &DbConnection,
+  job_id: &str,
+  cache_key: &str,
+) -> Result<Option<(ExtractionResult, String)>, String> {
+  let row = conn
+    .query_row(
+      "
+      SELECT id, job_id, model, raw_output, normalized_output, created_at
+      FROM extraction_result
+      WHERE cache_key = ?2
+      ORDER BY job_id = ?1 DESC, created_at DESC, id DESC
+      LIMIT 1
+      ",
+      params![job_id, cache_key],
+      |row| {
+        Ok((
+          row.get::<_, i64>(0)?,
+          row.get::<_, String>(1)?,
+          row.get::<_, String>(2)?,
+          row.get::<_, String>(3)?,
+          row.get::<_, String>(4)?,
+          row.get::<_, i64>(5)?,
+        ))
+      },
+    )
+    .optional()
+    .map_err(|error| format!("failed to load extraction result: {error}"))?;
+
+  row
+    .map(|(id, job_id, model, raw_output, normalized_output, created_at)| {
+      let extraction = serde_json::from_str(&normalized_output)
+        .map_err(|error| format!("stored extraction {id} is not valid JSON: {error}"))?;
+      let result = ExtractionResult {
+        id,
+        job_id,
+        model,
+        extraction,
+        created_at,
+      };
+      Ok((result, raw_output))
+    })
+    .transpose()
+}
+
+pub fn update_job_status(conn: &DbConnection, job_id: &str, next_status: &str, now: i64) -> Result<bool, String> {
+  let current_status = conn
+    .query_row(
+      "SELECT status FROM ingestion_job WHERE id = ?1",
+      [job_id],
+      |row| row.get::<_, String>(0),
+    )
+    .optional()
+    .map_err(|error| format!("failed to load current job status: {error}"))?;
+
+  let Some(current_status) = current_status else {
+    return Ok(false);
+  };
+
+  if !can_transition(&current_status, next_status) {
+    return Err(format!("invalid status transition: {current_status} -> {next_status}"));
+  }
+
+  // Whoever puts a job back in the queue by hand or after a budget wait gives it a fresh set of attempts,
+  // and a job queued again by hand is left to the runner even if it predates it.
+  let changed = conn
+    .execute(
+      "
+      UPDATE ingestion_job
+      SET status = ?1, updated_at = ?2,
+        attempt_count = CASE WHEN ?1 = 'queued' THEN 0 ELSE attempt_count END,
+        next_attempt_at = CASE WHEN ?1 = 'queued' THEN NULL ELSE next_attempt_at END,
+        auto_extract = CASE WHEN ?1 = 'queued' THEN 1 ELSE auto_extract END
+      WHERE id = ?3
+      ",
+      params![next_status, now, job_id],
+    )
+    .map_err(|error| format!("failed to update job status: {error}"))?;
+
+  Ok(changed > 0)
+}
+
+/// Ids of jobs in `status`, oldest first.
+pub fn list_job_ids_with_status(conn: &DbConnection, status: &str) -> Result<Vec<String>, String> {
+  let mut stmt = conn
+    .prepare("SELECT id FROM ingestion_job WHERE status = ?1 ORDER BY created_at, id")
+    .map_err(|error| format!("failed to prepare list_job_ids_with_status query: {error}"))?;
+
+  let rows = stmt
+    .query_map([status], |row| row.get::<_, String>(0))
+    .map_err(|error| format!("failed to run list_job_ids_with_status query: {error}"))?;
+
+  let mut ids = Vec::new();
+  for row in rows {
+    ids.push(row.map_err(|error| format!("failed to parse job id row: {error}"))?);
+  }
+
+  Ok(ids)
+}
+
+/// Ids of queued jobs whose retry delay, if any, has passed, oldest first.
+pub fn list_ready_job_ids(conn: &DbConnection, now: i64) -> Result<Vec<String>, String> {
+  let mut stmt = conn
+    .prepare(
+      "
+      SELECT id FROM ingestion_job
+      WHERE status = 'queued' AND auto_extract = 1 AND (next_attempt_at IS NULL OR next_attempt_at <= ?1)
+      ORDER BY created_at, id
+      ",
+    )
+    .map_err(|error| format!("failed to prepare list_ready_job_ids query: {error}"))?;
+
+  let rows = stmt
+    .query_map([now], |row| row.get::<_, String>(0))
+    .map_err(|error| format!("failed to run list_ready_job_ids query: {error}"))?;
+
+  let mut ids = Vec::new();
+  for row in rows {
+    ids.push(row.map_err(|error| format!("failed to parse job id row: {error}"))?);
+  }
+
+  Ok(ids)
+}
+
+/// Sends a `processing` job back to the queue, not to be picked up before `next_attempt_at`. Returns
+/// `false`, leaving the job alone, once it has used `max_attempts` retries.
+pub fn schedule_job_retry(
+  conn: &DbConnection,
+  job_id: &str,
+  max_attempts: i64,
+  next_attempt_at: i64,
+  now: i64,
+) -> Result<bool, String> {
+  let changed = conn
+    .execute(
+      "
+      UPDATE ingestion_job
+      SET status = 'queued', attempt_count = attempt_count + 1, next_attempt_at = ?1, updated_at = ?2
+      WHERE id = ?3 AND status = 'processing' AND attempt_count < ?4
+      ",
+      params![next_attempt_at, now, job_id, max_attempts],
+    )
+    .map_err(|error| format!("failed to schedule job retry: {error}"))?;
+  Ok(changed > 0)
+}
+
+/// Moves a queued job to `processing` unless someone else got to it first. Returns whether this caller
+/// owns the job now.
+pub fn claim_queued_job(conn: &DbConnection, job_id: &str, now: i64) -> Result<bool, String> {
+  conn
+    .execute(
+      "
+      UPDATE ingestion_job
+      SET status = 'processing', claimed_from_status = NULL, updated_at = ?1
+      WHERE id = ?2 AND status = 'queued'
+      ",
+      params![now, job_id],
+    )
+    .map(|changed| changed > 0)
+    .map_err(|error| format!("failed to claim job: {error}"))
+}
+
+/// Marks a job of any other status as `processing` for an extraction started from the UI, remembering the status
+/// it had. Returns that status to restore with [`release_claimed_job`], or `None` when the job is already being
+/// processed.
+pub fn claim_job_for_extraction(conn: &DbConnection, job_id: &str, now: i64) -> Result<Option<String>, String> {
+  let current_status = conn
+    .query_row("SELECT status FROM ingestion_job WHERE id = ?1", [job_id], |row| {
+      row.get::<_, String>(0)
+    })
+    .optional()
+    .map_err(|error| format!("failed to load current job status: {error}"))?
+    .ok_or_else(|| format!("job {job_id} not found"))?;
+  if current_status == "processing" {
+    return Ok(None);
+  }
+
+  // Guarded on the status just read, so a concurrent claim in between makes this one lose.
+  let changed = conn
+    .execute(
+      "
+      UPDATE ingestion_job
+      SET status = 'processing', claimed_from_status = ?3, updated_at = ?1
+      WHERE id = ?2 AND status = ?3
+      ",
+      params![now, job_id, current_status],
+    )
+    .map_err(|error| format!("failed to claim job: {error}"))?;
+  Ok((changed > 0).then_some(current_status))
+}
+
+/// Puts back the status a job had before [`claim_job_for_extraction`], unless it was changed meanwhile.
+pub fn release_claimed_job(conn: &DbConnection, job_id: &str, previous_status: &str, now: i64) -> Result<(), String> {
+  conn
+    .execute(
+      "
+      UPDATE ingestion_job
+      SET status = ?1, claimed_from_status = NULL, updated_at = ?2
+      WHERE id = ?3 AND status = 'processing'
+      ",
+      params![previous_status, now, job_id],
+    )
+    .map_err(|error| format!("failed to release job: {error}"))?;
+  Ok(())
+}
+
+/// Puts jobs left `processing` by an interrupted run back in the queue, or back to the status a UI extraction
+/// claimed them from. Returns how many were moved.
+pub fn requeue_interrupted_jobs(conn: &DbConnection, now: i64) -> Result<usize, String> {
+  conn
+    .execute(
+      "
+      UPDATE ingestion_job
+      SET status = COALESCE(claimed_from_status, 'queued'), claimed_from_status = NULL, updated_at = ?1
+      WHERE status = 'processing'
+      ",
+      [now],
+    )
+    .map_err(|error| format!("failed to requeue interrupted jobs: {error}"))
+}
+
+pub fn insert_job_event(conn: &DbConnection, job_id: &str, kind: &str, message: &str, now: i64) -> Result<(), String> {
+  conn
+    .execute(
+      "INSERT INTO job_event (job_id, kind, message, created_at) VALUES (?1, ?2, ?3, ?4)",
+      params![job_id, kind, message, now],
+    )
+    .map_err(|error| format!("failed to record job event: {error}"))?;
+  Ok(())
+}
+
+pub fn list_job_events(conn: &DbConnection, job_id: &str) -> Result<Vec<JobEvent>, String> {
+  let mut stmt = conn
+    .prepare(
+      "
+      SELECT id, job_id, kind, message, created_at
+      FROM job_event
+      WHERE job_id = ?1
+      ORDER BY created_at DESC, id DESC
+      ",
+    )
+    .map_err(|error| format!("failed to prepare list_job_events query: {error}"))?;
+
+  let rows = stmt
+    .query_map([job_id], |row| {
+      Ok(JobEvent {
+        id: row.get(0)?,
+        job_id: row.get(1)?,
+        kind: row.get(2)?,
+        message: row.get(3)?,
+        created_at: row.get(4)?,
+      })
+    })
+    .map_err(|error| format!("failed to run list_job_events query: {error}"))?;
+
+  let mut events = Vec::new();
+  for row in rows {
+    events.push(row.map_err(|error| format!("failed to parse job event row: {error}"))?);
+  }
+
+  Ok(events)
+}
+
+/// A corrupt value falls back to no roots, i.e. dialog-selected files only.
+fn decode_source_roots(value: &str) -> Vec<String> {
+  serde_json::from_str(value).unwrap_or_default()
+}
+
+fn stored_source_roots(conn: &DbConnection) -> Result<Vec<String>, String> {
+  conn
+    .query_row("SELECT allowed_source_roots FROM settings WHERE id = 1", [], |row| {
+      row.get::<_, String>(0)
+    })
+    .map(|value| decode_source_roots(&value))
+    .map_err(|error| format!("failed to load allowed source folders: {error}"))
+}
+
+/// The webview may drop folders from the allowlist but never add one; new folders only come in through
+/// [`add_allowed_source_root`] with a path from the native folder picker.
+fn kept_source_roots(conn: &DbConnection, roots: &[String]) -> Result<Vec<String>, String> {
+  let stored = stored_source_roots(conn)?;
+  let mut kept = Vec::new();
+  for root in roots.iter().map(|root| root.trim()).filter(|root| !root.is_empty()) {
+    if !stored.iter().any(|stored_root| stored_root == root) {
+      return Err(format!("allowed source folder '{root}' can only be added through the folder picker"));
+    }
+    if !kept.iter().any(|kept_root| kept_root == root) {
+      kept.push(root.to_string());
+    }
+  }
+  Ok(kept)
+}
+
+/// Once a key check has listed the models, a newly chosen Gemini model must be one of them. The saved model is
+/// accepted even after a later listing dropped it, so other settings can still be changed.
+fn validate_gemini_model(conn: &DbConnection, model: &str) -> Result<(), String> {
+  if model.is_empty() {
+    return Err("gemini_model must not be empty".to_string());
+  }
+  let current_model = conn
+    .query_row("SELECT gemini_model FROM settings WHERE id = 1", [], |row| row.get::<_, String>(0))
+    .map_err(|error| format!("failed to load current Gemini model: {error}"))?;
+  if model == current_model {
+    return Ok(());
+  }
+  let models = list_gemini_models(conn)?;
+  if models.is_empty() || models.iter().any(|listed| listed.id == model) {
+    return Ok(());
+  }
+  Err(format!("gemini_model '{model}' is not available to the configured API key"))
+}
+
+/// Allowlists a folder the user picked in the native dialog. Returns its canonical form.
+pub fn add_allowed_source_root(conn: &DbConnection, root: &str) -> Result<String, String> {
+  let root = policy::normalize_roots(&[root.to_string()])?
+    .pop()
+    .ok_or_else(|| "allowed source folder must not be empty".to_string())?;
+  let mut roots = stored_source_roots(conn)?;
+  if !roots.contains(&root) {
+    roots.push(root.clone());
+  }
+  let encoded =
+    serde_json::to_string(&roots).map_err(|error| format!("failed to encode allowed source folders: {error}"))?;
+  conn
+    .execute("UPDATE settings SET allowed_source_roots = ?1 WHERE id = 1", [encoded])
+    .map_err(|error| format!("failed to save allowed source folders: {error}"))?;
+  Ok(root)
+}
+
+pub fn get_settings(conn: &DbConnection) -> Result<SettingsPayload, String> {
+  conn
+    .query_row(
+      "
+      SELECT vault_path, obsidian_cli_path, gemini_model, write_mode, media_quota_bytes, media_retention_days,
+        ingest_mode, encrypt_media, image_derivatives_enabled, image_max_dimension, image_jpeg_quality,
+        allowed_source_roots, prompt_template_key, note_language, llm_provider, openai_base_url, openai_model,
+        gemini_base_url, daily_token_budget, monthly_token_budget, daily_cost_budget_usd, monthly_cost_budget_usd,
+        gemini_requests_per_minute
+      FROM settings
+      WHERE id = 1
+      ",
+      [],
+      |row| {
+        Ok(SettingsPayload {
+          vault_path: row.get(0)?,
+          obsidian_cli_path: row.get(1)?,
+          gemini_model: row.get(2)?,
+          write_mode: row.get(3)?,
+          media_quota_bytes: row.get(4)?,
+          media_retention_days: row.get(5)?,
+          ingest_mode: row.get(6)?,
+          encrypt_media: row.get(7)?,
+          image_derivatives_enabled: row.get(8)?,
+          image_max_dimension: row.get(9)?,
+          image_jpeg_quality: row.get(10)?,
+          allowed_source_roots: decode_source_roots(&row.get::<_, String>(11)?),
+          prompt_template_key: row.get(12)?,
+          note_language: row.get(13)?,
+          llm_provider: row.get(14)?,
+          openai_base_url: row.get(15)?,
+          openai_model: row.get(16)?,
+          gemini_base_url: row.get(17)?,
+          daily_token_budget: row.get(18)?,
+          monthly_token_budget: row.get(19)?,
+          daily_cost_budget_usd: row.get(20)?,
+          monthly_cost_budget_usd: row.get(21)?,
+          gemini_requests_per_minute: row.get(22)?,
+        })
+      },
+    )
+    .map_err(|error| format!("failed to load settings: {error}"))
+}
+
+fn normalize_base_url<'a>(field: &str, value: &'a str) -> Result<&'a str, String> {
+  let value = value.trim().trim_end_matches('/');
+  if !value.starts_with("http://") && !value.starts_with("https://") {
+    return Err(format!("{field} must start with http:// or https://"));
+  }
+  Ok(value)
+}
+
+pub fn save_settings(conn: &DbConnection, payload: &SettingsPayload) -> Result<(), String> {
+  let write_mode = match payload.write_mode.trim() {
+    "cli_only" | "filesystem_only" | "cli_fallback" => payload.write_mode.trim(),
+    _ => return Err("write_mode must be cli_only, filesystem_only, or cli_fallback".to_string()),
+  };
+  let ingest_mode = match payload.ingest_mode.trim() {
+    "copy" | "move" | "reference" => payload.ingest_mode.trim(),
+    _ => return Err("ingest_mode must be copy, move, or reference".to_string()),
+  };
+  if payload.media_quota_bytes < 0 {
+    return Err("media_quota_bytes must be zero (unlimited) or positive".to_string());
+  }
+  if payload.media_retention_days < 0 {
+    return Err("media_retention_days must be zero (disabled) or positive".to_string());
+  }
+  if !(256..=8192).contains(&payload.image_max_dimension) {
+    return Err("image_max_dimension must be between 256 and 8192".to_string());
+  }
+  if !(1..=100).contains(&payload.image_jpeg_quality) {
+    return Err("image_jpeg_quality must be between 1 and 100".to_string());
+  }
+  let note_language = language::normalize(&payload.note_language)?;
+  let prompt_template_key = payload.prompt_template_key.trim();
+  if find_prompt_template(conn, prompt_template_key)?.is_none() {
+    return Err(format!("prompt template '{prompt_template_key}' does not exist"));
+  }
+  let allowed_source_roots = serde_json::to_string(&kept_source_roots(conn, &payload.allowed_source_roots)?)
+    .map_err(|error| format!("failed to encode allowed source folders: {error}"))?;
+  if payload.daily_token_budget < 0 || payload.monthly_token_budget < 0 {
+    return Err("token budgets must be zero (unlimited) or positive".to_string());
+  }
+  let cost_budgets = [payload.daily_cost_budget_usd, payload.monthly_cost_budget_usd];
+  if cost_budgets.iter().any(|budget| !budget.is_finite() || *budget < 0.0) {
+    return Err("cost budgets must be zero (unlimited) or positive".to_string());
+  }
+  if !(0..=10_000).contains(&payload.gemini_requests_per_minute) {
+    return Err("gemini_requests_per_minute must be between 0 (unlimited) and 10000".to_string());
+  }
+  let llm_provider = llm::validate_provider(&payload.llm_provider)?;
+  let openai_base_url = normalize_base_url("openai_base_url", &payload.openai_base_url)?;
+  let gemini_base_url = normalize_base_url("gemini_base_url", &payload.gemini_base_url)?;
+  validate_gemini_model(conn, payload.gemini_model.trim())?;
+
+  conn
+    .execute(
+      "
+      UPDATE settings
+      SET vault_path = ?1, obsidian_cli_path = ?2, gemini_model = ?3, write_mode = ?4,
+        media_quota_bytes = ?5, media_retention_days = ?6, ingest_mode = ?7,
+        encrypt_media = ?8, image_derivatives_enabled = ?9, image_max_dimension = ?10, image_jpeg_quality = ?11,
+        allowed_source_roots = ?12, prompt_template_key = ?13,
+        note_language = ?14, llm_provider = ?15, openai_base_url = ?16, openai_model = ?17,
+        gemini_base_url = ?18, daily_token_budget = ?19, monthly_token_budget = ?20,
+        daily_cost_budget_usd = ?21, monthly_cost_budget_usd = ?22, gemini_requests_per_minute = ?23
+      WHERE id = 1
+      ",
+      params![
+        payload.vault_path.trim(),
+        payload.obsidian_cli_path.trim(),
+        payload.gemini_model.trim(),
         write_mode,
+        payload.media_quota_bytes,
+        payload.media_retention_days,
+        ingest_mode,
+        payload.encrypt_media,
+        payload.image_derivatives_enabled,
+        payload.image_max_dimension,
+        payload.image_jpeg_quality,
+        allowed_source_roots,
+        prompt_template_key,
+        note_language,
+        llm_provider,
+        openai_base_url,
+        payload.openai_model.trim(),
+        gemini_base_url,
+        payload.daily_token_budget,
+        payload.monthly_token_budget,
+        payload.daily_cost_budget_usd,
+        payload.monthly_cost_budget_usd,
+        payload.gemini_requests_per_minute,
       ],
     )
     .map_err(|error| format!("failed to save settings: {error}"))?;
   Ok(())
 }
+
+fn prompt_template_from_row(row: &Row<'_>) -> rusqlite::Result<PromptTemplate> {
+  Ok(PromptTemplate {
+    id: row.get(0)?,
+    key: row.get(1)?,
+    content: row.get(2)?,
+    updated_at: row.get(3)?,
+  })
+}
+
+/// Inserts the built-in templates that are missing; existing rows, including edited defaults, are kept.
+pub fn seed_prompt_templates(conn: &DbConnection, now: i64) -> Result<(), String> {
+  for (key, content) in prompt::SEEDED_TEMPLATES {
+    conn
+      .execute(
+        "INSERT OR IGNORE INTO prompt_template (key, content, updated_at) VALUES (?1, ?2, ?3)",
+        params![key, content, now],
+      )
+      .map_err(|error| format!("failed to seed prompt template '{key}': {error}"))?;
+  }
+  Ok(())
+}
+
+pub fn list_prompt_templates(conn: &DbConnection) -> Result<Vec<PromptTemplate>, String> {
+  let mut stmt = conn
+    .prepare("SELECT id, key, content, updated_at FROM prompt_template ORDER BY key ASC")
+    .map_err(|error| format!("failed to prepare list_prompt_templates query: {error}"))?;
+
+  let rows = stmt
+    .query_map([], prompt_template_from_row)
+    .map_err(|error| format!("failed to run list_prompt_templates query: {error}"))?;
+
+  let mut templates = Vec::new();
+  for row in rows {
+    templates.push(row.map_err(|error| format!("failed to parse prompt template row: {error}"))?);
+  }
+
+  Ok(templates)
+}
+
+pub fn find_prompt_template(conn: &DbConnection, key: &str) -> Result<Option<PromptTemplate>, String> {
+  conn
+    .query_row(
+      "SELECT id, key, content, updated_at FROM prompt_template WHERE key = ?1",
+      params![key],
+      prompt_template_from_row,
+    )
+    .optional()
+    .map_err(|error| format!("failed to load prompt template: {error}"))
+}
+
+/// Creates or replaces the template named `key`.
+pub fn save_prompt_template(conn: &DbConnection, key: &str, content: &str, now: i64) -> Result<PromptTemplate, String> {
+  let key = key.trim();
+  if key.is_empty()
+    || key.len() > 64
+    || !key
+      .chars()
+      .all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || matches!(ch, '_' | '-'))
+  {
+    return Err("prompt template key must be 1-64 characters of a-z, 0-9, '_' or '-'".to_string());
+  }
+  prompt::validate(content)?;
+
+  conn
+    .execute(
+      "
+      INSERT INTO prompt_template (key, content, updated_at)
+      VALUES (?1, ?2, ?3)
+      ON CONFLICT(key) DO UPDATE SET content = excluded.content, updated_at = excluded.updated_at
+      ",
+      params![key, content, now],
+    )
+    .map_err(|error| format!("failed to save prompt template: {error}"))?;
+  find_prompt_template(conn, key)?.ok_or_else(|| format!("prompt template '{key}' was not saved"))
+}
+
+/// Deletes a template; settings that selected it fall back to the default template.
+pub fn delete_prompt_template(conn: &DbConnection, key: &str) -> Result<bool, String> {
+  if key == prompt::DEFAULT_TEMPLATE_KEY {
+    return Err("the default prompt template cannot be deleted".to_string());
+  }
+  conn
+    .execute(
+      "UPDATE settings SET prompt_template_key = ?1 WHERE prompt_template_key = ?2",
+      params![prompt::DEFAULT_TEMPLATE_KEY, key],
+    )
+    .map_err(|error| format!("failed to reset prompt template selection: {error}"))?;
+  let changed = conn
+    .execute("DELETE FROM prompt_template WHERE key = ?1", params![key])
+    .map_err(|error| format!("failed to delete prompt template: {error}"))?;
+  Ok(changed > 0)
+}
+
+/// Records a model call whether it succeeded or not; `prompt_body` holds the full request JSON.
+pub fn insert_prompt_run(
+  conn: &DbConnection,
+  job_id: &str,
+  prompt_template_key: &str,
+  model: &str,
+  call: &ModelCall,
+  now: i64,
+) -> Result<i64, String> {
+  let cost_usd = find_model_price(conn, model)?.and_then(|price| call.usage.estimate_cost_usd(&price));
+  conn
+    .execute(
+      "
+      INSERT INTO prompt_run (
+        job_id, prompt_template_key, prompt_body, response_body, created_at, model, latency_ms,
+        prompt_tokens, response_tokens, total_tokens, error, cached_tokens, cost_usd
+      )
+      VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+      ",
+      params![
+        job_id,
+        prompt_template_key,
+        call.request_body,
+        call.response_body,
+        now,
+        model,
+        call.latency_ms,
+        call.usage.prompt_tokens,
+        call.usage.response_tokens,
+        call.usage.total_tokens,
+        call.outcome.as_ref().err().map(ToString::to_string),
+        call.usage.cached_tokens,
+        cost_usd,
+      ],
+    )
+    .map_err(|error| format!("failed to record prompt run: {error}"))?;
+  Ok(conn.last_insert_rowid())
+}
+
+pub fn list_prompt_runs(conn: &DbConnection, job_id: &str) -> Result<Vec<PromptRun>, String> {
+  let mut stmt = conn
+    .prepare(
+      "
+      SELECT id, job_id, prompt_template_key, model, prompt_body, response_body, latency_ms, prompt_tokens,
+        response_tokens, total_tokens, error, created_at, cached_tokens, cost_usd
+      FROM prompt_run
+      WHERE job_id = ?1
+      ORDER BY created_at DESC, id DESC
+      ",
+    )
+    .map_err(|error| format!("failed to prepare list_prompt_runs query: {error}"))?;
+
+  let rows = stmt
+    .query_map([job_id], |row| {
+      Ok(PromptRun {
+        id: row.get(0)?,
+        job_id: row.get(1)?,
+        prompt_template_key: row.get(2)?,
+        model: row.get(3)?,
+        prompt_body: row.get(4)?,
+        response_body: row.get(5)?,
+        latency_ms: row.get(6)?,
+        prompt_tokens: row.get(7)?,
+        response_tokens: row.get(8)?,
+        total_tokens: row.get(9)?,
+        error: row.get(10)?,
+        created_at: row.get(11)?,
+        cached_tokens: row.get(12)?,
+        cost_usd: row.get(13)?,
+      })
+    })
+    .map_err(|error| format!("failed to run list_prompt_runs query: {error}"))?;
+
+  let mut runs = Vec::new();
+  for row in rows {
+    runs.push(row.map_err(|error| format!("failed to parse prompt run row: {error}"))?);
+  }
+
+  Ok(runs)
+}
+
+fn model_price_from_row(row: &Row<'_>) -> rusqlite::Result<ModelPrice> {
+  Ok(ModelPrice {
+    model: row.get(0)?,
+    input_usd_per_mtok: row.get(1)?,
+    output_usd_per_mtok: row.get(2)?,
+    cached_input_usd_per_mtok: row.get(3)?,
+    updated_at: row.get(4)?,
+  })
+}
+
+pub fn list_model_prices(conn: &DbConnection) -> Result<Vec<ModelPrice>, String> {
+  let mut stmt = conn
+    .prepare(
+      "
+      SELECT model, input_usd_per_mtok, output_usd_per_mtok, cached_input_usd_per_mtok, updated_at
+      FROM model_price
+      ORDER BY model
+      ",
+    )
+    .map_err(|error| format!("failed to prepare list_model_prices query: {error}"))?;
+
+  let rows = stmt
+    .query_map([], model_price_from_row)
+    .map_err(|error| format!("failed to run list_model_prices query: {error}"))?;
+
+  let mut prices = Vec::new();
+  for row in rows {
+    prices.push(row.map_err(|error| format!("failed to parse model price row: {error}"))?);
+  }
+
+  Ok(prices)
+}
+
+pub fn find_model_price(conn: &DbConnection, model: &str) -> Result<Option<ModelPrice>, String> {
+  conn
+    .query_row(
+      "
+      SELECT model, input_usd_per_mtok, output_usd_per_mtok, cached_input_usd_per_mtok, updated_at
+      FROM model_price
+      WHERE model = ?1
+      ",
+      [model],
+      model_price_from_row,
+    )
+    .optional()
+    .map_err(|error| format!("failed to load model price: {error}"))
+}
+
+/// Upserts a price. Applies to calls made from now on; costs already recorded are left as estimated.
+pub fn save_model_price(conn: &DbConnection, price: &ModelPrice, now: i64) -> Result<ModelPrice, String> {
+  let model = price.model.trim();
+  if model.is_empty() {
+    return Err("model price needs a model name".to_string());
+  }
+  let rates = [price.input_usd_per_mtok, price.output_usd_per_mtok, price.cached_input_usd_per_mtok];
+  if rates.iter().any(|rate| !rate.is_finite() || *rate < 0.0) {
+    return Err("model prices must be zero or positive".to_string());
+  }
+
+  conn
+    .execute(
+      "
+      INSERT INTO model_price (model, input_usd_per_mtok, output_usd_per_mtok, cached_input_usd_per_mtok, updated_at)
+      VALUES (?1, ?2, ?3, ?4, ?5)
+      ON CONFLICT(model) DO UPDATE SET
+        input_usd_per_mtok = excluded.input_usd_per_mtok,
+        output_usd_per_mtok = excluded.output_usd_per_mtok,
+        cached_input_usd_per_mtok = excluded.cached_input_usd_per_mtok,
+        updated_at = excluded.updated_at
+      ",
+      params![model, rates[0], rates[1], rates[2], now],
+    )
+    .map_err(|error| format!("failed to save model price: {error}"))?;
+  find_model_price(conn, model)?.ok_or_else(|| "model price disappeared after saving".to_string())
+}
+
+pub fn delete_model_price(conn: &DbConnection, model: &str) -> Result<(), String> {
+  conn
+    .execute("DELETE FROM model_price WHERE model = ?1", [model.trim()])
+    .map_err(|error| format!("failed to delete model price: {error}"))?;
+  Ok(())
+}
+
+/// Aggregates shared by the token usage queries, in the order `token_totals_from_row` reads them.
+const TOKEN_TOTALS_COLUMNS: &str = "
+  COUNT(r.id), COALESCE(SUM(r.prompt_tokens), 0), COALESCE(SUM(r.response_tokens), 0),
+  COALESCE(SUM(r.cached_tokens), 0), COALESCE(SUM(r.total_tokens), 0), COALESCE(SUM(r.cost_usd), 0)
+";
+
+fn token_totals_from_row(row: &Row<'_>, offset: usize) -> rusqlite::Result<TokenTotals> {
+  Ok(TokenTotals {
+    calls: row.get(offset)?,
+    prompt_tokens: row.get(offset + 1)?,
+    response_tokens: row.get(offset + 2)?,
+    cached_tokens: row.get(offset + 3)?,
+    total_tokens: row.get(offset + 4)?,
+    cost_usd: row.get(offset + 5)?,
+  })
+}
+
+pub fn token_usage_totals(conn: &DbConnection) -> Result<TokenTotals, String> {
+  conn
+    .query_row(&format!("SELECT {TOKEN_TOTALS_COLUMNS} FROM prompt_run r"), [], |row| {
+      token_totals_from_row(row, 0)
+    })
+    .map_err(|error| format!("failed to load token usage totals: {error}"))
+}
+
+/// Totals over calls made at or after `since` (ms).
+pub fn token_usage_since(conn: &DbConnection, since: i64) -> Result<TokenTotals, String> {
+  conn
+    .query_row(
+      &format!("SELECT {TOKEN_TOTALS_COLUMNS} FROM prompt_run r WHERE r.created_at >= ?1"),
+      [since],
+      |row| token_totals_from_row(row, 0),
+    )
+    .map_err(|error| format!("failed to load token usage for budget window: {error}"))
+}
+
+pub fn token_usage_by_job(conn: &DbConnection) -> Result<Vec<JobTokenUsage>, String> {
+  let mut stmt = conn
+    .prepare(&format!(
+      "
+      SELECT j.id, j.title, {TOKEN_TOTALS_COLUMNS}
+      FROM ingestion_job j
+      JOIN prompt_run r ON r.job_id = j.id
+      GROUP BY j.id, j.title
+      ORDER BY COALESCE(SUM(r.cost_usd), 0) DESC, MAX(r.created_at) DESC
+      "
+    ))
+    .map_err(|error| format!("failed to prepare token_usage_by_job query: {error}"))?;
+
+  let rows = stmt
+    .query_map([], |row| {
+      Ok(JobTokenUsage {
+        job_id: row.get(0)?,
+        title: row.get(1)?,
+        totals: token_totals_from_row(row, 2)?,
+      })
+    })
+    .map_err(|error| format!("failed to run token_usage_by_job query: {error}"))?;
+
+  let mut usage = Vec::new();
+  for row in rows {
+    usage.push(row.map_err(|error| format!("failed to parse job token usage row: {error}"))?);
+  }
+
+  Ok(usage)
+}
+
+pub fn token_usage_by_month(conn: &DbConnection) -> Result<Vec<MonthlyTokenUsage>, String> {
+  let mut stmt = conn
+    .prepare(&format!(
+      "
+      SELECT strftime('%Y-%m', r.created_at / 1000, 'unixepoch') AS month, {TOKEN_TOTALS_COLUMNS}
+      FROM prompt_run r
+      GROUP BY month
+      ORDER BY month DESC
+      "
+    ))
+    .map_err(|error| format!("failed to prepare token_usage_by_month query: {error}"))?;
+
+  let rows = stmt
+    .query_map([], |row| {
+      Ok(MonthlyTokenUsage {
+        month: row.get(0)?,
+        totals: token_totals_from_row(row, 1)?,
+      })
+    })
+    .map_err(|error| format!("failed to run token_usage_by_month query: {error}"))?;
+
+  let mut usage = Vec::new();
+  for row in rows {
+    usage.push(row.map_err(|error| format!("failed to parse monthly token usage row: {error}"))?);
+  }
+
+  Ok(usage)
+}
+
+pub fn save_key_validation(conn: &DbConnection, provider: &str, validation: &KeyValidation) -> Result<(), String> {
+  conn
+    .execute(
+      "
+      INSERT INTO api_key_validation (provider, ok, message, validated_at)
+      VALUES (?1, ?2, ?3, ?4)
+      ON CONFLICT(provider) DO UPDATE SET
+        ok = excluded.ok,
+        message = excluded.message,
+        validated_at = excluded.validated_at
+      ",
+      params![provider, validation.ok, validation.message, validation.validated_at],
+    )
+    .map_err(|error| format!("failed to record key validation: {error}"))?;
+  Ok(())
+}
+
+pub fn find_key_validation(conn: &DbConnection, provider: &str) -> Result<Option<KeyValidation>, String> {
+  conn
+    .query_row(
+      "SELECT ok, message, validated_at FROM api_key_validation WHERE provider = ?1",
+      [provider],
+      |row| {
+        Ok(KeyValidation {
+          ok: row.get(0)?,
+          message: row.get(1)?,
+          validated_at: row.get(2)?,
+        })
+      },
+    )
+    .optional()
+    .map_err(|error| format!("failed to load key validation: {error}"))
+}
+
+/// Replaces the stored Gemini model list with the one just fetched for the current key.
+pub fn replace_gemini_models(conn: &mut DbConnection, models: &[GeminiModelInfo], now: i64) -> Result<(), String> {
+  let tx = conn
+    .transaction()
+    .map_err(|error| format!("failed to start replace_gemini_models transaction: {error}"))?;
+
+  tx
+    .execute("DELETE FROM gemini_model", [])
+    .map_err(|error| format!("failed to clear gemini models: {error}"))?;
+  for model in models {
+    let assumed_input_modalities = serde_json::to_string(&model.assumed_input_modalities)
+      .map_err(|error| format!("failed to encode input modalities: {error}"))?;
+    tx
+      .execute(
+        "
+        INSERT OR REPLACE INTO gemini_model (
+          id, display_name, description, input_token_limit, output_token_limit, assumed_input_modalities, fetched_at
+        )
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        ",
+        params![
+          model.id,
+          model.display_name,
+          model.description,
+          model.input_token_limit,
+          model.output_token_limit,
+          assumed_input_modalities,
+          now,
+        ],
+      )
+      .map_err(|error| format!("failed to store gemini model: {error}"))?;
+  }
+
+  tx
+    .commit()
+    .map_err(|error| format!("failed to commit replace_gemini_models transaction: {error}"))
+}
+
+pub fn list_gemini_models(conn: &DbConnection) -> Result<Vec<GeminiModelInfo>, String> {
+  let mut stmt = conn
+    .prepare(
+      "
+      SELECT id, display_name, description, input_token_limit, output_token_limit, assumed_input_modalities
+      FROM gemini_model
+      ORDER BY id
+      ",
+    )
+    .map_err(|error| format!("failed to prepare list_gemini_models query: {error}"))?;
+
+  let rows = stmt
+    .query_map([], |row| {
+      Ok(GeminiModelInfo {
+        id: row.get(0)?,
+        display_name: row.get(1)?,
+        description: row.get(2)?,
+        input_token_limit: row.get(3)?,
+        output_token_limit: row.get(4)?,
+        assumed_input_modalities: serde_json::from_str(&row.get::<_, String>(5)?).unwrap_or_default(),
+      })
+    })
+    .map_err(|error| format!("failed to run list_gemini_models query: {error}"))?;
+
+  let mut models = Vec::new();
+  for row in rows {
+    models.push(row.map_err(|error| format!("failed to parse gemini model row: {error}"))?);
+  }
+
+  Ok(models)
+}
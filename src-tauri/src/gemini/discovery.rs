@@ -0,0 +1,99 @@
+use serde::Deserialize;
+
+use super::{error, GeminiClient, API_KEY_HEADER};
+use crate::{
+  llm::{ModelError, ModelErrorKind},
+  models::GeminiModelInfo,
+};
+
+/// Stop following `nextPageToken` after this many pages; the catalogue is far smaller.
+const MAX_PAGES: usize = 20;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiModel {
+  name: String,
+  display_name: Option<String>,
+  description: Option<String>,
+  input_token_limit: Option<i64>,
+  output_token_limit: Option<i64>,
+  #[serde(default)]
+  supported_generation_methods: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModelPage {
+  #[serde(default)]
+  models: Vec<ApiModel>,
+  next_page_token: Option<String>,
+}
+
+/// A name-based guess, since `models.list` does not report input modalities: Gemini models take text, images,
+/// audio and video; Gemma takes text and images; anything else is assumed to be text only.
+fn assumed_input_modalities(id: &str) -> Vec<String> {
+  let modalities: &[&str] = if id.starts_with("gemini-") {
+    &["text", "image", "audio", "video"]
+  } else if id.starts_with("gemma-") {
+    &["text", "image"]
+  } else {
+    &["text"]
+  };
+  modalities.iter().map(|modality| modality.to_string()).collect()
+}
+
+impl GeminiClient {
+  /// Models the key may call `generateContent` on. Being an authenticated request that costs no tokens, this
+  /// doubles as the key check.
+  pub fn list_models(&self, api_key: &str) -> Result<Vec<GeminiModelInfo>, ModelError> {
+    let mut models = Vec::new();
+    let mut page_token: Option<String> = None;
+    for _ in 0..MAX_PAGES {
+      let mut request = self
+        .http
+        .get(format!("{}/v1beta/models", self.base_url))
+        .header(API_KEY_HEADER, api_key.trim())
+        .query(&[("pageSize", "1000")]);
+      if let Some(token) = page_token.as_deref() {
+        request = request.query(&[("pageToken", token)]);
+      }
+      let response = request.send().map_err(|error| {
+        ModelError::new(ModelErrorKind::Network, format!("failed to list Gemini models: {}", error.without_url()))
+      })?;
+      let status = response.status();
+      let retry_after = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+      let body = response.text().unwrap_or_default();
+      if !status.is_success() {
+        return Err(error::from_response(status, retry_after.as_deref(), &body));
+      }
+
+      let page = serde_json::from_str::<ModelPage>(&body).map_err(|error| {
+        ModelError::new(ModelErrorKind::MalformedResponse, format!("failed to parse Gemini model list: {error}"))
+      })?;
+      for model in page.models {
+        if !model.supported_generation_methods.iter().any(|method| method == "generateContent") {
+          continue;
+        }
+        let id = model.name.strip_prefix("models/").unwrap_or(&model.name).to_string();
+        models.push(GeminiModelInfo {
+          display_name: model.display_name.unwrap_or_else(|| id.clone()),
+          description: model.description,
+          input_token_limit: model.input_token_limit,
+          output_token_limit: model.output_token_limit,
+          assumed_input_modalities: assumed_input_modalities(&id),
+          id,
+        });
+      }
+      match page.next_page_token.filter(|token| !token.is_empty()) {
+        Some(token) => page_token = Some(token),
+        None => break,
+      }
+    }
+    models.sort_by(|left, right| left.id.cmp(&right.id));
+    Ok(models)
+  }
+}
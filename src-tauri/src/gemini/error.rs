@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::llm::{parse_retry_after, ModelError, ModelErrorKind, MAX_RETRY_AFTER};
+
+/// Finish reasons for which Gemini withholds the answer on policy grounds.
+const BLOCKING_FINISH_REASONS: &[&str] =
+  &["SAFETY", "PROHIBITED_CONTENT", "BLOCKLIST", "SPII", "RECITATION", "IMAGE_SAFETY"];
+
+/// Google's error envelope: `{"error": {"code", "message", "status", "details": [...]}}`.
+#[derive(Debug, Deserialize)]
+struct ErrorEnvelope {
+  error: ErrorBody,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorBody {
+  status: Option<String>,
+  #[serde(default)]
+  details: Vec<Value>,
+}
+
+fn detail_of_type<'a>(details: &'a [Value], type_suffix: &str) -> impl Iterator<Item = &'a Value> + 'a {
+  let type_suffix = type_suffix.to_string();
+  details.iter().filter(move |detail| {
+    detail["@type"]
+      .as_str()
+      .is_some_and(|kind| kind.ends_with(&type_suffix))
+  })
+}
+
+/// Reads a `google.rpc.RetryInfo` delay such as `"17s"` or `"0.5s"`.
+fn parse_retry_delay(value: &str) -> Option<Duration> {
+  let seconds = value.trim().strip_suffix('s')?.parse::<f64>().ok()?;
+  if !seconds.is_finite() || seconds < 0.0 {
+    return None;
+  }
+  Some(Duration::from_secs_f64(seconds.min(MAX_RETRY_AFTER.as_secs_f64())))
+}
+
+/// Classifies a non-2xx `generateContent` reply. The delay comes from the `Retry-After` header or else from
+/// the body's `RetryInfo`.
+pub fn from_response(status: StatusCode, retry_after: Option<&str>, body: &str) -> ModelError {
+  let envelope = serde_json::from_str::<ErrorEnvelope>(body).ok().map(|envelope| envelope.error);
+  let details = envelope.as_ref().map(|error| error.details.as_slice()).unwrap_or_default();
+  let rpc_status = envelope.as_ref().and_then(|error| error.status.as_deref());
+
+  let invalid_key = detail_of_type(details, "ErrorInfo")
+    .any(|detail| detail["reason"].as_str().is_some_and(|reason| reason.starts_with("API_KEY_")));
+  // Quotas counted per day do not come back within the retry window of a rate limit.
+  let daily_quota = detail_of_type(details, "QuotaFailure")
+    .filter_map(|detail| detail["violations"].as_array())
+    .flatten()
+    .any(|violation| violation["quotaId"].as_str().is_some_and(|quota| quota.contains("PerDay")));
+  let kind = match (status.as_u16(), rpc_status) {
+    (_, Some("UNAUTHENTICATED" | "PERMISSION_DENIED")) => ModelErrorKind::Auth,
+    (400, _) if invalid_key => ModelErrorKind::Auth,
+    (429, _) if daily_quota => ModelErrorKind::Quota,
+    (code, _) => ModelErrorKind::from_status(code),
+  };
+
+  let retry_after = parse_retry_after(retry_after).or_else(|| {
+    detail_of_type(details, "RetryInfo")
+      .find_map(|detail| detail["retryDelay"].as_str())
+      .and_then(parse_retry_delay)
+  });
+  ModelError {
+    kind,
+    message: format!("Gemini API returned {status}: {body}"),
+    retry_after,
+  }
+}
+
+/// The reason a successful reply carries no text because the prompt or answer was blocked.
+pub fn blocked_reason(block_reason: Option<&str>, finish_reasons: &[Option<String>]) -> Option<String> {
+  if let Some(reason) = block_reason {
+    return Some(format!("Gemini blocked the prompt: {reason}"));
+  }
+  finish_reasons
+    .iter()
+    .flatten()
+    .find(|reason| BLOCKING_FINISH_REASONS.contains(&reason.as_str()))
+    .map(|reason| format!("Gemini withheld the response: {reason}"))
+}
@@ -0,0 +1,150 @@
+use std::{io::Read, thread, time::Duration};
+
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{GeminiClient, API_KEY_HEADER};
+
+/// Uploads are sent in pieces of this size; Google's resumable protocol wants multiples of 256 KiB.
+const UPLOAD_CHUNK_BYTES: usize = 8 * 1024 * 1024;
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Video processing is the slow case; give up on a file after ten minutes.
+const MAX_POLL_ATTEMPTS: u32 = 300;
+
+/// A file stored in the Gemini Files API, referenced from `generateContent` by `uri`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadedFile {
+  pub name: String,
+  pub uri: String,
+  pub mime_type: String,
+  #[serde(default)]
+  pub state: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UploadResponse {
+  file: UploadedFile,
+}
+
+fn read_chunk(reader: &mut dyn Read, buffer: &mut [u8]) -> Result<usize, String> {
+  let mut filled = 0;
+  while filled < buffer.len() {
+    match reader.read(&mut buffer[filled..]) {
+      Ok(0) => break,
+      Ok(read) => filled += read,
+      Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
+      Err(error) => return Err(format!("failed to read media for upload: {error}")),
+    }
+  }
+  Ok(filled)
+}
+
+impl GeminiClient {
+  /// Resumable upload that streams `reader` in fixed-size pieces, so encrypted media is decrypted on the
+  /// fly and its plaintext length never has to be known up front.
+  pub fn upload_file(
+    &self,
+    api_key: &str,
+    display_name: &str,
+    mime_type: &str,
+    mut reader: Box<dyn Read + Send>,
+  ) -> Result<UploadedFile, String> {
+    let start = self
+      .http
+      .post(format!("{}/upload/v1beta/files", self.base_url))
+      .header(API_KEY_HEADER, api_key.trim())
+      .header("X-Goog-Upload-Protocol", "resumable")
+      .header("X-Goog-Upload-Command", "start")
+      .header("X-Goog-Upload-Header-Content-Type", mime_type)
+      .json(&json!({ "file": { "display_name": display_name } }))
+      .send()
+      .map_err(|error| format!("failed to start Gemini file upload: {}", error.without_url()))?;
+    if !start.status().is_success() {
+      let status = start.status();
+      let body = start.text().unwrap_or_default();
+      return Err(format!("Gemini file upload returned {status}: {body}"));
+    }
+    let upload_url = start
+      .headers()
+      .get("x-goog-upload-url")
+      .and_then(|value| value.to_str().ok())
+      .map(str::to_string)
+      .ok_or_else(|| "Gemini file upload did not return an upload URL".to_string())?;
+
+    let mut buffer = vec![0_u8; UPLOAD_CHUNK_BYTES];
+    let mut offset = 0_u64;
+    loop {
+      let filled = read_chunk(reader.as_mut(), &mut buffer)?;
+      let last = filled < buffer.len();
+      let response = self
+        .http
+        .post(&upload_url)
+        .header("X-Goog-Upload-Offset", offset.to_string())
+        .header("X-Goog-Upload-Command", if last { "upload, finalize" } else { "upload" })
+        .body(buffer[..filled].to_vec())
+        .send()
+        .map_err(|error| format!("failed to upload media to Gemini: {}", error.without_url()))?;
+      if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().unwrap_or_default();
+        return Err(format!("Gemini file upload returned {status}: {body}"));
+      }
+      offset += filled as u64;
+
+      if last {
+        let payload: UploadResponse = response
+          .json()
+          .map_err(|error| format!("failed to parse Gemini file upload response: {error}"))?;
+        return Ok(payload.file);
+      }
+    }
+  }
+
+  pub fn get_file(&self, api_key: &str, name: &str) -> Result<UploadedFile, String> {
+    let response = self
+      .http
+      .get(format!("{}/v1beta/{name}", self.base_url))
+      .header(API_KEY_HEADER, api_key.trim())
+      .send()
+      .map_err(|error| format!("failed to read Gemini file state: {}", error.without_url()))?;
+    if !response.status().is_success() {
+      let status = response.status();
+      let body = response.text().unwrap_or_default();
+      return Err(format!("Gemini Files API returned {status}: {body}"));
+    }
+    response
+      .json()
+      .map_err(|error| format!("failed to parse Gemini file state: {error}"))
+  }
+
+  /// Audio and video are processed asynchronously and cannot be referenced until they are ACTIVE.
+  pub fn wait_until_active(&self, api_key: &str, file: UploadedFile) -> Result<UploadedFile, String> {
+    let mut current = file;
+    for _ in 0..MAX_POLL_ATTEMPTS {
+      match current.state.as_deref() {
+        None | Some("ACTIVE") => return Ok(current),
+        Some("FAILED") => return Err(format!("Gemini failed to process file {}", current.name)),
+        _ => {
+          thread::sleep(POLL_INTERVAL);
+          current = self.get_file(api_key, &current.name)?;
+        }
+      }
+    }
+    Err(format!("timed out waiting for Gemini to process file {}", current.name))
+  }
+
+  /// Remote files expire on their own after 48 hours; deleting them keeps media off Google's side sooner.
+  pub fn delete_file(&self, api_key: &str, name: &str) -> Result<(), String> {
+    let response = self
+      .http
+      .delete(format!("{}/v1beta/{name}", self.base_url))
+      .header(API_KEY_HEADER, api_key.trim())
+      .send()
+      .map_err(|error| format!("failed to delete Gemini file: {}", error.without_url()))?;
+    if !response.status().is_success() {
+      return Err(format!("Gemini Files API returned {} deleting {name}", response.status()));
+    }
+    Ok(())
+  }
+}
@@ -1,10 +1,28 @@
+use std::{io::Read, time::Instant};
+
 use reqwest::blocking::Client;
 use serde::Deserialize;
-use serde_json::json;
+use serde_json::{json, Map, Value};
+
+use crate::llm::{Attachment, GenerationRequest, LlmProvider, ModelCall, ModelError, ModelErrorKind, TokenUsage};
+
+mod discovery;
+pub mod error;
+pub mod files;
+mod rate_limit;
+
+pub const DEFAULT_BASE_URL: &str = "https://generativelanguage.googleapis.com";
+/// The key travels in this header rather than in `?key=`, so it never shows up in URLs that reqwest errors
+/// print and that end up in the audit log.
+const API_KEY_HEADER: &str = "x-goog-api-key";
 
 #[derive(Debug, Clone)]
 pub struct GeminiClient {
   http: Client,
+  /// API root without the version segment, e.g. [`DEFAULT_BASE_URL`].
+  base_url: String,
+  /// Generation calls allowed per model and minute before this client holds back; zero for no limit.
+  requests_per_minute: u32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -18,88 +36,256 @@ struct GeminiContent {
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct GeminiCandidate {
   content: Option<GeminiContent>,
+  finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiPromptFeedback {
+  block_reason: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct GeminiResponse {
   candidates: Option<Vec<GeminiCandidate>>,
+  prompt_feedback: Option<GeminiPromptFeedback>,
+  usage_metadata: Option<GeminiUsage>,
+}
+
+/// Token counts from a response's `usageMetadata`; absent fields stay `None`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiUsage {
+  prompt_token_count: Option<i64>,
+  candidates_token_count: Option<i64>,
+  cached_content_token_count: Option<i64>,
+  thoughts_token_count: Option<i64>,
+  total_token_count: Option<i64>,
+}
+
+impl From<GeminiUsage> for TokenUsage {
+  fn from(usage: GeminiUsage) -> Self {
+    // Thinking tokens are billed as output but reported apart from the candidates.
+    let response_tokens = match (usage.candidates_token_count, usage.thoughts_token_count) {
+      (None, None) => None,
+      (candidates, thoughts) => Some(candidates.unwrap_or(0) + thoughts.unwrap_or(0)),
+    };
+    Self {
+      prompt_tokens: usage.prompt_token_count,
+      response_tokens,
+      cached_tokens: usage.cached_content_token_count,
+      total_tokens: usage.total_token_count,
+    }
+  }
+}
+
+/// Gemini's `responseSchema` is an OpenAPI subset: upper-case type names and no `additionalProperties`.
+fn to_gemini_schema(schema: &Value) -> Value {
+  match schema {
+    Value::Object(fields) => {
+      let mut converted = Map::new();
+      for (key, value) in fields {
+        match (key.as_str(), value) {
+          ("additionalProperties", _) => {}
+          ("type", Value::String(kind)) => {
+            converted.insert(key.clone(), Value::String(kind.to_ascii_uppercase()));
+          }
+          _ => {
+            converted.insert(key.clone(), to_gemini_schema(value));
+          }
+        }
+      }
+      Value::Object(converted)
+    }
+    Value::Array(items) => Value::Array(items.iter().map(to_gemini_schema).collect()),
+    other => other.clone(),
+  }
+}
+
+/// Request body for `generateContent`. With `redact`, inline media is replaced by its size so the body
+/// can be stored for auditing.
+fn request_body(request: &GenerationRequest<'_>, redact: bool) -> Value {
+  // Each attachment is introduced by its source label so the model can tie it back to the file list.
+  let mut parts = Vec::<Value>::new();
+  for (label, attachment) in request.attachments {
+    parts.push(json!({ "text": format!("Attached file: {label}") }));
+    parts.push(match attachment {
+      Attachment::Remote { uri, mime_type, .. } => {
+        json!({ "file_data": { "mime_type": mime_type, "file_uri": uri } })
+      }
+      Attachment::Inline { mime_type, data_base64 } => {
+        let data = if redact {
+          format!("<{} base64 characters omitted>", data_base64.len())
+        } else {
+          data_base64.clone()
+        };
+        json!({ "inline_data": { "mime_type": mime_type, "data": data } })
+      }
+    });
+  }
+  parts.push(json!({ "text": request.prompt }));
+
+  let mut generation_config = json!({ "temperature": 0.2 });
+  if let Some(schema) = request.response_schema {
+    generation_config["responseMimeType"] = json!("application/json");
+    generation_config["responseSchema"] = to_gemini_schema(schema);
+  }
+  json!({
+    "contents": [
+      {
+        "parts": parts
+      }
+    ],
+    "generationConfig": generation_config
+  })
 }
 
 impl GeminiClient {
-  pub fn new() -> Self {
+  pub fn new(base_url: &str) -> Self {
     Self {
       http: Client::new(),
+      base_url: base_url.trim().trim_end_matches('/').to_string(),
+      requests_per_minute: 0,
     }
   }
 
+  pub fn with_rate_limit(mut self, requests_per_minute: u32) -> Self {
+    self.requests_per_minute = requests_per_minute;
+    self
+  }
+
   pub fn model_health(&self) -> &'static str {
     "configured"
   }
 
-  pub fn generate_job_summary(
-    &self,
-    api_key: &str,
-    model: &str,
-    source_files: &[String],
-  ) -> Result<String, String> {
+  pub fn generate(&self, api_key: &str, request: &GenerationRequest<'_>) -> ModelCall {
+    let body = request_body(request, false);
+    let audited_body = request_body(request, true).to_string();
     if api_key.trim().is_empty() {
-      return Err("missing Gemini API key".to_string());
+      return ModelCall::failed(audited_body, ModelError::new(ModelErrorKind::Auth, "missing Gemini API key"));
+    }
+    // Buckets are per endpoint as well as per model so a proxy and the real API are throttled apart.
+    let bucket = format!("{} {}", self.base_url, request.model.trim());
+    if let Err(wait) = rate_limit::acquire(&bucket, self.requests_per_minute) {
+      let mut error = ModelError::new(
+        ModelErrorKind::RateLimited,
+        format!("Gemini calls to {} are over {} per minute", request.model.trim(), self.requests_per_minute),
+      );
+      error.retry_after = Some(wait);
+      return ModelCall::failed(audited_body, error);
     }
 
-    let prompt = format!(
-      "Summarize this ingestion batch for an Obsidian note.\n\
-       Return exactly 3 concise bullet points (Portuguese).\n\
-       Source files:\n{}",
-      source_files
-        .iter()
-        .map(|item| format!("- {item}"))
-        .collect::<Vec<_>>()
-        .join("\n")
-    );
-
-    let url = format!(
-      "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent?key={}",
-      model.trim(),
-      api_key.trim()
-    );
-
-    let response = self
-      .http
-      .post(&url)
-      .json(&json!({
-        "contents": [
-          {
-            "parts": [{ "text": prompt }]
-          }
-        ],
-        "generationConfig": {
-          "temperature": 0.2
+    let url = format!("{}/v1beta/models/{}:generateContent", self.base_url, request.model.trim());
+    let started = Instant::now();
+    let response = match self.http.post(&url).header(API_KEY_HEADER, api_key.trim()).json(&body).send() {
+      Ok(response) => response,
+      Err(error) => {
+        let error = ModelError::new(
+          ModelErrorKind::Network,
+          format!("failed to call Gemini API: {}", error.without_url()),
+        );
+        let mut call = ModelCall::failed(audited_body, error);
+        call.latency_ms = started.elapsed().as_millis() as i64;
+        return call;
+      }
+    };
+    let status = response.status();
+    let retry_after = response
+      .headers()
+      .get(reqwest::header::RETRY_AFTER)
+      .and_then(|value| value.to_str().ok())
+      .map(str::to_string);
+    let response_body = response
+      .text()
+      .unwrap_or_else(|_| "unable to read response body".to_string());
+    let mut call = ModelCall::failed(audited_body, ModelError::new(ModelErrorKind::Server, String::new()));
+    call.latency_ms = started.elapsed().as_millis() as i64;
+    call.response_body = Some(response_body.clone());
+
+    if !status.is_success() {
+      let error = error::from_response(status, retry_after.as_deref(), &response_body);
+      if let (ModelErrorKind::RateLimited | ModelErrorKind::Quota, Some(delay)) = (error.kind, error.retry_after) {
+        rate_limit::back_off(&bucket, delay);
+      }
+      call.outcome = Err(error);
+      return call;
+    }
+
+    call.outcome = serde_json::from_str::<GeminiResponse>(&response_body)
+      .map_err(|error| {
+        ModelError::new(ModelErrorKind::MalformedResponse, format!("failed to parse Gemini API response: {error}"))
+      })
+      .and_then(|payload| {
+        call.usage = payload.usage_metadata.unwrap_or_default().into();
+        let candidates = payload.candidates.unwrap_or_default();
+        let finish_reasons = candidates
+          .iter()
+          .map(|candidate| candidate.finish_reason.clone())
+          .collect::<Vec<_>>();
+        let text = candidates
+          .into_iter()
+          .next_back()
+          .and_then(|candidate| candidate.content)
+          .and_then(|content| content.parts.into_iter().find_map(|part| part.text));
+        let block_reason = payload.prompt_feedback.and_then(|feedback| feedback.block_reason);
+        match (text, error::blocked_reason(block_reason.as_deref(), &finish_reasons)) {
+          (_, Some(reason)) => Err(ModelError::new(ModelErrorKind::SafetyBlocked, reason)),
+          (Some(text), None) => Ok(text),
+          (None, None) => Err(ModelError::new(
+            ModelErrorKind::MalformedResponse,
+            "Gemini API response did not contain text output",
+          )),
         }
-      }))
-      .send()
-      .map_err(|error| format!("failed to call Gemini API: {error}"))?;
-
-    if !response.status().is_success() {
-      let status = response.status();
-      let body = response
-        .text()
-        .unwrap_or_else(|_| "unable to read response body".to_string());
-      return Err(format!("Gemini API returned {status}: {body}"));
+      });
+    call
+  }
+}
+
+/// `LlmProvider` over the Gemini API; media goes through the Files API and is deleted on release.
+pub struct GeminiProvider {
+  client: GeminiClient,
+  api_key: String,
+}
+
+impl GeminiProvider {
+  pub fn new(base_url: &str, api_key: String, requests_per_minute: u32) -> Self {
+    Self {
+      client: GeminiClient::new(base_url).with_rate_limit(requests_per_minute),
+      api_key,
     }
+  }
+}
 
-    let payload: GeminiResponse = response
-      .json()
-      .map_err(|error| format!("failed to parse Gemini API response: {error}"))?;
+impl LlmProvider for GeminiProvider {
+  fn name(&self) -> &'static str {
+    crate::llm::PROVIDER_GEMINI
+  }
+
+  fn attach(&self, label: &str, mime_type: &str, reader: Box<dyn Read + Send>) -> Result<Attachment, String> {
+    let file = self.client.upload_file(&self.api_key, label, mime_type, reader)?;
+    let name = file.name.clone();
+    let file = self.client.wait_until_active(&self.api_key, file).inspect_err(|_| {
+      let _ = self.client.delete_file(&self.api_key, &name);
+    })?;
+    Ok(Attachment::Remote {
+      name: file.name,
+      uri: file.uri,
+      mime_type: file.mime_type,
+    })
+  }
 
-    let text = payload
-      .candidates
-      .and_then(|mut candidates| candidates.pop())
-      .and_then(|candidate| candidate.content)
-      .and_then(|content| content.parts.into_iter().find_map(|part| part.text))
-      .ok_or_else(|| "Gemini API response did not contain text output".to_string())?;
+  fn release(&self, attachment: &Attachment) -> Result<(), String> {
+    match attachment {
+      Attachment::Remote { name, .. } => self.client.delete_file(&self.api_key, name),
+      Attachment::Inline { .. } => Ok(()),
+    }
+  }
 
-    Ok(text.trim().to_string())
+  fn generate(&self, request: &GenerationRequest<'_>) -> ModelCall {
+    self.client.generate(&self.api_key, request)
   }
 }
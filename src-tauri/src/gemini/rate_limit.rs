@@ -0,0 +1,93 @@
+use std::{
+  collections::HashMap,
+  sync::{Mutex, OnceLock},
+  thread,
+  time::{Duration, Instant},
+};
+
+/// Longest a call waits for a free slot before giving up with a rate-limit error of its own.
+const MAX_WAIT: Duration = Duration::from_secs(30);
+
+/// Token bucket holding up to a minute's worth of requests, refilled continuously.
+struct Bucket {
+  capacity: f64,
+  tokens: f64,
+  refilled_at: Instant,
+  /// Set from a 429's retry delay; no request leaves before it.
+  blocked_until: Option<Instant>,
+}
+
+impl Bucket {
+  fn new(requests_per_minute: u32, now: Instant) -> Self {
+    Self {
+      capacity: f64::from(requests_per_minute),
+      tokens: f64::from(requests_per_minute),
+      refilled_at: now,
+      blocked_until: None,
+    }
+  }
+
+  fn refill(&mut self, requests_per_minute: u32, now: Instant) {
+    self.capacity = f64::from(requests_per_minute);
+    let elapsed = now.saturating_duration_since(self.refilled_at).as_secs_f64();
+    self.tokens = (self.tokens + elapsed * self.capacity / 60.0).min(self.capacity);
+    self.refilled_at = now;
+  }
+
+  /// Takes a token, or returns how long until one is available.
+  fn try_take(&mut self, now: Instant) -> Result<(), Duration> {
+    if let Some(until) = self.blocked_until {
+      if until > now {
+        return Err(until - now);
+      }
+      self.blocked_until = None;
+    }
+    if self.tokens >= 1.0 {
+      self.tokens -= 1.0;
+      return Ok(());
+    }
+    Err(Duration::from_secs_f64((1.0 - self.tokens) * 60.0 / self.capacity))
+  }
+}
+
+fn buckets() -> &'static Mutex<HashMap<String, Bucket>> {
+  static BUCKETS: OnceLock<Mutex<HashMap<String, Bucket>>> = OnceLock::new();
+  BUCKETS.get_or_init(Default::default)
+}
+
+/// Waits for a request slot on `key` (endpoint and model) under `requests_per_minute`, zero meaning no
+/// limit. Returns how long a slot is still away when that exceeds [`MAX_WAIT`].
+pub fn acquire(key: &str, requests_per_minute: u32) -> Result<(), Duration> {
+  if requests_per_minute == 0 {
+    return Ok(());
+  }
+  loop {
+    let wait = {
+      let mut buckets = buckets().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+      let now = Instant::now();
+      let bucket = buckets
+        .entry(key.to_string())
+        .or_insert_with(|| Bucket::new(requests_per_minute, now));
+      bucket.refill(requests_per_minute, now);
+      match bucket.try_take(now) {
+        Ok(()) => return Ok(()),
+        Err(wait) => wait,
+      }
+    };
+    if wait > MAX_WAIT {
+      return Err(wait);
+    }
+    thread::sleep(wait);
+  }
+}
+
+/// Holds back every request on `key` for `delay`, after the server said to slow down.
+pub fn back_off(key: &str, delay: Duration) {
+  let mut buckets = buckets().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+  let now = Instant::now();
+  let bucket = buckets.entry(key.to_string()).or_insert_with(|| Bucket::new(1, now));
+  bucket.tokens = 0.0;
+  if let Some(until) = now.checked_add(delay) {
+    bucket.blocked_until = Some(bucket.blocked_until.map_or(until, |current| current.max(until)));
+  }
+}
@@ -0,0 +1,219 @@
+use std::{
+  fs,
+  io::{self, Read},
+  path::{Component, Path, PathBuf},
+};
+
+use super::{infer_media_type, SourceFile, MAX_FILE_BYTES};
+
+/// Directory under `media_root` where archives are extracted before their entries are stored.
+pub const STAGING_DIR: &str = ".staging";
+
+const MAX_ARCHIVE_ENTRIES: usize = 500;
+const MAX_ARCHIVE_TOTAL_BYTES: u64 = 4 * 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct ArchiveOrigin {
+  pub archive_path: String,
+  pub entry_name: String,
+}
+
+pub fn is_archive(file_path: &str) -> bool {
+  file_path.to_ascii_lowercase().ends_with(".zip")
+}
+
+fn is_ignored_entry(relative: &Path) -> bool {
+  relative.components().any(|component| match component {
+    Component::Normal(name) => {
+      let name = name.to_string_lossy();
+      name.starts_with('.') || name == "__MACOSX"
+    }
+    _ => true,
+  })
+}
+
+/// Sums the uncompressed sizes the central directory declares for the media entries of a `.zip` archive, so
+/// the storage quota can be checked before anything is inflated. The sizes are forgeable; extraction still
+/// enforces its own limits on the bytes actually written.
+pub fn declared_media_bytes(archive_path: &str) -> Result<u64, String> {
+  let file =
+    fs::File::open(archive_path).map_err(|error| format!("failed to open archive '{archive_path}': {error}"))?;
+  let mut archive =
+    zip::ZipArchive::new(file).map_err(|error| format!("failed to read archive '{archive_path}': {error}"))?;
+
+  let mut total_bytes = 0_u64;
+  for index in 0..archive.len() {
+    let entry = archive
+      .by_index_raw(index)
+      .map_err(|error| format!("failed to read entry {index} of '{archive_path}': {error}"))?;
+    let Some(relative) = entry.enclosed_name() else {
+      continue;
+    };
+    if entry.is_dir() || is_ignored_entry(&relative) || infer_media_type(&relative.to_string_lossy()) == "unknown" {
+      continue;
+    }
+    total_bytes = total_bytes.saturating_add(entry.size());
+  }
+  Ok(total_bytes)
+}
+
+/// Extracts the media entries of a `.zip` archive into `staging_dir`.
+///
+/// Entry names go through `enclosed_name` so nothing can escape the staging directory, and sizes are
+/// enforced on the bytes actually inflated rather than on the (forgeable) sizes in the central
+/// directory. Entries whose extension is not an accepted media type are skipped.
+pub fn extract_archive(archive_path: &str, staging_dir: &Path) -> Result<Vec<SourceFile>, String> {
+  let canonical_archive = PathBuf::from(archive_path)
+    .canonicalize()
+    .map_err(|error| format!("failed to canonicalize archive path '{archive_path}': {error}"))?;
+  let file = fs::File::open(&canonical_archive)
+    .map_err(|error| format!("failed to open archive '{archive_path}': {error}"))?;
+  let mut archive =
+    zip::ZipArchive::new(file).map_err(|error| format!("failed to read archive '{archive_path}': {error}"))?;
+
+  if archive.len() > MAX_ARCHIVE_ENTRIES {
+    return Err(format!(
+      "archive '{archive_path}' has {} entries (limit {MAX_ARCHIVE_ENTRIES})",
+      archive.len()
+    ));
+  }
+
+  fs::create_dir_all(staging_dir).map_err(|error| format!("failed to create archive staging directory: {error}"))?;
+
+  let mut extracted = Vec::new();
+  let mut total_bytes = 0_u64;
+  for index in 0..archive.len() {
+    let mut entry = archive
+      .by_index(index)
+      .map_err(|error| format!("failed to read entry {index} of '{archive_path}': {error}"))?;
+    if entry.is_dir() {
+      continue;
+    }
+
+    let Some(relative) = entry.enclosed_name() else {
+      return Err(format!("archive '{archive_path}' contains an unsafe entry path: {}", entry.name()));
+    };
+    if is_ignored_entry(&relative) {
+      continue;
+    }
+
+    let entry_name = relative.to_string_lossy().replace('\\', "/");
+    if infer_media_type(&entry_name) == "unknown" {
+      log::warn!("skipping unsupported archive entry '{entry_name}' in '{archive_path}'");
+      continue;
+    }
+
+    let remaining = MAX_ARCHIVE_TOTAL_BYTES - total_bytes;
+    let entry_limit = MAX_FILE_BYTES.min(remaining);
+    let file_name = relative.file_name().unwrap_or_default().to_string_lossy();
+    let destination = staging_dir.join(format!("{index}-{file_name}"));
+    let mut output = fs::File::create(&destination)
+      .map_err(|error| format!("failed to create staged archive entry: {error}"))?;
+    let written = io::copy(&mut (&mut entry).take(entry_limit + 1), &mut output)
+      .map_err(|error| format!("failed to extract '{entry_name}' from '{archive_path}': {error}"))?;
+    if written > entry_limit {
+      return Err(format!(
+        "archive '{archive_path}' exceeds extraction size limits at entry '{entry_name}'"
+      ));
+    }
+    total_bytes += written;
+
+    extracted.push(SourceFile {
+      path: destination.to_string_lossy().to_string(),
+      archive_origin: Some(ArchiveOrigin {
+        archive_path: canonical_archive.to_string_lossy().to_string(),
+        entry_name,
+      }),
+    });
+  }
+
+  if extracted.is_empty() {
+    return Err(format!("archive '{archive_path}' does not contain any supported media"));
+  }
+
+  Ok(extracted)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Write;
+
+  use super::*;
+  use crate::test_support::TempDir;
+
+  fn write_zip(path: &Path, entries: &[(&str, &[u8])]) {
+    let mut writer = zip::ZipWriter::new(fs::File::create(path).expect("create zip"));
+    for (name, content) in entries {
+      writer.start_file(*name, zip::write::SimpleFileOptions::default()).expect("start entry");
+      writer.write_all(content).expect("write entry");
+    }
+    writer.finish().expect("finish zip");
+  }
+
+  #[test]
+  fn declared_size_counts_only_media_entries() {
+    let dir = TempDir::new("archive-declared");
+    let archive = dir.path().join("capture.zip");
+    write_zip(
+      &archive,
+      &[
+        ("memo.wav", &[0; 300]),
+        ("photos/beach.jpg", &[0; 200]),
+        ("notes.exe", &[0; 1000]),
+        ("__MACOSX/._memo.wav", &[0; 1000]),
+      ],
+    );
+
+    assert_eq!(declared_media_bytes(&archive.to_string_lossy()).unwrap(), 500);
+  }
+
+  #[test]
+  fn media_entries_are_extracted_and_the_rest_skipped() {
+    let dir = TempDir::new("archive-extract");
+    let archive = dir.path().join("capture.zip");
+    write_zip(
+      &archive,
+      &[
+        ("memo.wav", b"RIFF"),
+        ("photos/beach.jpg", b"JPEG"),
+        ("readme.txt.exe", b"MZ"),
+        (".hidden/secret.wav", b"RIFF"),
+        ("__MACOSX/._memo.wav", b"junk"),
+      ],
+    );
+
+    let extracted = extract_archive(&archive.to_string_lossy(), &dir.path().join("staging")).unwrap();
+
+    let names = extracted
+      .iter()
+      .map(|source| source.archive_origin.as_ref().unwrap().entry_name.as_str())
+      .collect::<Vec<_>>();
+    assert_eq!(names, ["memo.wav", "photos/beach.jpg"]);
+    assert_eq!(fs::read(&extracted[1].path).unwrap(), b"JPEG");
+  }
+
+  #[test]
+  fn unsafe_paths_and_oversized_entry_counts_are_refused() {
+    let dir = TempDir::new("archive-limits");
+    let escaping = dir.path().join("escaping.zip");
+    write_zip(&escaping, &[("../../outside.wav", b"RIFF")]);
+    let error = extract_archive(&escaping.to_string_lossy(), &dir.path().join("staging-a")).unwrap_err();
+    assert!(error.contains("unsafe entry path"), "{error}");
+    assert!(!dir.path().join("outside.wav").exists());
+
+    let crowded = dir.path().join("crowded.zip");
+    let names = (0..=MAX_ARCHIVE_ENTRIES).map(|index| format!("{index}.wav")).collect::<Vec<_>>();
+    write_zip(&crowded, &names.iter().map(|name| (name.as_str(), &b""[..])).collect::<Vec<_>>());
+    let error = extract_archive(&crowded.to_string_lossy(), &dir.path().join("staging-b")).unwrap_err();
+    assert!(error.contains("entries (limit"), "{error}");
+  }
+
+  #[test]
+  fn archives_without_media_are_refused() {
+    let dir = TempDir::new("archive-empty");
+    let archive = dir.path().join("documents.zip");
+    write_zip(&archive, &[("report.exe", b"MZ")]);
+
+    assert!(extract_archive(&archive.to_string_lossy(), &dir.path().join("staging")).is_err());
+  }
+}
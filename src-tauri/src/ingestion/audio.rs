@@ -0,0 +1,589 @@
+use std::{
+  fs,
+  io::{self, BufReader, BufWriter, Read, Write},
+  path::Path,
+};
+
+use super::{
+  crypto::{EncryptingWriter, MediaKey},
+  media_destination_dir, open_stored_reader, sanitize_file_name, PreparedAsset,
+};
+
+/// Recordings longer than this are split; shorter ones are processed whole.
+const CHUNK_DURATION_US: u64 = 10 * 60 * 1_000_000;
+/// Each chunk repeats the tail of the previous one so sentences cut at a boundary survive in one piece.
+const CHUNK_OVERLAP_US: u64 = 15 * 1_000_000;
+/// WAV data is cut on block boundaries in units of roughly this length.
+const WAV_UNIT_US: u64 = 100_000;
+/// `WAVE_FORMAT_EXTENSIBLE` needs 40 bytes; anything much larger is not a real `fmt ` chunk.
+const MAX_WAV_FMT_BYTES: u64 = 64;
+
+#[derive(Debug, Clone)]
+pub struct AudioChunk {
+  pub chunk_index: i64,
+  pub start_ms: i64,
+  pub end_ms: i64,
+  pub storage_path: String,
+  pub size_bytes: i64,
+}
+
+/// A byte range of the source that can be cut on either side without corrupting the audio.
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+  offset: u64,
+  len: u64,
+  start_us: u64,
+}
+
+enum AudioFormat {
+  /// Holds the body of the source `fmt ` chunk so every piece can get a matching header.
+  Wav { fmt: Vec<u8> },
+  Mp3,
+}
+
+struct AudioLayout {
+  format: AudioFormat,
+  frames: Vec<Frame>,
+  duration_us: u64,
+}
+
+fn read_exact_or_eof(reader: &mut impl Read, buffer: &mut [u8]) -> Result<bool, String> {
+  match reader.read_exact(buffer) {
+    Ok(()) => Ok(true),
+    Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+    Err(error) => Err(format!("failed to read audio stream: {error}")),
+  }
+}
+
+fn skip_bytes(reader: &mut impl Read, count: u64) -> Result<u64, String> {
+  io::copy(&mut reader.take(count), &mut io::sink()).map_err(|error| format!("failed to read audio stream: {error}"))
+}
+
+fn parse_wav(reader: &mut impl Read, total_bytes: u64) -> Result<AudioLayout, String> {
+  let mut riff = [0_u8; 12];
+  if !read_exact_or_eof(reader, &mut riff)? || &riff[..4] != b"RIFF" || &riff[8..] != b"WAVE" {
+    return Err("file is not a RIFF/WAVE stream".to_string());
+  }
+
+  let mut position = 12_u64;
+  let mut fmt = None;
+  loop {
+    let mut header = [0_u8; 8];
+    if !read_exact_or_eof(reader, &mut header)? {
+      return Err("WAV stream has no data chunk".to_string());
+    }
+    position += 8;
+    let chunk_size = u64::from(u32::from_le_bytes([header[4], header[5], header[6], header[7]]));
+
+    match &header[..4] {
+      b"fmt " => {
+        if chunk_size > MAX_WAV_FMT_BYTES {
+          return Err(format!("WAV fmt chunk is too large ({chunk_size} bytes)"));
+        }
+        let mut body = vec![0_u8; chunk_size as usize];
+        if !read_exact_or_eof(reader, &mut body)? || body.len() < 16 {
+          return Err("WAV fmt chunk is truncated".to_string());
+        }
+        position += chunk_size;
+        if chunk_size % 2 == 1 {
+          position += skip_bytes(reader, 1)?;
+        }
+        fmt = Some(body);
+      }
+      b"data" => {
+        let fmt = fmt.ok_or_else(|| "WAV data chunk precedes its fmt chunk".to_string())?;
+        let byte_rate = u64::from(u32::from_le_bytes([fmt[8], fmt[9], fmt[10], fmt[11]]));
+        let block_align = u64::from(u16::from_le_bytes([fmt[12], fmt[13]]));
+        if byte_rate == 0 || block_align == 0 {
+          return Err("WAV fmt chunk has an invalid byte rate".to_string());
+        }
+
+        // Streaming recorders often leave the data size at 0 or 0xFFFFFFFF; trust the file length.
+        let remaining = total_bytes.saturating_sub(position);
+        let data_len = match chunk_size {
+          0 | 0xFFFF_FFFF => remaining,
+          declared => declared.min(remaining),
+        };
+        let data_len = data_len - data_len % block_align;
+        let unit_bytes = (byte_rate * WAV_UNIT_US / 1_000_000 / block_align).max(1) * block_align;
+
+        let mut frames = Vec::new();
+        let mut consumed = 0;
+        while consumed < data_len {
+          let len = unit_bytes.min(data_len - consumed);
+          frames.push(Frame {
+            offset: position + consumed,
+            len,
+            start_us: consumed * 1_000_000 / byte_rate,
+          });
+          consumed += len;
+        }
+
+        return Ok(AudioLayout {
+          format: AudioFormat::Wav { fmt },
+          frames,
+          duration_us: data_len * 1_000_000 / byte_rate,
+        });
+      }
+      _ => {
+        let padded = chunk_size + chunk_size % 2;
+        if skip_bytes(reader, padded)? != padded {
+          return Err("WAV stream is truncated".to_string());
+        }
+        position += padded;
+      }
+    }
+  }
+}
+
+/// Returns `(frame_len, samples, sample_rate)` for a valid MPEG audio frame header.
+fn mp3_frame_info(header: [u8; 4]) -> Option<(u64, u64, u64)> {
+  const BITRATES_V1_L1: [u64; 15] = [0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448];
+  const BITRATES_V1_L2: [u64; 15] = [0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384];
+  const BITRATES_V1_L3: [u64; 15] = [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320];
+  const BITRATES_V2_L1: [u64; 15] = [0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256];
+  const BITRATES_V2_L23: [u64; 15] = [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+
+  if header[0] != 0xFF || header[1] & 0xE0 != 0xE0 {
+    return None;
+  }
+
+  let version = (header[1] >> 3) & 0b11;
+  let layer = (header[1] >> 1) & 0b11;
+  let bitrate_index = usize::from(header[2] >> 4);
+  let sample_rate_index = usize::from((header[2] >> 2) & 0b11);
+  let padding = u64::from((header[2] >> 1) & 1);
+  if version == 1 || layer == 0 || bitrate_index == 0 || bitrate_index == 15 || sample_rate_index == 3 {
+    return None;
+  }
+
+  let mpeg1 = version == 3;
+  let sample_rate = match version {
+    3 => [44_100, 48_000, 32_000][sample_rate_index],
+    2 => [22_050, 24_000, 16_000][sample_rate_index],
+    _ => [11_025, 12_000, 8_000][sample_rate_index],
+  };
+  let bitrate_table = match (mpeg1, layer) {
+    (true, 3) => &BITRATES_V1_L1,
+    (true, 2) => &BITRATES_V1_L2,
+    (true, _) => &BITRATES_V1_L3,
+    (false, 3) => &BITRATES_V2_L1,
+    (false, _) => &BITRATES_V2_L23,
+  };
+  let bitrate = bitrate_table[bitrate_index] * 1000;
+
+  let (frame_len, samples) = match layer {
+    3 => ((12 * bitrate / sample_rate + padding) * 4, 384),
+    2 => (144 * bitrate / sample_rate + padding, 1152),
+    _ if mpeg1 => (144 * bitrate / sample_rate + padding, 1152),
+    _ => (72 * bitrate / sample_rate + padding, 576),
+  };
+  Some((frame_len, samples, sample_rate))
+}
+
+fn parse_mp3(reader: &mut impl Read) -> Result<AudioLayout, String> {
+  let mut reader = BufReader::new(reader);
+  let mut window = [0_u8; 4];
+  if !read_exact_or_eof(&mut reader, &mut window)? {
+    return Err("MP3 stream is empty".to_string());
+  }
+  let mut position = 0_u64;
+
+  if &window[..3] == b"ID3" {
+    let mut rest = [0_u8; 6];
+    if !read_exact_or_eof(&mut reader, &mut rest)? {
+      return Err("MP3 ID3 tag is truncated".to_string());
+    }
+    let tag_len = rest[2..]
+      .iter()
+      .fold(0_u64, |size, byte| (size << 7) | u64::from(byte & 0x7F));
+    let footer = if rest[1] & 0x10 != 0 { 10 } else { 0 };
+    skip_bytes(&mut reader, tag_len + footer)?;
+    position = 10 + tag_len + footer;
+    if !read_exact_or_eof(&mut reader, &mut window)? {
+      return Err("MP3 stream has no audio frames".to_string());
+    }
+  }
+
+  let mut frames = Vec::new();
+  let mut elapsed_us = 0_u64;
+  loop {
+    if let Some((frame_len, samples, sample_rate)) = mp3_frame_info(window) {
+      if skip_bytes(&mut reader, frame_len - 4)? < frame_len - 4 {
+        break;
+      }
+      frames.push(Frame {
+        offset: position,
+        len: frame_len,
+        start_us: elapsed_us,
+      });
+      elapsed_us += samples * 1_000_000 / sample_rate;
+      position += frame_len;
+      if !read_exact_or_eof(&mut reader, &mut window)? {
+        break;
+      }
+    } else {
+      // Not a frame header (junk, trailing ID3v1 tag): slide forward one byte to resync.
+      let mut next = [0_u8; 1];
+      if !read_exact_or_eof(&mut reader, &mut next)? {
+        break;
+      }
+      window = [window[1], window[2], window[3], next[0]];
+      position += 1;
+    }
+  }
+
+  if frames.is_empty() {
+    return Err("MP3 stream has no audio frames".to_string());
+  }
+
+  Ok(AudioLayout {
+    format: AudioFormat::Mp3,
+    frames,
+    duration_us: elapsed_us,
+  })
+}
+
+fn read_layout(asset: &PreparedAsset, key: Option<&MediaKey>) -> Result<Option<AudioLayout>, String> {
+  if asset.media_type != "audio" {
+    return Ok(None);
+  }
+
+  // The extension was already checked against the sniffed MIME type; it is also reliable for MP3s
+  // without an ID3 tag, which MIME sniffing reports as octet-stream.
+  let lower = asset.original_path.to_ascii_lowercase();
+  if lower.ends_with(".wav") {
+    let mut reader = open_stored_reader(&asset.storage_path, asset.encrypted, key)?;
+    parse_wav(&mut reader, asset.size_bytes as u64).map(Some)
+  } else if lower.ends_with(".mp3") {
+    let mut reader = open_stored_reader(&asset.storage_path, asset.encrypted, key)?;
+    parse_mp3(&mut reader).map(Some)
+  } else {
+    Ok(None)
+  }
+}
+
+struct ChunkPlan {
+  first_frame: usize,
+  last_frame: usize,
+  data_bytes: u64,
+  start_us: u64,
+  end_us: u64,
+}
+
+fn plan_chunks(layout: &AudioLayout) -> Vec<ChunkPlan> {
+  let chunk_count = layout.duration_us.div_ceil(CHUNK_DURATION_US);
+  let mut plans = Vec::new();
+  for chunk in 0..chunk_count {
+    let window_start = (chunk * CHUNK_DURATION_US).saturating_sub(CHUNK_OVERLAP_US);
+    let window_end = (chunk + 1) * CHUNK_DURATION_US;
+    let mut covered = layout
+      .frames
+      .iter()
+      .enumerate()
+      .filter(|(_, frame)| frame.start_us >= window_start && frame.start_us < window_end);
+    let Some((first_frame, first)) = covered.next() else {
+      continue;
+    };
+    let (last_frame, data_bytes) = covered.fold((first_frame, first.len), |(_, bytes), (index, frame)| {
+      (index, bytes + frame.len)
+    });
+    let end_us = layout
+      .frames
+      .get(last_frame + 1)
+      .map_or(layout.duration_us, |next| next.start_us);
+
+    plans.push(ChunkPlan {
+      first_frame,
+      last_frame,
+      data_bytes,
+      start_us: first.start_us,
+      end_us,
+    });
+  }
+  plans
+}
+
+fn wav_header(fmt: &[u8], data_bytes: u64) -> Vec<u8> {
+  let fmt_len = fmt.len() as u32;
+  let data_len = data_bytes as u32;
+  let mut header = Vec::with_capacity(28 + fmt.len());
+  header.extend_from_slice(b"RIFF");
+  header.extend_from_slice(&(4 + 8 + fmt_len + fmt_len % 2 + 8 + data_len).to_le_bytes());
+  header.extend_from_slice(b"WAVE");
+  header.extend_from_slice(b"fmt ");
+  header.extend_from_slice(&fmt_len.to_le_bytes());
+  header.extend_from_slice(fmt);
+  if fmt_len % 2 == 1 {
+    header.push(0);
+  }
+  header.extend_from_slice(b"data");
+  header.extend_from_slice(&data_len.to_le_bytes());
+  header
+}
+
+enum ChunkWriter {
+  Plain(BufWriter<fs::File>),
+  Encrypted(EncryptingWriter<BufWriter<fs::File>>),
+}
+
+impl ChunkWriter {
+  fn create(path: &Path, key: Option<&MediaKey>) -> Result<Self, String> {
+    let file = BufWriter::new(fs::File::create(path).map_err(|error| format!("failed to create audio chunk: {error}"))?);
+    match key {
+      Some(key) => Ok(Self::Encrypted(EncryptingWriter::new(file, key)?)),
+      None => Ok(Self::Plain(file)),
+    }
+  }
+
+  fn write_all(&mut self, bytes: &[u8]) -> Result<(), String> {
+    match self {
+      Self::Plain(writer) => writer.write_all(bytes),
+      Self::Encrypted(writer) => writer.write_all(bytes),
+    }
+    .map_err(|error| format!("failed to write audio chunk: {error}"))
+  }
+
+  fn finish(self) -> Result<(), String> {
+    let writer = match self {
+      Self::Plain(writer) => writer,
+      Self::Encrypted(writer) => writer.finish()?,
+    };
+    writer
+      .into_inner()
+      .map_err(|error| format!("failed to flush audio chunk: {}", error.error()))?
+      .sync_all()
+      .map_err(|error| format!("failed to sync audio chunk: {error}"))
+  }
+}
+
+/// Streams the source once and fans each frame out to every chunk that covers it. Frames sit in at
+/// most two chunks because the overlap is shorter than a chunk.
+fn write_chunks(
+  asset: &PreparedAsset,
+  layout: &AudioLayout,
+  plans: &[ChunkPlan],
+  paths: &[std::path::PathBuf],
+  key: Option<&MediaKey>,
+) -> Result<(), String> {
+  let chunk_key = if asset.encrypted { key } else { None };
+  let mut reader = BufReader::new(open_stored_reader(&asset.storage_path, asset.encrypted, key)?);
+  let mut writers: Vec<Option<ChunkWriter>> = plans.iter().map(|_| None).collect();
+  let mut position = 0_u64;
+  let mut buffer = Vec::new();
+
+  for (frame_index, frame) in layout.frames.iter().enumerate() {
+    position += skip_bytes(&mut reader, frame.offset - position)?;
+    buffer.resize(frame.len as usize, 0);
+    if !read_exact_or_eof(&mut reader, &mut buffer)? {
+      return Err("audio stream ended before the last frame".to_string());
+    }
+    position += frame.len;
+
+    for (chunk_index, plan) in plans.iter().enumerate() {
+      if frame_index < plan.first_frame || frame_index > plan.last_frame {
+        continue;
+      }
+      if writers[chunk_index].is_none() {
+        let mut writer = ChunkWriter::create(&paths[chunk_index], chunk_key)?;
+        if let AudioFormat::Wav { fmt } = &layout.format {
+          writer.write_all(&wav_header(fmt, plan.data_bytes))?;
+        }
+        writers[chunk_index] = Some(writer);
+      }
+      if let Some(writer) = writers[chunk_index].as_mut() {
+        writer.write_all(&buffer)?;
+      }
+      if frame_index == plan.last_frame {
+        if let Some(writer) = writers[chunk_index].take() {
+          writer.finish()?;
+        }
+      }
+    }
+  }
+
+  Ok(())
+}
+
+/// Splits a long WAV or MP3 asset into overlapping pieces stored next to the other app copies.
+///
+/// Returns the total duration alongside the chunks; recordings shorter than one chunk get a duration
+/// but no chunks. Pieces of encrypted assets are encrypted with the same key.
+pub fn split_audio_asset(
+  asset: &PreparedAsset,
+  media_root: &Path,
+  now: i64,
+  index: usize,
+  key: Option<&MediaKey>,
+) -> Result<Option<(i64, Vec<AudioChunk>)>, String> {
+  let Some(layout) = read_layout(asset, key)? else {
+    return Ok(None);
+  };
+  let duration_ms = (layout.duration_us / 1000) as i64;
+  if layout.duration_us <= CHUNK_DURATION_US {
+    return Ok(Some((duration_ms, Vec::new())));
+  }
+
+  let plans = plan_chunks(&layout);
+  let extension = match layout.format {
+    AudioFormat::Wav { .. } => "wav",
+    AudioFormat::Mp3 => "mp3",
+  };
+  let original_name = Path::new(&asset.original_path)
+    .file_name()
+    .and_then(|name| name.to_str())
+    .unwrap_or("audio");
+  let destination_dir = media_destination_dir(media_root, now)?;
+  let paths = (0..plans.len())
+    .map(|chunk| {
+      destination_dir.join(format!(
+        "{now}-{index}-{}.chunk{chunk:03}.{extension}",
+        sanitize_file_name(original_name)
+      ))
+    })
+    .collect::<Vec<_>>();
+
+  if let Err(error) = write_chunks(asset, &layout, &plans, &paths, key) {
+    for path in &paths {
+      let _ = fs::remove_file(path);
+    }
+    return Err(error);
+  }
+
+  let mut chunks = Vec::new();
+  for (chunk, (plan, path)) in plans.iter().zip(&paths).enumerate() {
+    let size_bytes = fs::metadata(path)
+      .map_err(|error| format!("failed to read audio chunk metadata: {error}"))?
+      .len() as i64;
+    chunks.push(AudioChunk {
+      chunk_index: chunk as i64,
+      start_ms: (plan.start_us / 1000) as i64,
+      end_ms: (plan.end_us / 1000) as i64,
+      storage_path: path.to_string_lossy().to_string(),
+      size_bytes,
+    });
+  }
+
+  Ok(Some((duration_ms, chunks)))
+}
+
+/// Best-effort segmentation; audio we cannot parse is kept whole.
+pub fn attach_audio_chunks(assets: &mut [PreparedAsset], media_root: &Path, now: i64, key: Option<&MediaKey>) {
+  for (index, asset) in assets.iter_mut().enumerate() {
+    match split_audio_asset(asset, media_root, now, index, key) {
+      Ok(Some((duration_ms, chunks))) => {
+        asset.duration_ms = Some(duration_ms);
+        asset.chunks = chunks;
+      }
+      Ok(None) => {}
+      Err(error) => log::warn!("skipping audio segmentation for '{}': {error}", asset.original_path),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// 8 kHz, 8-bit mono PCM: one byte per sample.
+  fn pcm_fmt() -> Vec<u8> {
+    let mut fmt = Vec::new();
+    fmt.extend_from_slice(&1_u16.to_le_bytes());
+    fmt.extend_from_slice(&1_u16.to_le_bytes());
+    fmt.extend_from_slice(&8000_u32.to_le_bytes());
+    fmt.extend_from_slice(&8000_u32.to_le_bytes());
+    fmt.extend_from_slice(&1_u16.to_le_bytes());
+    fmt.extend_from_slice(&8_u16.to_le_bytes());
+    fmt
+  }
+
+  fn wav_with_declared_size(declared: u32, data_bytes: usize) -> Vec<u8> {
+    let mut bytes = wav_header(&pcm_fmt(), 0);
+    let size_at = bytes.len() - 4;
+    bytes[size_at..].copy_from_slice(&declared.to_le_bytes());
+    bytes.resize(bytes.len() + data_bytes, 0x80);
+    bytes
+  }
+
+  fn wav_duration_us(bytes: &[u8]) -> u64 {
+    parse_wav(&mut &bytes[..], bytes.len() as u64).expect("parse wav").duration_us
+  }
+
+  /// One MPEG-1 Layer III frame header: 128 kbit/s, 44.1 kHz, no padding (417 bytes, 1152 samples).
+  const MP3_FRAME_HEADER: [u8; 4] = [0xFF, 0xFB, 0x90, 0x00];
+
+  fn mp3_frame() -> Vec<u8> {
+    let mut frame = MP3_FRAME_HEADER.to_vec();
+    frame.resize(417, 0);
+    frame
+  }
+
+  /// A layout of one-second WAV frames, as `parse_wav` would produce for 8 kHz 8-bit mono audio.
+  fn wav_layout(seconds: u64) -> AudioLayout {
+    AudioLayout {
+      format: AudioFormat::Wav { fmt: pcm_fmt() },
+      frames: (0..seconds)
+        .map(|second| Frame {
+          offset: 44 + second * 8000,
+          len: 8000,
+          start_us: second * 1_000_000,
+        })
+        .collect(),
+      duration_us: seconds * 1_000_000,
+    }
+  }
+
+  #[test]
+  fn wav_frames_cover_the_declared_data_in_tenth_second_units() {
+    let mut bytes = wav_with_declared_size(8000, 8000);
+    bytes.extend_from_slice(b"LIST");
+    let layout = parse_wav(&mut &bytes[..], bytes.len() as u64).unwrap();
+
+    assert_eq!(layout.frames.len(), 10);
+    assert!(layout.frames.iter().all(|frame| frame.len == 800));
+    assert_eq!(layout.frames[3].offset, 44 + 2400);
+    assert_eq!(layout.frames[3].start_us, 300_000);
+  }
+
+  #[test]
+  fn mp3_frames_are_found_after_an_id3_tag_and_junk() {
+    let mut bytes = b"ID3\x04\x00\x00\x00\x00\x00\x05".to_vec();
+    bytes.extend_from_slice(&[0; 5]);
+    bytes.extend_from_slice(b"junk");
+    for _ in 0..3 {
+      bytes.extend(mp3_frame());
+    }
+
+    let layout = parse_mp3(&mut &bytes[..]).unwrap();
+
+    assert_eq!(layout.frames.len(), 3);
+    assert_eq!(layout.frames[0].offset, 19);
+    assert_eq!(layout.frames[1].offset, 19 + 417);
+    assert_eq!(layout.frames[2].start_us, 2 * 1152 * 1_000_000 / 44_100);
+    assert!(parse_mp3(&mut &b"not audio at all"[..]).is_err());
+  }
+
+  #[test]
+  fn chunks_overlap_by_the_configured_margin() {
+    let plans = plan_chunks(&wav_layout(25 * 60));
+
+    assert_eq!(plans.len(), 3);
+    assert_eq!((plans[0].start_us, plans[0].end_us), (0, CHUNK_DURATION_US));
+    assert_eq!(plans[1].start_us, CHUNK_DURATION_US - CHUNK_OVERLAP_US);
+    assert_eq!(plans[1].end_us, 2 * CHUNK_DURATION_US);
+    assert_eq!(plans[2].end_us, 25 * 60 * 1_000_000);
+    assert_eq!(plans[1].data_bytes, (plans[1].last_frame - plans[1].first_frame + 1) as u64 * 8000);
+  }
+
+  #[test]
+  fn wav_data_size_is_capped_at_the_file_length() {
+    assert_eq!(wav_duration_us(&wav_with_declared_size(4000, 8000)), 500_000);
+    assert_eq!(wav_duration_us(&wav_with_declared_size(16000, 8000)), 1_000_000);
+  }
+
+  #[test]
+  fn unknown_wav_data_size_runs_to_the_end_of_the_file() {
+    assert_eq!(wav_duration_us(&wav_with_declared_size(0, 8000)), 1_000_000);
+    assert_eq!(wav_duration_us(&wav_with_declared_size(u32::MAX, 8000)), 1_000_000);
+  }
+}
@@ -0,0 +1,284 @@
+use std::{
+  fs,
+  io::{Read, Write},
+  path::Path,
+};
+
+use chacha20poly1305::{
+  aead::{
+    generic_array::GenericArray,
+    rand_core::RngCore,
+    stream::{DecryptorBE32, EncryptorBE32},
+    OsRng,
+  },
+  KeyInit, XChaCha20Poly1305,
+};
+
+pub type MediaKey = [u8; 32];
+
+const MAGIC: &[u8; 5] = b"OAAE1";
+const NONCE_BYTES: usize = 19;
+const CHUNK_BYTES: usize = 64 * 1024;
+const TAG_BYTES: usize = 16;
+
+pub fn generate_key() -> MediaKey {
+  let mut key = [0_u8; 32];
+  OsRng.fill_bytes(&mut key);
+  key
+}
+
+fn read_full(reader: &mut impl Read, buffer: &mut [u8]) -> Result<usize, String> {
+  let mut filled = 0;
+  while filled < buffer.len() {
+    let count = reader
+      .read(&mut buffer[filled..])
+      .map_err(|error| format!("failed to read media stream: {error}"))?;
+    if count == 0 {
+      break;
+    }
+    filled += count;
+  }
+  Ok(filled)
+}
+
+/// Encrypts everything written to it as a STREAM of 64KiB XChaCha20-Poly1305 chunks.
+///
+/// The final chunk is always shorter than a full chunk (possibly empty), which lets the reader
+/// detect the end of the stream without a length prefix. Call [`EncryptingWriter::finish`] to
+/// write it; dropping the writer without finishing leaves a truncated, unreadable stream.
+pub struct EncryptingWriter<W: Write> {
+  inner: W,
+  encryptor: EncryptorBE32<XChaCha20Poly1305>,
+  buffer: Vec<u8>,
+}
+
+impl<W: Write> EncryptingWriter<W> {
+  pub fn new(mut inner: W, key: &MediaKey) -> Result<Self, String> {
+    let mut nonce = [0_u8; NONCE_BYTES];
+    OsRng.fill_bytes(&mut nonce);
+
+    inner
+      .write_all(MAGIC)
+      .and_then(|()| inner.write_all(&nonce))
+      .map_err(|error| format!("failed to write encrypted media header: {error}"))?;
+
+    let cipher = XChaCha20Poly1305::new(GenericArray::from_slice(key));
+    Ok(Self {
+      inner,
+      encryptor: EncryptorBE32::from_aead(cipher, GenericArray::from_slice(&nonce)),
+      buffer: Vec::with_capacity(CHUNK_BYTES),
+    })
+  }
+
+  fn write_full_chunk(&mut self) -> std::io::Result<()> {
+    let ciphertext = self
+      .encryptor
+      .encrypt_next(self.buffer.as_slice())
+      .map_err(|_| std::io::Error::other("failed to encrypt media chunk"))?;
+    self.buffer.clear();
+    self.inner.write_all(&ciphertext)
+  }
+
+  pub fn finish(mut self) -> Result<W, String> {
+    if self.buffer.len() == CHUNK_BYTES {
+      self
+        .write_full_chunk()
+        .map_err(|error| format!("failed to write encrypted media: {error}"))?;
+    }
+
+    let ciphertext = self
+      .encryptor
+      .encrypt_last(self.buffer.as_slice())
+      .map_err(|_| "failed to encrypt final media chunk".to_string())?;
+    self
+      .inner
+      .write_all(&ciphertext)
+      .and_then(|()| self.inner.flush())
+      .map_err(|error| format!("failed to write encrypted media: {error}"))?;
+    Ok(self.inner)
+  }
+}
+
+impl<W: Write> Write for EncryptingWriter<W> {
+  fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+    // A full buffer is only flushed once more data arrives, so `finish` always sees a short tail.
+    if self.buffer.len() == CHUNK_BYTES && !data.is_empty() {
+      self.write_full_chunk()?;
+    }
+    let count = data.len().min(CHUNK_BYTES - self.buffer.len());
+    self.buffer.extend_from_slice(&data[..count]);
+    Ok(count)
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    self.inner.flush()
+  }
+}
+
+pub fn encrypt_stream(reader: &mut impl Read, writer: &mut impl Write, key: &MediaKey) -> Result<(), String> {
+  let mut encrypting = EncryptingWriter::new(writer, key)?;
+  std::io::copy(reader, &mut encrypting).map_err(|error| format!("failed to encrypt media stream: {error}"))?;
+  encrypting.finish().map(|_| ())
+}
+
+pub fn encrypt_file(source: &Path, destination: &Path, key: &MediaKey) -> Result<(), String> {
+  let mut reader =
+    fs::File::open(source).map_err(|error| format!("failed to open media for encryption: {error}"))?;
+  let mut writer =
+    fs::File::create(destination).map_err(|error| format!("failed to create encrypted media file: {error}"))?;
+  encrypt_stream(&mut reader, &mut writer, key)?;
+  writer
+    .sync_all()
+    .map_err(|error| format!("failed to sync encrypted media file: {error}"))
+}
+
+/// Streams the plaintext of an encrypted media file, verifying each chunk as it is read.
+pub struct DecryptingReader<R: Read> {
+  inner: R,
+  decryptor: Option<DecryptorBE32<XChaCha20Poly1305>>,
+  ciphertext: Vec<u8>,
+  plaintext: Vec<u8>,
+  position: usize,
+}
+
+impl<R: Read> DecryptingReader<R> {
+  pub fn new(mut inner: R, key: &MediaKey) -> Result<Self, String> {
+    let mut header = [0_u8; MAGIC.len() + NONCE_BYTES];
+    if read_full(&mut inner, &mut header)? != header.len() || &header[..MAGIC.len()] != MAGIC {
+      return Err("media file is not in the encrypted storage format".to_string());
+    }
+
+    let cipher = XChaCha20Poly1305::new(GenericArray::from_slice(key));
+    let decryptor = DecryptorBE32::from_aead(cipher, GenericArray::from_slice(&header[MAGIC.len()..]));
+
+    Ok(Self {
+      inner,
+      decryptor: Some(decryptor),
+      ciphertext: vec![0_u8; CHUNK_BYTES + TAG_BYTES],
+      plaintext: Vec::new(),
+      position: 0,
+    })
+  }
+
+  fn fill_plaintext(&mut self) -> Result<(), String> {
+    let Some(mut decryptor) = self.decryptor.take() else {
+      return Ok(());
+    };
+
+    let count = read_full(&mut self.inner, &mut self.ciphertext)?;
+    self.position = 0;
+    if count == self.ciphertext.len() {
+      self.plaintext = decryptor
+        .decrypt_next(&self.ciphertext[..count])
+        .map_err(|_| "encrypted media chunk failed authentication".to_string())?;
+      self.decryptor = Some(decryptor);
+    } else {
+      self.plaintext = decryptor
+        .decrypt_last(&self.ciphertext[..count])
+        .map_err(|_| "encrypted media is truncated or failed authentication".to_string())?;
+    }
+    Ok(())
+  }
+}
+
+impl<R: Read> Read for DecryptingReader<R> {
+  fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+    while self.position == self.plaintext.len() {
+      if self.decryptor.is_none() {
+        return Ok(0);
+      }
+      self
+        .fill_plaintext()
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+    }
+
+    let count = buffer.len().min(self.plaintext.len() - self.position);
+    buffer[..count].copy_from_slice(&self.plaintext[self.position..self.position + count]);
+    self.position += count;
+    Ok(count)
+  }
+}
+
+pub fn decrypt_file(source: &Path, destination: &Path, key: &MediaKey) -> Result<(), String> {
+  let file = fs::File::open(source).map_err(|error| format!("failed to open encrypted media: {error}"))?;
+  let mut reader = DecryptingReader::new(file, key)?;
+  let mut writer =
+    fs::File::create(destination).map_err(|error| format!("failed to create decrypted media file: {error}"))?;
+  std::io::copy(&mut reader, &mut writer).map_err(|error| format!("failed to decrypt media: {error}"))?;
+  writer
+    .sync_all()
+    .map_err(|error| format!("failed to sync decrypted media file: {error}"))
+}
+
+pub fn encode_key(key: &MediaKey) -> String {
+  key.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+pub fn decode_key(value: &str) -> Result<MediaKey, String> {
+  let value = value.trim();
+  if value.len() != 64 || !value.is_ascii() {
+    return Err("stored media encryption key is malformed".to_string());
+  }
+
+  let mut key = [0_u8; 32];
+  for (index, byte) in key.iter_mut().enumerate() {
+    *byte = u8::from_str_radix(&value[index * 2..index * 2 + 2], 16)
+      .map_err(|_| "stored media encryption key is malformed".to_string())?;
+  }
+  Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn encrypt(plaintext: &[u8], key: &MediaKey) -> Vec<u8> {
+    let mut ciphertext = Vec::new();
+    encrypt_stream(&mut &plaintext[..], &mut ciphertext, key).unwrap();
+    ciphertext
+  }
+
+  fn decrypt(ciphertext: &[u8], key: &MediaKey) -> Result<Vec<u8>, String> {
+    let mut plaintext = Vec::new();
+    DecryptingReader::new(ciphertext, key)?
+      .read_to_end(&mut plaintext)
+      .map_err(|error| error.to_string())?;
+    Ok(plaintext)
+  }
+
+  #[test]
+  fn streams_round_trip_across_chunk_boundaries() {
+    let key = generate_key();
+    for len in [0, 1, CHUNK_BYTES - 1, CHUNK_BYTES, CHUNK_BYTES + 1, 3 * CHUNK_BYTES] {
+      let plaintext = (0..len).map(|index| (index % 251) as u8).collect::<Vec<_>>();
+      let ciphertext = encrypt(&plaintext, &key);
+      let chunks = len / CHUNK_BYTES + 1;
+      assert_eq!(ciphertext.len(), MAGIC.len() + NONCE_BYTES + len + chunks * TAG_BYTES);
+      assert_eq!(decrypt(&ciphertext, &key).unwrap(), plaintext, "length {len}");
+    }
+  }
+
+  #[test]
+  fn tampered_truncated_or_foreign_streams_are_rejected() {
+    let key = generate_key();
+    let ciphertext = encrypt(&vec![7_u8; 2 * CHUNK_BYTES + 10], &key);
+
+    let mut tampered = ciphertext.clone();
+    tampered[MAGIC.len() + NONCE_BYTES + 5] ^= 1;
+    assert!(decrypt(&tampered, &key).is_err());
+
+    // Dropping the final chunk must not pass for a complete stream.
+    let truncated = &ciphertext[..MAGIC.len() + NONCE_BYTES + 2 * (CHUNK_BYTES + TAG_BYTES)];
+    assert!(decrypt(truncated, &key).is_err());
+
+    assert!(decrypt(&ciphertext, &generate_key()).is_err());
+    assert!(decrypt(b"plain text", &key).is_err());
+  }
+
+  #[test]
+  fn keys_survive_encoding() {
+    let key = generate_key();
+    assert_eq!(decode_key(&encode_key(&key)).unwrap(), key);
+    assert!(decode_key("not a key").is_err());
+  }
+}
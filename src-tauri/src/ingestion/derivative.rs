@@ -0,0 +1,102 @@
+use std::{fs, io::Read, path::Path};
+
+use image::{codecs::jpeg::JpegEncoder, imageops::FilterType};
+
+use super::{
+  crypto, crypto::MediaKey, media_destination_dir, open_stored_reader, sanitize_file_name, PreparedAsset,
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct DerivativeOptions {
+  pub max_dimension: u32,
+  pub jpeg_quality: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImageDerivative {
+  pub path: String,
+  pub mime_type: String,
+  pub size_bytes: i64,
+}
+
+fn is_decodable(mime_type: &str) -> bool {
+  matches!(mime_type, "image/jpeg" | "image/png")
+}
+
+/// Produces a downscaled JPEG of an image asset in app storage, next to the stored copy.
+///
+/// Returns `Ok(None)` for formats we cannot decode and when re-encoding would not make the file
+/// smaller, so callers can keep using the original in those cases.
+pub fn create_image_derivative(
+  asset: &PreparedAsset,
+  media_root: &Path,
+  now: i64,
+  index: usize,
+  key: Option<&MediaKey>,
+  options: &DerivativeOptions,
+) -> Result<Option<ImageDerivative>, String> {
+  if asset.media_type != "image" || !is_decodable(&asset.mime_type) {
+    return Ok(None);
+  }
+
+  let mut source_bytes = Vec::new();
+  open_stored_reader(&asset.storage_path, asset.encrypted, key)?
+    .read_to_end(&mut source_bytes)
+    .map_err(|error| format!("failed to read image for downscaling: {error}"))?;
+  let decoded = image::load_from_memory(&source_bytes)
+    .map_err(|error| format!("failed to decode image for downscaling: {error}"))?;
+
+  let resized = if decoded.width().max(decoded.height()) > options.max_dimension {
+    decoded.resize(options.max_dimension, options.max_dimension, FilterType::Lanczos3)
+  } else {
+    decoded
+  };
+
+  let mut encoded = Vec::new();
+  JpegEncoder::new_with_quality(&mut encoded, options.jpeg_quality)
+    .encode_image(&resized.to_rgb8())
+    .map_err(|error| format!("failed to encode downscaled image: {error}"))?;
+  if encoded.len() as i64 >= asset.size_bytes {
+    return Ok(None);
+  }
+
+  let original_name = Path::new(&asset.original_path)
+    .file_name()
+    .and_then(|name| name.to_str())
+    .unwrap_or("image");
+  let destination = media_destination_dir(media_root, now)?.join(format!(
+    "{now}-{index}-{}.derived.jpg",
+    sanitize_file_name(original_name)
+  ));
+
+  if asset.encrypted {
+    let key = key.ok_or_else(|| "media encryption key is required to store encrypted derivatives".to_string())?;
+    let mut file =
+      fs::File::create(&destination).map_err(|error| format!("failed to create image derivative: {error}"))?;
+    crypto::encrypt_stream(&mut encoded.as_slice(), &mut file, key)?;
+  } else {
+    fs::write(&destination, &encoded).map_err(|error| format!("failed to write image derivative: {error}"))?;
+  }
+
+  Ok(Some(ImageDerivative {
+    path: destination.to_string_lossy().to_string(),
+    mime_type: "image/jpeg".to_string(),
+    size_bytes: encoded.len() as i64,
+  }))
+}
+
+/// Best-effort preprocessing: an image that cannot be downscaled keeps being used as-is.
+pub fn attach_image_derivatives(
+  assets: &mut [PreparedAsset],
+  media_root: &Path,
+  now: i64,
+  key: Option<&MediaKey>,
+  options: &DerivativeOptions,
+) {
+  for (index, asset) in assets.iter_mut().enumerate() {
+    match create_image_derivative(asset, media_root, now, index, key, options) {
+      Ok(derivative) => asset.derivative = derivative,
+      Err(error) => log::warn!("skipping image derivative for '{}': {error}", asset.original_path),
+    }
+  }
+}
@@ -0,0 +1,137 @@
+use std::{fs, path::Path};
+
+use crate::models::{IngestMode, JobAsset, MediaIssue};
+
+use super::{crypto, crypto::MediaKey, hash_asset, hash_sha256};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueKind {
+  Missing,
+  HashMismatch,
+  ZeroByte,
+  /// The check itself failed, e.g. the file could not be read or the encryption key is unavailable.
+  Unreadable,
+}
+
+impl IssueKind {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      IssueKind::Missing => "missing",
+      IssueKind::HashMismatch => "hash_mismatch",
+      IssueKind::ZeroByte => "zero_byte",
+      IssueKind::Unreadable => "unreadable",
+    }
+  }
+}
+
+/// Re-hashes the stored copy of an asset and reports the first problem found, if any.
+pub fn inspect_asset(asset: &JobAsset, key: Option<&MediaKey>) -> Result<Option<(IssueKind, String)>, String> {
+  let storage_path = Path::new(&asset.storage_path);
+  if asset.storage_path.trim().is_empty() || !storage_path.is_file() {
+    let detail = match IngestMode::parse(&asset.ingest_mode) {
+      IngestMode::Reference => "referenced source file does not exist",
+      IngestMode::Copy | IngestMode::Move => "stored copy does not exist",
+    };
+    return Ok(Some((IssueKind::Missing, detail.to_string())));
+  }
+
+  let metadata = fs::metadata(storage_path)
+    .map_err(|error| format!("failed to read metadata for '{}': {error}", asset.storage_path))?;
+  if metadata.len() == 0 && asset.size_bytes > 0 {
+    return Ok(Some((
+      IssueKind::ZeroByte,
+      format!("stored copy is empty (expected {} bytes)", asset.size_bytes),
+    )));
+  }
+
+  let actual = match hash_asset(asset, key) {
+    Ok(hash) => hash,
+    Err(error) if asset.encrypted => return Ok(Some((IssueKind::HashMismatch, error))),
+    Err(error) => return Err(error),
+  };
+  if actual != asset.sha256 {
+    return Ok(Some((
+      IssueKind::HashMismatch,
+      format!("expected sha256 {} but found {actual}", asset.sha256),
+    )));
+  }
+
+  Ok(None)
+}
+
+/// Restores the stored copy from `original_path` when the original still matches the recorded hash.
+pub fn repair_asset(asset: &JobAsset, key: Option<&MediaKey>) -> Result<(), String> {
+  if IngestMode::parse(&asset.ingest_mode) == IngestMode::Reference {
+    return Err("asset is referenced in place; there is no stored copy to restore".to_string());
+  }
+
+  let original = Path::new(&asset.original_path);
+  if !original.is_file() {
+    return Err("original file is no longer available".to_string());
+  }
+
+  if hash_sha256(original)? != asset.sha256 {
+    return Err("original file no longer matches the recorded sha256".to_string());
+  }
+
+  let storage_path = Path::new(&asset.storage_path);
+  if let Some(parent) = storage_path.parent() {
+    fs::create_dir_all(parent).map_err(|error| format!("failed to recreate media directory: {error}"))?;
+  }
+
+  let temp_path = storage_path.with_extension("repair.tmp");
+  if asset.encrypted {
+    let key = key.ok_or_else(|| "media encryption key is required to repair encrypted media".to_string())?;
+    crypto::encrypt_file(original, &temp_path, key)?;
+  } else {
+    fs::copy(original, &temp_path)
+      .map_err(|error| format!("failed to copy original into app storage: {error}"))?;
+  }
+  fs::rename(&temp_path, storage_path).map_err(|error| format!("failed to replace stored copy: {error}"))?;
+
+  if hash_asset(asset, key)? != asset.sha256 {
+    return Err("repaired copy does not match the recorded sha256".to_string());
+  }
+
+  Ok(())
+}
+
+pub fn verify_asset(
+  asset: &JobAsset,
+  repair: bool,
+  key: Option<&MediaKey>,
+) -> Result<Option<MediaIssue>, String> {
+  let Some((kind, detail)) = inspect_asset(asset, key)? else {
+    return Ok(None);
+  };
+
+  let mut issue = MediaIssue {
+    asset_id: asset.id,
+    job_id: asset.job_id.clone(),
+    storage_path: asset.storage_path.clone(),
+    kind: kind.as_str().to_string(),
+    detail,
+    repaired: false,
+  };
+
+  if repair && !asset.storage_path.trim().is_empty() {
+    match repair_asset(asset, key) {
+      Ok(()) => issue.repaired = true,
+      Err(error) => issue.detail = format!("{}; repair failed: {error}", issue.detail),
+    }
+  }
+
+  Ok(Some(issue))
+}
+
+/// Reports an asset whose check could not run, so one bad file does not abort the whole verification.
+pub fn unreadable_issue(asset: &JobAsset, error: String) -> MediaIssue {
+  MediaIssue {
+    asset_id: asset.id,
+    job_id: asset.job_id.clone(),
+    storage_path: asset.storage_path.clone(),
+    kind: IssueKind::Unreadable.as_str().to_string(),
+    detail: error,
+    repaired: false,
+  }
+}
@@ -6,7 +6,33 @@ use std::{
 
 use sha2::{Digest, Sha256};
 
+use crate::models::{IngestMode, JobAsset};
+
+use self::{
+  archive::ArchiveOrigin,
+  audio::AudioChunk,
+  crypto::{DecryptingReader, MediaKey},
+  derivative::ImageDerivative,
+  policy::SourcePolicy,
+  subtitles::SubtitleCue,
+};
+
+pub mod archive;
+pub mod audio;
+pub mod crypto;
+pub mod derivative;
+pub mod integrity;
+pub mod perceptual;
+pub mod policy;
+pub mod quarantine;
+pub mod storage;
+pub mod subtitles;
+pub mod thumbnail;
+
 const MAX_FILE_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+const MAX_TEXT_BYTES: usize = 1024 * 1024;
+
+pub const TEXT_CAPTURE_ORIGIN: &str = "text-capture";
 
 #[derive(Debug, Clone)]
 pub struct PreparedAsset {
@@ -16,6 +42,72 @@ pub struct PreparedAsset {
   pub mime_type: String,
   pub size_bytes: i64,
   pub sha256: String,
+  pub ingest_mode: String,
+  pub encrypted: bool,
+  pub archive_origin: Option<ArchiveOrigin>,
+  pub derivative: Option<ImageDerivative>,
+  pub thumbnail_path: Option<String>,
+  pub perceptual_hash: Option<i64>,
+  pub duration_ms: Option<i64>,
+  pub chunks: Vec<AudioChunk>,
+  /// Index of the video this subtitle sidecar belongs to, within the same batch.
+  pub parent_index: Option<usize>,
+  pub subtitle_cues: Vec<SubtitleCue>,
+}
+
+/// A file held back from its job until the user approves or discards it.
+#[derive(Debug, Clone)]
+pub struct QuarantinedFile {
+  pub asset: PreparedAsset,
+  pub reason: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PreparedBatch {
+  pub assets: Vec<PreparedAsset>,
+  pub quarantined: Vec<QuarantinedFile>,
+}
+
+impl PreparedBatch {
+  pub fn rollback(&self, key: Option<&MediaKey>) {
+    rollback_stored_assets(&self.assets, key);
+    for file in &self.quarantined {
+      rollback_stored_assets(std::slice::from_ref(&file.asset), key);
+    }
+  }
+}
+
+/// A file to ingest; entries extracted from an archive remember where they came from.
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+  pub path: String,
+  pub archive_origin: Option<ArchiveOrigin>,
+}
+
+impl SourceFile {
+  pub fn from_path(path: &str) -> Self {
+    Self {
+      path: path.to_string(),
+      archive_origin: None,
+    }
+  }
+
+  fn label(&self) -> String {
+    match &self.archive_origin {
+      Some(origin) => format!("{}!/{}", origin.archive_path, origin.entry_name),
+      None => self.path.clone(),
+    }
+  }
+}
+
+/// How a batch of source files is brought into app storage.
+#[derive(Debug, Clone)]
+pub struct IngestOptions {
+  pub mode: IngestMode,
+  /// When set, stored copies under `media_root` are encrypted; referenced files are never touched.
+  pub encryption_key: Option<MediaKey>,
+  /// Where sources may come from; archive entries are judged by the archive they were extracted from.
+  pub source_policy: SourcePolicy,
 }
 
 pub fn infer_media_type(file_path: &str) -> &'static str {
@@ -30,6 +122,8 @@ pub fn infer_media_type(file_path: &str) -> &'static str {
     || lower.ends_with(".heif")
   {
     "image"
+  } else if subtitles::is_subtitle(&lower) {
+    "subtitle"
   } else {
     "unknown"
   }
@@ -65,17 +159,27 @@ fn mime_matches_media_type(media_type: &str, mime_type: &str) -> bool {
     "audio" => mime_type.starts_with("audio/"),
     "video" => mime_type.starts_with("video/"),
     "image" => mime_type.starts_with("image/"),
+    "subtitle" => mime_type.starts_with("text/"),
     _ => false,
   }
 }
 
-fn hash_sha256(path: &Path) -> Result<String, String> {
-  let mut file = fs::File::open(path).map_err(|error| format!("failed to open file for hashing: {error}"))?;
+fn media_destination_dir(media_root: &Path, now: i64) -> Result<PathBuf, String> {
+  let timestamp = chrono::DateTime::from_timestamp_millis(now).unwrap_or_else(chrono::Utc::now);
+  let destination_dir = media_root
+    .join(timestamp.format("%Y").to_string())
+    .join(timestamp.format("%m").to_string());
+  fs::create_dir_all(&destination_dir)
+    .map_err(|error| format!("failed to create media destination directory: {error}"))?;
+  Ok(destination_dir)
+}
+
+fn hash_reader(reader: &mut impl Read) -> Result<String, String> {
   let mut hasher = Sha256::new();
   let mut buffer = [0_u8; 8192];
 
   loop {
-    let count = file
+    let count = reader
       .read(&mut buffer)
       .map_err(|error| format!("failed while hashing file: {error}"))?;
     if count == 0 {
@@ -87,26 +191,162 @@ fn hash_sha256(path: &Path) -> Result<String, String> {
   Ok(format!("{:x}", hasher.finalize()))
 }
 
-pub fn prepare_assets(file_paths: &[String], media_root: &Path, now: i64) -> Result<Vec<PreparedAsset>, String> {
-  if file_paths.is_empty() {
+fn hash_sha256(path: &Path) -> Result<String, String> {
+  let mut file = fs::File::open(path).map_err(|error| format!("failed to open file for hashing: {error}"))?;
+  hash_reader(&mut file)
+}
+
+/// Opens the plaintext content of a stored file, transparently decrypting it when `encrypted`.
+pub fn open_stored_reader(
+  storage_path: &str,
+  encrypted: bool,
+  key: Option<&MediaKey>,
+) -> Result<Box<dyn Read + Send>, String> {
+  let file = fs::File::open(storage_path)
+    .map_err(|error| format!("failed to open stored media '{storage_path}': {error}"))?;
+  if !encrypted {
+    return Ok(Box::new(file));
+  }
+
+  let key = key.ok_or_else(|| "media encryption key is required to read encrypted media".to_string())?;
+  Ok(Box::new(DecryptingReader::new(file, key)?))
+}
+
+pub fn open_asset_reader(asset: &JobAsset, key: Option<&MediaKey>) -> Result<Box<dyn Read + Send>, String> {
+  open_stored_reader(&asset.storage_path, asset.encrypted, key)
+}
+
+pub fn read_asset_to_string(asset: &JobAsset, key: Option<&MediaKey>) -> Result<String, String> {
+  let mut content = String::new();
+  open_asset_reader(asset, key)?
+    .read_to_string(&mut content)
+    .map_err(|error| format!("failed to read stored media '{}': {error}", asset.storage_path))?;
+  Ok(content)
+}
+
+pub fn hash_asset(asset: &JobAsset, key: Option<&MediaKey>) -> Result<String, String> {
+  hash_reader(&mut open_asset_reader(asset, key)?)
+}
+
+fn store_source(
+  source: &Path,
+  destination: &Path,
+  mode: IngestMode,
+  encryption_key: Option<&MediaKey>,
+  sha256: &str,
+) -> Result<PathBuf, String> {
+  match (mode, encryption_key) {
+    (IngestMode::Reference, _) => Ok(source.to_path_buf()),
+    (IngestMode::Copy, Some(key)) => {
+      crypto::encrypt_file(source, destination, key)?;
+      Ok(destination.to_path_buf())
+    }
+    (IngestMode::Copy, None) => {
+      fs::copy(source, destination).map_err(|error| format!("failed to copy file to app storage: {error}"))?;
+      Ok(destination.to_path_buf())
+    }
+    (IngestMode::Move, Some(key)) => {
+      crypto::encrypt_file(source, destination, key)?;
+      fs::remove_file(source).map_err(|error| format!("failed to remove moved source file: {error}"))?;
+      Ok(destination.to_path_buf())
+    }
+    (IngestMode::Move, None) => {
+      if fs::rename(source, destination).is_err() {
+        // Renames fail across volumes, so fall back to a verified copy before removing the source.
+        fs::copy(source, destination).map_err(|error| format!("failed to copy file to app storage: {error}"))?;
+        if hash_sha256(destination)? != sha256 {
+          let _ = fs::remove_file(destination);
+          return Err("moved copy does not match the source sha256".to_string());
+        }
+        fs::remove_file(source).map_err(|error| format!("failed to remove moved source file: {error}"))?;
+      }
+      Ok(destination.to_path_buf())
+    }
+  }
+}
+
+/// Undoes the storage side effects of already prepared assets, e.g. when the job insert fails.
+pub fn rollback_stored_assets(assets: &[PreparedAsset], key: Option<&MediaKey>) {
+  for asset in assets {
+    if let Some(derivative) = &asset.derivative {
+      let _ = fs::remove_file(&derivative.path);
+    }
+    if let Some(thumbnail_path) = &asset.thumbnail_path {
+      let _ = fs::remove_file(thumbnail_path);
+    }
+    for chunk in &asset.chunks {
+      let _ = fs::remove_file(&chunk.storage_path);
+    }
+
+    if asset.archive_origin.is_some() {
+      // Archive entries were staged by us, so there is no user file to restore.
+      let _ = fs::remove_file(&asset.storage_path);
+      continue;
+    }
+
+    match IngestMode::parse(&asset.ingest_mode) {
+      IngestMode::Copy => {
+        let _ = fs::remove_file(&asset.storage_path);
+      }
+      IngestMode::Move if asset.encrypted => {
+        let restored = key
+          .map(|key| crypto::decrypt_file(Path::new(&asset.storage_path), Path::new(&asset.original_path), key))
+          .is_some_and(|result| result.is_ok());
+        if restored {
+          let _ = fs::remove_file(&asset.storage_path);
+        }
+      }
+      IngestMode::Move => {
+        if fs::rename(&asset.storage_path, &asset.original_path).is_err()
+          && fs::copy(&asset.storage_path, &asset.original_path).is_ok()
+        {
+          let _ = fs::remove_file(&asset.storage_path);
+        }
+      }
+      IngestMode::Reference => {}
+    }
+  }
+}
+
+/// Validates and stores a batch of source files. Archive entries are always moved out of their
+/// staging directory, whatever mode the batch uses.
+///
+/// Files whose content does not match their extension are set aside in quarantine instead of failing
+/// the batch. Quarantine always holds a private copy and never the user's only one, so files the user picked are
+/// copied there whatever the mode; discarding them leaves the source where it was.
+pub fn prepare_assets(
+  sources: &[SourceFile],
+  media_root: &Path,
+  now: i64,
+  options: &IngestOptions,
+) -> Result<PreparedBatch, String> {
+  if sources.is_empty() {
     return Err("at least one file path is required".to_string());
   }
 
-  let timestamp = chrono::DateTime::from_timestamp_millis(now).unwrap_or_else(chrono::Utc::now);
-  let destination_dir = media_root
-    .join(timestamp.format("%Y").to_string())
-    .join(timestamp.format("%m").to_string());
-  fs::create_dir_all(&destination_dir)
-    .map_err(|error| format!("failed to create media destination directory: {error}"))?;
+  let destination_dir = media_destination_dir(media_root, now)?;
+  let quarantine_dir = quarantine::quarantine_dir(media_root)?;
 
-  file_paths
+  // Validate the whole batch before touching any file, so a late failure never leaves sources moved.
+  let validated = sources
     .iter()
     .enumerate()
-    .map(|(index, input_path)| {
-      let source = PathBuf::from(input_path);
+    .map(|(index, source_file)| {
+      let input_path = source_file.label();
+      let input_path = input_path.as_str();
+      let mut mode = if source_file.archive_origin.is_some() {
+        IngestMode::Move
+      } else {
+        options.mode
+      };
+      let source = PathBuf::from(&source_file.path);
       let canonical_source = source
         .canonicalize()
         .map_err(|error| format!("failed to canonicalize source path '{input_path}': {error}"))?;
+      match &source_file.archive_origin {
+        Some(origin) => options.source_policy.check(&origin.archive_path).map(|_| ())?,
+        None => options.source_policy.ensure_permitted(&canonical_source, input_path)?,
+      }
 
       let metadata = fs::metadata(&canonical_source)
         .map_err(|error| format!("failed to read file metadata '{input_path}': {error}"))?;
@@ -124,40 +364,141 @@ pub fn prepare_assets(file_paths: &[String], media_root: &Path, now: i64) -> Res
       }
 
       let mime_type = detect_mime(&canonical_source)?;
-      if !mime_matches_media_type(media_type, &mime_type) {
-        return Err(format!(
-          "file extension/media mismatch for '{input_path}' (detected mime: {mime_type})"
-        ));
+      let quarantine_reason = (!mime_matches_media_type(media_type, &mime_type))
+        .then(|| format!("file extension/media mismatch (detected mime: {mime_type})"));
+      if quarantine_reason.is_some() && source_file.archive_origin.is_none() {
+        mode = IngestMode::Copy;
       }
 
       let sha256 = hash_sha256(&canonical_source)?;
-      let original_name = canonical_source
+      let name_source = match &source_file.archive_origin {
+        Some(origin) => Path::new(&origin.entry_name),
+        None => canonical_source.as_path(),
+      };
+      let original_name = name_source
         .file_name()
         .and_then(|name| name.to_str())
         .unwrap_or("asset.bin");
       let safe_name = sanitize_file_name(original_name);
-      let destination_file = destination_dir.join(format!("{}-{}-{}", now, index, safe_name));
-
-      fs::copy(&canonical_source, &destination_file)
-        .map_err(|error| format!("failed to copy file to app storage '{input_path}': {error}"))?;
-
-      Ok(PreparedAsset {
-        original_path: canonical_source.to_string_lossy().to_string(),
-        storage_path: destination_file.to_string_lossy().to_string(),
-        media_type: media_type.to_string(),
-        mime_type,
-        size_bytes: metadata.len() as i64,
-        sha256,
-      })
+      let destination_file = match quarantine_reason {
+        Some(_) => quarantine_dir.join(format!("{}-{}-{}", now, index, safe_name)),
+        None => destination_dir.join(format!("{}-{}-{}", now, index, safe_name)),
+      };
+
+      let original_path = match source_file.archive_origin {
+        Some(_) => input_path.to_string(),
+        None => canonical_source.to_string_lossy().to_string(),
+      };
+
+      Ok((
+        canonical_source,
+        destination_file,
+        quarantine_reason,
+        PreparedAsset {
+          original_path,
+          storage_path: String::new(),
+          media_type: media_type.to_string(),
+          mime_type,
+          size_bytes: metadata.len() as i64,
+          sha256,
+          ingest_mode: mode.as_str().to_string(),
+          encrypted: options.encryption_key.is_some() && mode != IngestMode::Reference,
+          archive_origin: source_file.archive_origin.clone(),
+          derivative: None,
+          thumbnail_path: None,
+          perceptual_hash: None,
+          duration_ms: None,
+          chunks: Vec::new(),
+          parent_index: None,
+          subtitle_cues: Vec::new(),
+        },
+      ))
     })
-    .collect()
+    .collect::<Result<Vec<_>, String>>()?;
+
+  let mut assets = Vec::with_capacity(validated.len());
+  let mut quarantined = Vec::new();
+  for (source, destination, quarantine_reason, mut asset) in validated {
+    let mode = IngestMode::parse(&asset.ingest_mode);
+    let encryption_key = options.encryption_key.as_ref();
+    match store_source(&source, &destination, mode, encryption_key, &asset.sha256) {
+      Ok(stored_path) => {
+        asset.storage_path = stored_path.to_string_lossy().to_string();
+        match quarantine_reason {
+          Some(reason) => quarantined.push(QuarantinedFile { asset, reason }),
+          None => assets.push(asset),
+        }
+      }
+      Err(error) => {
+        let batch = PreparedBatch { assets, quarantined };
+        batch.rollback(options.encryption_key.as_ref());
+        return Err(format!("{error} ({})", asset.original_path));
+      }
+    }
+  }
+
+  Ok(PreparedBatch { assets, quarantined })
+}
+
+/// Stores pasted text as a `.txt` asset so it flows through the same job pipeline as files.
+pub fn prepare_text_asset(
+  text: &str,
+  media_root: &Path,
+  now: i64,
+  index: usize,
+  encryption_key: Option<&MediaKey>,
+) -> Result<PreparedAsset, String> {
+  let trimmed = text.trim();
+  if trimmed.is_empty() {
+    return Err("text capture cannot be empty".to_string());
+  }
+  if trimmed.len() > MAX_TEXT_BYTES {
+    return Err("text capture exceeds 1MB limit".to_string());
+  }
+
+  let destination_dir = media_destination_dir(media_root, now)?;
+  let destination_file = destination_dir.join(format!("{now}-{index}-{TEXT_CAPTURE_ORIGIN}.txt"));
+  match encryption_key {
+    Some(key) => {
+      let mut file = fs::File::create(&destination_file)
+        .map_err(|error| format!("failed to write text capture to app storage: {error}"))?;
+      crypto::encrypt_stream(&mut trimmed.as_bytes(), &mut file, key)?;
+    }
+    None => fs::write(&destination_file, trimmed.as_bytes())
+      .map_err(|error| format!("failed to write text capture to app storage: {error}"))?,
+  }
+
+  let mut hasher = Sha256::new();
+  hasher.update(trimmed.as_bytes());
+
+  Ok(PreparedAsset {
+    original_path: TEXT_CAPTURE_ORIGIN.to_string(),
+    storage_path: destination_file.to_string_lossy().to_string(),
+    media_type: "text".to_string(),
+    mime_type: "text/plain".to_string(),
+    size_bytes: trimmed.len() as i64,
+    sha256: format!("{:x}", hasher.finalize()),
+    ingest_mode: IngestMode::Copy.as_str().to_string(),
+    encrypted: encryption_key.is_some(),
+    archive_origin: None,
+    derivative: None,
+    thumbnail_path: None,
+    perceptual_hash: None,
+    duration_ms: None,
+    chunks: Vec::new(),
+    parent_index: None,
+    subtitle_cues: Vec::new(),
+  })
 }
 
-pub fn build_job_title(optional_title: Option<&str>, file_count: usize) -> String {
+pub fn build_job_title(optional_title: Option<&str>, file_count: usize, has_text: bool) -> String {
   if let Some(title) = optional_title {
     if !title.trim().is_empty() {
       return title.trim().to_owned();
     }
   }
+  if file_count == 0 && has_text {
+    return "Text capture".to_string();
+  }
   format!("Capture batch ({file_count} files)")
 }
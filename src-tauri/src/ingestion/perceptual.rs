@@ -0,0 +1,104 @@
+use std::io::Read;
+
+use image::imageops::FilterType;
+
+use super::{crypto::MediaKey, open_stored_reader, PreparedAsset};
+
+/// Maximum Hamming distance between two difference hashes for images to count as near-duplicates.
+pub const NEAR_DUPLICATE_DISTANCE: u32 = 6;
+
+/// Computes a 64-bit difference hash (dHash): the image is reduced to 9x8 grayscale and each bit
+/// records whether a pixel is brighter than its right-hand neighbour. Re-encodes, resizes and small
+/// crops leave most bits unchanged, which byte-level hashes cannot tolerate.
+fn difference_hash(image: &image::DynamicImage) -> u64 {
+  let reduced = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+  let mut hash = 0_u64;
+  for y in 0..8 {
+    for x in 0..8 {
+      hash <<= 1;
+      if reduced.get_pixel(x, y)[0] > reduced.get_pixel(x + 1, y)[0] {
+        hash |= 1;
+      }
+    }
+  }
+  hash
+}
+
+/// Hashes an image asset from its stored copy. Stored as `i64` because SQLite integers are signed;
+/// only the bit pattern matters.
+pub fn compute_perceptual_hash(asset: &PreparedAsset, key: Option<&MediaKey>) -> Result<Option<i64>, String> {
+  if asset.media_type != "image" || !matches!(asset.mime_type.as_str(), "image/jpeg" | "image/png") {
+    return Ok(None);
+  }
+
+  let mut bytes = Vec::new();
+  open_stored_reader(&asset.storage_path, asset.encrypted, key)?
+    .read_to_end(&mut bytes)
+    .map_err(|error| format!("failed to read image for perceptual hash: {error}"))?;
+  let decoded =
+    image::load_from_memory(&bytes).map_err(|error| format!("failed to decode image for perceptual hash: {error}"))?;
+  Ok(Some(difference_hash(&decoded) as i64))
+}
+
+pub fn attach_perceptual_hashes(assets: &mut [PreparedAsset], key: Option<&MediaKey>) {
+  for asset in assets.iter_mut() {
+    match compute_perceptual_hash(asset, key) {
+      Ok(hash) => asset.perceptual_hash = hash,
+      Err(error) => log::warn!("skipping perceptual hash for '{}': {error}", asset.original_path),
+    }
+  }
+}
+
+pub fn hamming_distance(left: i64, right: i64) -> u32 {
+  (left ^ right).count_ones()
+}
+
+/// Picks the closest earlier asset within [`NEAR_DUPLICATE_DISTANCE`] of `hash`; ties go to the
+/// oldest asset so a group of near-duplicates always collapses onto the same original.
+pub fn find_near_duplicate(asset_id: i64, hash: i64, earlier: &[(i64, i64)]) -> Option<i64> {
+  earlier
+    .iter()
+    .filter(|(candidate_id, _)| *candidate_id < asset_id)
+    .map(|(candidate_id, candidate_hash)| (hamming_distance(hash, *candidate_hash), *candidate_id))
+    .filter(|(distance, _)| *distance <= NEAR_DUPLICATE_DISTANCE)
+    .min()
+    .map(|(_, candidate_id)| candidate_id)
+}
+
+#[cfg(test)]
+mod tests {
+  use image::{DynamicImage, GrayImage, Luma};
+
+  use super::*;
+
+  fn gradient(width: u32, height: u32, falling: bool) -> DynamicImage {
+    DynamicImage::ImageLuma8(GrayImage::from_fn(width, height, |x, _| {
+      let level = (x * 255 / (width - 1)) as u8;
+      Luma([if falling { 255 - level } else { level }])
+    }))
+  }
+
+  #[test]
+  fn dhash_records_left_to_right_brightness() {
+    assert_eq!(difference_hash(&gradient(90, 80, true)), u64::MAX);
+    assert_eq!(difference_hash(&gradient(90, 80, false)), 0);
+  }
+
+  #[test]
+  fn resized_copies_hash_as_near_duplicates() {
+    let original = gradient(640, 480, true);
+    let smaller = original.resize_exact(160, 120, FilterType::Nearest);
+
+    let distance = hamming_distance(difference_hash(&original) as i64, difference_hash(&smaller) as i64);
+    assert!(distance <= NEAR_DUPLICATE_DISTANCE, "distance {distance}");
+  }
+
+  #[test]
+  fn near_duplicates_collapse_onto_the_oldest_close_match() {
+    let hash = 0b1111_0000_i64;
+    let earlier = [(3, 0b1111_0001), (1, 0b1111_0010), (2, !0b1111_0000), (9, 0b1111_0000)];
+
+    assert_eq!(find_near_duplicate(5, hash, &earlier), Some(1));
+    assert_eq!(find_near_duplicate(1, hash, &earlier), None);
+  }
+}
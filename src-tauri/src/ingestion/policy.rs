@@ -0,0 +1,72 @@
+use std::{
+  collections::HashSet,
+  path::{Path, PathBuf},
+};
+
+/// Which source files the backend is willing to read on behalf of the webview: files the user picked
+/// through the native dialog, plus anything under a directory allowlisted in settings.
+#[derive(Debug, Clone, Default)]
+pub struct SourcePolicy {
+  allowed_roots: Vec<PathBuf>,
+  approved_files: HashSet<PathBuf>,
+}
+
+impl SourcePolicy {
+  /// Roots that no longer exist are ignored rather than failing every ingestion.
+  pub fn new(allowed_roots: &[String], approved_files: HashSet<PathBuf>) -> Self {
+    Self {
+      allowed_roots: allowed_roots
+        .iter()
+        .filter_map(|root| Path::new(root).canonicalize().ok())
+        .collect(),
+      approved_files,
+    }
+  }
+
+  /// Expects a canonical path, so `..` segments and symlinks cannot step outside a root.
+  pub fn permits(&self, canonical_path: &Path) -> bool {
+    self.approved_files.contains(canonical_path)
+      || self.allowed_roots.iter().any(|root| canonical_path.starts_with(root))
+  }
+
+  pub fn ensure_permitted(&self, canonical_path: &Path, label: &str) -> Result<(), String> {
+    if self.permits(canonical_path) {
+      return Ok(());
+    }
+    Err(format!(
+      "source path '{label}' was not selected in the file dialog and is outside the allowed source folders"
+    ))
+  }
+
+  /// Canonicalizes `path` and returns it if the policy allows reading it.
+  pub fn check(&self, path: &str) -> Result<PathBuf, String> {
+    let canonical = Path::new(path)
+      .canonicalize()
+      .map_err(|error| format!("failed to canonicalize source path '{path}': {error}"))?;
+    self.ensure_permitted(&canonical, path)?;
+    Ok(canonical)
+  }
+}
+
+/// Validates settings input: every root must be an existing absolute directory. Returns the canonical
+/// forms so the stored allowlist matches what `SourcePolicy::permits` compares against.
+pub fn normalize_roots(roots: &[String]) -> Result<Vec<String>, String> {
+  let mut normalized = Vec::new();
+  for root in roots.iter().map(|root| root.trim()).filter(|root| !root.is_empty()) {
+    let path = Path::new(root);
+    if !path.is_absolute() {
+      return Err(format!("allowed source folder must be an absolute path: {root}"));
+    }
+    let canonical = path
+      .canonicalize()
+      .map_err(|error| format!("allowed source folder '{root}' is not accessible: {error}"))?;
+    if !canonical.is_dir() {
+      return Err(format!("allowed source folder is not a directory: {root}"));
+    }
+    let canonical = canonical.to_string_lossy().to_string();
+    if !normalized.contains(&canonical) {
+      normalized.push(canonical);
+    }
+  }
+  Ok(normalized)
+}
@@ -0,0 +1,73 @@
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+use crate::models::QuarantineItem;
+
+use super::{media_destination_dir, storage, PreparedAsset};
+
+/// Directory under `media_root` holding files that failed content checks.
+pub const QUARANTINE_DIR: &str = "quarantine";
+
+pub fn quarantine_dir(media_root: &Path) -> Result<PathBuf, String> {
+  let dir = media_root.join(QUARANTINE_DIR);
+  fs::create_dir_all(&dir).map_err(|error| format!("failed to create quarantine directory: {error}"))?;
+  Ok(dir)
+}
+
+/// Moves an approved file from quarantine into regular app storage and describes it as an asset. Derived media is
+/// attached by the caller, as for any newly stored file.
+pub fn release(item: &QuarantineItem, media_root: &Path, now: i64) -> Result<PreparedAsset, String> {
+  let source = Path::new(&item.storage_path);
+  if !source.starts_with(media_root.join(QUARANTINE_DIR)) {
+    return Err(format!("quarantined file is outside the quarantine area: {}", item.storage_path));
+  }
+
+  let file_name = source
+    .file_name()
+    .ok_or_else(|| format!("quarantined file has no name: {}", item.storage_path))?;
+  let destination = media_destination_dir(media_root, now)?.join(file_name);
+  fs::rename(source, &destination).map_err(|error| format!("failed to release quarantined file: {error}"))?;
+
+  Ok(PreparedAsset {
+    original_path: item.original_path.clone(),
+    storage_path: destination.to_string_lossy().to_string(),
+    media_type: item.media_type.clone(),
+    mime_type: item.mime_type.clone(),
+    size_bytes: item.size_bytes,
+    sha256: item.sha256.clone(),
+    ingest_mode: item.ingest_mode.clone(),
+    encrypted: item.encrypted,
+    archive_origin: None,
+    derivative: None,
+    thumbnail_path: None,
+    perceptual_hash: None,
+    duration_ms: None,
+    chunks: Vec::new(),
+    parent_index: None,
+    subtitle_cues: Vec::new(),
+  })
+}
+
+/// Puts a released file back and drops what was derived from it, e.g. when recording the approval fails.
+pub fn restore(asset: &PreparedAsset, item: &QuarantineItem) {
+  if let Some(derivative) = &asset.derivative {
+    let _ = fs::remove_file(&derivative.path);
+  }
+  if let Some(thumbnail_path) = &asset.thumbnail_path {
+    let _ = fs::remove_file(thumbnail_path);
+  }
+  for chunk in &asset.chunks {
+    let _ = fs::remove_file(&chunk.storage_path);
+  }
+  let _ = fs::rename(&asset.storage_path, &item.storage_path);
+}
+
+/// Deletes the quarantined copy. Files the user picked were copied into quarantine, so their source is untouched.
+pub fn discard(item: &QuarantineItem, media_root: &Path) -> Result<(), String> {
+  if !Path::new(&item.storage_path).starts_with(media_root.join(QUARANTINE_DIR)) {
+    return Err(format!("quarantined file is outside the quarantine area: {}", item.storage_path));
+  }
+  storage::evict_stored_copy(media_root, &item.storage_path)
+}
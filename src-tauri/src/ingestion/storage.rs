@@ -0,0 +1,112 @@
+use std::{
+  collections::HashSet,
+  fs,
+  path::{Path, PathBuf},
+  time::{Duration, SystemTime},
+};
+
+use crate::models::IngestMode;
+
+use super::SourceFile;
+
+/// Files younger than this are never treated as orphans, so in-flight ingestions are left alone.
+const ORPHAN_GRACE_PERIOD: Duration = Duration::from_secs(60 * 60);
+
+pub fn incoming_bytes(sources: &[SourceFile], text_content: Option<&str>, mode: IngestMode) -> u64 {
+  let file_bytes = sources
+    .iter()
+    .filter(|source| source.archive_origin.is_some() || mode != IngestMode::Reference)
+    .filter_map(|source| fs::metadata(&source.path).ok())
+    .map(|metadata| metadata.len())
+    .sum::<u64>();
+  file_bytes + text_content.map(|text| text.len() as u64).unwrap_or(0)
+}
+
+pub fn ensure_within_quota(used_bytes: i64, incoming_bytes: u64, quota_bytes: i64) -> Result<(), String> {
+  if quota_bytes <= 0 {
+    return Ok(());
+  }
+
+  let projected = used_bytes.saturating_add(incoming_bytes as i64);
+  if projected > quota_bytes {
+    return Err(format!(
+      "media storage quota exceeded: {used_bytes} bytes stored + {incoming_bytes} incoming > {quota_bytes} allowed"
+    ));
+  }
+  Ok(())
+}
+
+fn walk_files(root: &Path) -> Result<Vec<PathBuf>, String> {
+  let mut files = Vec::new();
+  let mut pending = vec![root.to_path_buf()];
+
+  while let Some(dir) = pending.pop() {
+    let entries = fs::read_dir(&dir)
+      .map_err(|error| format!("failed to read media directory '{}': {error}", dir.to_string_lossy()))?;
+    for entry in entries {
+      let entry = entry.map_err(|error| format!("failed to read media directory entry: {error}"))?;
+      let file_type = entry
+        .file_type()
+        .map_err(|error| format!("failed to read media entry type: {error}"))?;
+      if file_type.is_dir() {
+        pending.push(entry.path());
+      } else if file_type.is_file() {
+        files.push(entry.path());
+      }
+    }
+  }
+
+  Ok(files)
+}
+
+/// Deletes files under `root` (media or thumbnails) that no row points to. Returns `(files, bytes)`.
+pub fn collect_orphans(
+  root: &Path,
+  known_paths: &HashSet<PathBuf>,
+  dry_run: bool,
+) -> Result<(i64, i64), String> {
+  let now = SystemTime::now();
+  let mut files = 0;
+  let mut bytes = 0;
+
+  for path in walk_files(root)? {
+    if known_paths.contains(&path) {
+      continue;
+    }
+
+    let metadata = fs::metadata(&path)
+      .map_err(|error| format!("failed to read metadata for '{}': {error}", path.to_string_lossy()))?;
+    let is_recent = metadata
+      .modified()
+      .ok()
+      .and_then(|modified| now.duration_since(modified).ok())
+      .map(|age| age < ORPHAN_GRACE_PERIOD)
+      .unwrap_or(true);
+    if is_recent {
+      continue;
+    }
+
+    if !dry_run {
+      fs::remove_file(&path)
+        .map_err(|error| format!("failed to delete orphaned media '{}': {error}", path.to_string_lossy()))?;
+    }
+    files += 1;
+    bytes += metadata.len() as i64;
+  }
+
+  Ok((files, bytes))
+}
+
+/// Removes a stored copy or thumbnail of an asset, refusing to touch anything outside `root`.
+pub fn evict_stored_copy(root: &Path, storage_path: &str) -> Result<(), String> {
+  let path = Path::new(storage_path);
+  if !path.starts_with(root) {
+    return Err(format!("refusing to evict media outside app storage: {storage_path}"));
+  }
+
+  match fs::remove_file(path) {
+    Ok(()) => Ok(()),
+    Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+    Err(error) => Err(format!("failed to evict stored media '{storage_path}': {error}")),
+  }
+}
@@ -0,0 +1,206 @@
+use std::{fs, io::Read, path::Path};
+
+use super::{crypto::MediaKey, open_stored_reader, PreparedAsset};
+
+#[derive(Debug, Clone)]
+pub struct SubtitleCue {
+  pub start_ms: i64,
+  pub end_ms: i64,
+  pub text: String,
+}
+
+pub fn is_subtitle(file_path: &str) -> bool {
+  let lower = file_path.to_ascii_lowercase();
+  lower.ends_with(".srt") || lower.ends_with(".vtt")
+}
+
+fn file_stem(file_name: &str) -> &str {
+  file_name.rsplit_once('.').map_or(file_name, |(stem, _)| stem)
+}
+
+/// Whether a subtitle file is named after `video_stem`, with or without a language tag
+/// (`talk.srt`, `talk.en.srt`, `talk.pt-BR.vtt`).
+fn names_video(subtitle_name: &str, video_stem: &str) -> bool {
+  let stem = file_stem(subtitle_name);
+  stem == video_stem
+    || stem
+      .strip_prefix(video_stem)
+      .and_then(|rest| rest.strip_prefix('.'))
+      .is_some_and(|language| !language.is_empty() && language.len() <= 8 && !language.contains('.'))
+}
+
+/// Finds `.srt`/`.vtt` files next to a video that are named after it.
+pub fn find_sidecars(video_path: &str) -> Vec<String> {
+  let path = Path::new(video_path);
+  let (Some(directory), Some(file_name)) = (path.parent(), path.file_name().and_then(|name| name.to_str())) else {
+    return Vec::new();
+  };
+  let video_stem = file_stem(file_name);
+
+  let Ok(entries) = fs::read_dir(directory) else {
+    return Vec::new();
+  };
+  let mut sidecars = entries
+    .filter_map(Result::ok)
+    .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_file()))
+    .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+    .filter(|name| is_subtitle(name) && names_video(name, video_stem))
+    .map(|name| directory.join(name).to_string_lossy().to_string())
+    .collect::<Vec<_>>();
+  sidecars.sort();
+  sidecars
+}
+
+/// Points each subtitle asset at the video in the same batch it is named after.
+pub fn link_sidecars(assets: &mut [PreparedAsset]) {
+  let videos = assets
+    .iter()
+    .enumerate()
+    .filter(|(_, asset)| asset.media_type == "video")
+    .filter_map(|(index, asset)| {
+      let name = Path::new(&asset.original_path).file_name()?.to_str()?;
+      Some((index, file_stem(name).to_string()))
+    })
+    .collect::<Vec<_>>();
+
+  for asset in assets.iter_mut().filter(|asset| asset.media_type == "subtitle") {
+    let Some(name) = Path::new(&asset.original_path).file_name().and_then(|name| name.to_str()) else {
+      continue;
+    };
+    asset.parent_index = videos
+      .iter()
+      .find(|(_, video_stem)| names_video(name, video_stem))
+      .map(|(index, _)| *index);
+  }
+}
+
+/// Parses `HH:MM:SS,mmm`, `HH:MM:SS.mmm` or the WebVTT short form `MM:SS.mmm`.
+fn parse_timestamp(value: &str) -> Option<i64> {
+  let (clock, millis) = value.trim().split_once([',', '.'])?;
+  let millis = millis.parse::<i64>().ok()?;
+  let parts = clock
+    .split(':')
+    .map(|part| part.parse::<i64>().ok())
+    .collect::<Option<Vec<_>>>()?;
+  let seconds = match parts.as_slice() {
+    [hours, minutes, seconds] => hours * 3600 + minutes * 60 + seconds,
+    [minutes, seconds] => minutes * 60 + seconds,
+    _ => return None,
+  };
+  Some(seconds * 1000 + millis)
+}
+
+fn strip_markup(line: &str) -> String {
+  let mut text = String::with_capacity(line.len());
+  let mut in_tag = false;
+  for ch in line.chars() {
+    match ch {
+      '<' => in_tag = true,
+      '>' if in_tag => in_tag = false,
+      _ if !in_tag => text.push(ch),
+      _ => {}
+    }
+  }
+  text.trim().to_string()
+}
+
+/// Parses SubRip and WebVTT cues. Both are blank-line separated blocks with a `start --> end` timing
+/// line; cue numbers, the WebVTT header, NOTE/STYLE blocks and inline markup are dropped.
+pub fn parse_cues(content: &str) -> Result<Vec<SubtitleCue>, String> {
+  let normalized = content.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+  let mut cues = Vec::new();
+
+  for block in normalized.split("\n\n") {
+    let lines = block
+      .lines()
+      .map(str::trim)
+      .filter(|line| !line.is_empty())
+      .collect::<Vec<_>>();
+    if lines
+      .first()
+      .is_some_and(|line| line.starts_with("NOTE") || line.starts_with("STYLE"))
+    {
+      continue;
+    }
+    let Some(timing_index) = lines.iter().position(|line| line.contains("-->")) else {
+      continue;
+    };
+
+    let (start, end) = lines[timing_index].split_once("-->").unwrap_or_default();
+    // WebVTT allows cue settings after the end timestamp.
+    let end = end.split_whitespace().next().unwrap_or_default();
+    let (Some(start_ms), Some(end_ms)) = (parse_timestamp(start), parse_timestamp(end)) else {
+      return Err(format!("invalid subtitle timing line: {}", lines[timing_index]));
+    };
+
+    let text = lines[timing_index + 1..]
+      .iter()
+      .map(|line| strip_markup(line))
+      .filter(|line| !line.is_empty())
+      .collect::<Vec<_>>()
+      .join("\n");
+    if !text.is_empty() {
+      cues.push(SubtitleCue { start_ms, end_ms, text });
+    }
+  }
+
+  if cues.is_empty() {
+    return Err("subtitle file does not contain any cues".to_string());
+  }
+  Ok(cues)
+}
+
+fn read_cues(asset: &PreparedAsset, key: Option<&MediaKey>) -> Result<Vec<SubtitleCue>, String> {
+  let mut content = String::new();
+  open_stored_reader(&asset.storage_path, asset.encrypted, key)?
+    .read_to_string(&mut content)
+    .map_err(|error| format!("failed to read subtitle file: {error}"))?;
+  parse_cues(&content)
+}
+
+/// Best-effort parsing; a malformed sidecar is kept as a file but contributes no timed text.
+pub fn attach_subtitle_cues(assets: &mut [PreparedAsset], key: Option<&MediaKey>) {
+  for asset in assets.iter_mut().filter(|asset| asset.media_type == "subtitle") {
+    match read_cues(asset, key) {
+      Ok(cues) => asset.subtitle_cues = cues,
+      Err(error) => log::warn!("skipping subtitle cues for '{}': {error}", asset.original_path),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn srt_cues_are_parsed_without_numbers_or_markup() {
+    let srt = "\u{feff}1\r\n00:00:01,000 --> 00:00:02,500\r\n<i>Hello</i> there\r\n\r\n\
+               2\r\n01:02:03,040 --> 01:02:04,000\r\nsecond line\r\ncontinues\r\n";
+
+    let cues = parse_cues(srt).unwrap();
+
+    assert_eq!(cues.len(), 2);
+    assert_eq!((cues[0].start_ms, cues[0].end_ms), (1_000, 2_500));
+    assert_eq!(cues[0].text, "Hello there");
+    assert_eq!(cues[1].start_ms, 3_723_040);
+    assert_eq!(cues[1].text, "second line\ncontinues");
+  }
+
+  #[test]
+  fn vtt_cues_skip_the_header_notes_and_cue_settings() {
+    let vtt = "WEBVTT\n\nNOTE written by hand\n\nSTYLE\n::cue { color: red }\n\n\
+               intro\n00:05.000 --> 00:07.250 align:start position:10%\n<v Ana>Welcome back\n";
+
+    let cues = parse_cues(vtt).unwrap();
+
+    assert_eq!(cues.len(), 1);
+    assert_eq!((cues[0].start_ms, cues[0].end_ms), (5_000, 7_250));
+    assert_eq!(cues[0].text, "Welcome back");
+  }
+
+  #[test]
+  fn malformed_or_empty_subtitles_are_rejected() {
+    assert!(parse_cues("1\n00:00:01 --> 00:00:02\nno millis\n").is_err());
+    assert!(parse_cues("WEBVTT\n\n").is_err());
+  }
+}
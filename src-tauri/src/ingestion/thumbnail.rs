@@ -0,0 +1,137 @@
+use std::{
+  fs,
+  io::Read,
+  path::{Path, PathBuf},
+  process::{Command, Stdio},
+  thread,
+  time::{Duration, Instant},
+};
+
+use image::{codecs::jpeg::JpegEncoder, DynamicImage};
+
+use super::{crypto, crypto::MediaKey, open_stored_reader, PreparedAsset};
+
+const THUMBNAIL_DIMENSION: u32 = 320;
+const THUMBNAIL_QUALITY: u8 = 75;
+/// A damaged or huge video can keep `ffmpeg` seeking for a long time; the poster frame is not worth the wait.
+const POSTER_FRAME_TIMEOUT: Duration = Duration::from_secs(20);
+
+fn encode_thumbnail(image: DynamicImage) -> Result<Vec<u8>, String> {
+  let thumbnail = image.thumbnail(THUMBNAIL_DIMENSION, THUMBNAIL_DIMENSION);
+  let mut encoded = Vec::new();
+  JpegEncoder::new_with_quality(&mut encoded, THUMBNAIL_QUALITY)
+    .encode_image(&thumbnail.to_rgb8())
+    .map_err(|error| format!("failed to encode thumbnail: {error}"))?;
+  Ok(encoded)
+}
+
+fn image_thumbnail(asset: &PreparedAsset, key: Option<&MediaKey>) -> Result<Option<Vec<u8>>, String> {
+  if !matches!(asset.mime_type.as_str(), "image/jpeg" | "image/png") {
+    return Ok(None);
+  }
+
+  let mut bytes = Vec::new();
+  open_stored_reader(&asset.storage_path, asset.encrypted, key)?
+    .read_to_end(&mut bytes)
+    .map_err(|error| format!("failed to read image for thumbnail: {error}"))?;
+  let decoded = image::load_from_memory(&bytes).map_err(|error| format!("failed to decode image: {error}"))?;
+  encode_thumbnail(decoded).map(Some)
+}
+
+/// Grabs a poster frame with `ffmpeg` when it is on PATH. Encrypted copies are skipped because
+/// decrypting a whole video just for a preview is not cheap.
+fn video_poster_frame(asset: &PreparedAsset, scratch_path: &Path) -> Result<Option<Vec<u8>>, String> {
+  if asset.encrypted {
+    return Ok(None);
+  }
+
+  let child = Command::new("ffmpeg")
+    .args(["-hide_banner", "-loglevel", "error", "-nostdin", "-y", "-ss", "1", "-i"])
+    .arg(&asset.storage_path)
+    .args(["-frames:v", "1", "-f", "image2"])
+    .arg(scratch_path)
+    .stdin(Stdio::null())
+    .stdout(Stdio::null())
+    .stderr(Stdio::null())
+    .spawn();
+  let Ok(mut child) = child else {
+    return Ok(None);
+  };
+
+  let deadline = Instant::now() + POSTER_FRAME_TIMEOUT;
+  let succeeded = loop {
+    match child.try_wait() {
+      Ok(Some(status)) => break status.success(),
+      Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(50)),
+      Ok(None) => {
+        log::warn!("ffmpeg timed out on '{}'; skipping its poster frame", asset.original_path);
+        let _ = child.kill();
+        let _ = child.wait();
+        break false;
+      }
+      Err(_) => {
+        let _ = child.kill();
+        break false;
+      }
+    }
+  };
+  if !succeeded || !scratch_path.is_file() {
+    let _ = fs::remove_file(scratch_path);
+    return Ok(None);
+  }
+
+  let bytes = fs::read(scratch_path).map_err(|error| format!("failed to read poster frame: {error}"))?;
+  let _ = fs::remove_file(scratch_path);
+  let decoded = image::load_from_memory(&bytes).map_err(|error| format!("failed to decode poster frame: {error}"))?;
+  encode_thumbnail(decoded).map(Some)
+}
+
+fn write_thumbnail(path: &Path, bytes: &[u8], key: Option<&MediaKey>) -> Result<(), String> {
+  match key {
+    Some(key) => {
+      let mut file = fs::File::create(path).map_err(|error| format!("failed to create thumbnail: {error}"))?;
+      crypto::encrypt_stream(&mut &bytes[..], &mut file, key)
+    }
+    None => fs::write(path, bytes).map_err(|error| format!("failed to write thumbnail: {error}")),
+  }
+}
+
+/// Best-effort preview generation; assets we cannot cheaply decode simply have no thumbnail.
+/// Thumbnails of encrypted media are encrypted with the same key. File names start with `name_prefix` (the job
+/// id), so batches prepared in the same millisecond do not overwrite each other's previews.
+pub fn attach_thumbnails(
+  assets: &mut [PreparedAsset],
+  thumbnail_root: &Path,
+  name_prefix: &str,
+  key: Option<&MediaKey>,
+) {
+  for (index, asset) in assets.iter_mut().enumerate() {
+    let thumbnail_path = thumbnail_root.join(format!("{name_prefix}-{index}.jpg"));
+    let result = match asset.media_type.as_str() {
+      "image" => image_thumbnail(asset, key),
+      "video" => video_poster_frame(asset, &thumbnail_root.join(format!("{name_prefix}-{index}.frame.jpg"))),
+      _ => Ok(None),
+    };
+
+    let stored = result.and_then(|bytes| match bytes {
+      Some(bytes) => {
+        let thumbnail_key = if asset.encrypted { key } else { None };
+        write_thumbnail(&thumbnail_path, &bytes, thumbnail_key).map(|()| Some(thumbnail_path.clone()))
+      }
+      None => Ok(None),
+    });
+
+    match stored {
+      Ok(path) => asset.thumbnail_path = path.map(|path: PathBuf| path.to_string_lossy().to_string()),
+      Err(error) => log::warn!("skipping thumbnail for '{}': {error}", asset.original_path),
+    }
+  }
+}
+
+pub fn read_thumbnail(path: &str, encrypted: bool, key: Option<&MediaKey>) -> Result<Vec<u8>, String> {
+  let mut bytes = Vec::new();
+  open_stored_reader(path, encrypted, key)?
+    .read_to_end(&mut bytes)
+    .map_err(|error| format!("failed to read thumbnail: {error}"))?;
+  Ok(bytes)
+}
@@ -0,0 +1,128 @@
+use std::{
+  io::{BufRead, BufReader, Read, Write},
+  net::{TcpListener, TcpStream},
+  sync::{Arc, Mutex},
+  thread,
+};
+
+/// A request as the mock server received it. `path` includes the query string.
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+  pub method: String,
+  pub path: String,
+  pub headers: Vec<(String, String)>,
+  pub body: Vec<u8>,
+}
+
+impl RecordedRequest {
+  pub fn header(&self, name: &str) -> Option<&str> {
+    self
+      .headers
+      .iter()
+      .find(|(key, _)| key.eq_ignore_ascii_case(name))
+      .map(|(_, value)| value.as_str())
+  }
+
+  pub fn body_text(&self) -> String {
+    String::from_utf8_lossy(&self.body).to_string()
+  }
+}
+
+pub struct MockResponse {
+  pub status: u16,
+  pub headers: Vec<(String, String)>,
+  pub body: String,
+}
+
+impl MockResponse {
+  pub fn json(status: u16, body: impl Into<String>) -> Self {
+    Self {
+      status,
+      headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+      body: body.into(),
+    }
+  }
+}
+
+type Handler = dyn Fn(&RecordedRequest, &str) -> MockResponse + Send + Sync;
+
+/// Minimal HTTP/1.1 server on a loopback port. Each connection carries one request, answered by the
+/// handler, which also receives the server's base URL for building absolute links.
+pub struct MockServer {
+  pub base_url: String,
+  requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl MockServer {
+  pub fn start(handler: impl Fn(&RecordedRequest, &str) -> MockResponse + Send + Sync + 'static) -> Self {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock server");
+    let base_url = format!("http://{}", listener.local_addr().expect("mock server address"));
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let handler: Arc<Handler> = Arc::new(handler);
+
+    let recorded = Arc::clone(&requests);
+    let server_url = base_url.clone();
+    thread::spawn(move || {
+      for stream in listener.incoming().flatten() {
+        if let Some(request) = read_request(&stream) {
+          let response = handler(&request, &server_url);
+          recorded.lock().expect("request log").push(request);
+          write_response(stream, &response);
+        }
+      }
+    });
+
+    Self { base_url, requests }
+  }
+
+  pub fn requests(&self) -> Vec<RecordedRequest> {
+    self.requests.lock().expect("request log").clone()
+  }
+}
+
+fn read_request(stream: &TcpStream) -> Option<RecordedRequest> {
+  let mut reader = BufReader::new(stream);
+  let mut request_line = String::new();
+  reader.read_line(&mut request_line).ok()?;
+  let mut parts = request_line.split_whitespace();
+  let method = parts.next()?.to_string();
+  let path = parts.next()?.to_string();
+
+  let mut headers = Vec::new();
+  loop {
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let line = line.trim_end();
+    if line.is_empty() {
+      break;
+    }
+    let (key, value) = line.split_once(':')?;
+    headers.push((key.trim().to_string(), value.trim().to_string()));
+  }
+
+  let length = headers
+    .iter()
+    .find(|(key, _)| key.eq_ignore_ascii_case("content-length"))
+    .and_then(|(_, value)| value.parse::<usize>().ok())
+    .unwrap_or(0);
+  let mut body = vec![0_u8; length];
+  reader.read_exact(&mut body).ok()?;
+
+  Some(RecordedRequest {
+    method,
+    path,
+    headers,
+    body,
+  })
+}
+
+fn write_response(mut stream: TcpStream, response: &MockResponse) {
+  let mut head = format!("HTTP/1.1 {} Mock\r\n", response.status);
+  for (key, value) in &response.headers {
+    head.push_str(&format!("{key}: {value}\r\n"));
+  }
+  head.push_str(&format!("Content-Length: {}\r\nConnection: close\r\n\r\n", response.body.len()));
+  let _ = stream.write_all(head.as_bytes());
+  let _ = stream.write_all(response.body.as_bytes());
+  let _ = stream.flush();
+}
@@ -0,0 +1,780 @@
+//! End-to-end preview/publish runs against a stand-in Gemini server, wired in through the
+//! `gemini_base_url` setting.
+
+mod mock_server;
+
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use crate::{app_state::AppState, db::repository, models::EnqueueIngestionRequest, test_support::TempDir};
+use mock_server::{MockResponse, MockServer, RecordedRequest};
+
+const UPLOADED_FILE_NAME: &str = "files/mock-upload";
+
+fn mock_extraction() -> Value {
+  json!({
+    "summary": "Mock summary of the capture.",
+    "key_points": ["The endpoint is configurable"],
+    "topics": ["Testing"],
+    "entities": [{ "name": "Mock Server", "kind": "tool" }],
+    "action_items": ["Ship it"],
+    "quotes": [],
+    "language": "en"
+  })
+}
+
+fn invalid_key_error() -> Value {
+  json!({
+    "error": {
+      "code": 400,
+      "status": "INVALID_ARGUMENT",
+      "message": "API key not valid. Please pass a valid API key.",
+      "details": [{ "@type": "type.googleapis.com/google.rpc.ErrorInfo", "reason": "API_KEY_INVALID" }]
+    }
+  })
+}
+
+/// Answers the Files API, `models.list` and `generateContent` the way Gemini does, with `generate_status` deciding
+/// whether generation succeeds.
+fn gemini_handler(generate_status: u16) -> impl Fn(&RecordedRequest, &str) -> MockResponse + Send + Sync {
+  move |request, base_url| match (request.method.as_str(), request.path.as_str()) {
+    ("POST", path) if path.starts_with("/upload/v1beta/files") => {
+      let mut response = MockResponse::json(200, "{}");
+      response
+        .headers
+        .push(("X-Goog-Upload-URL".to_string(), format!("{base_url}/upload-session")));
+      response
+    }
+    ("POST", "/upload-session") => MockResponse::json(
+      200,
+      json!({
+        "file": {
+          "name": UPLOADED_FILE_NAME,
+          "uri": format!("{base_url}/v1beta/{UPLOADED_FILE_NAME}"),
+          "mimeType": request.header("X-Goog-Upload-Header-Content-Type").unwrap_or("image/png"),
+          "state": "ACTIVE"
+        }
+      })
+      .to_string(),
+    ),
+    ("POST", path) if path.contains(":generateContent") && generate_status == 200 => MockResponse::json(
+      200,
+      json!({
+        "candidates": [{ "content": { "parts": [{ "text": mock_extraction().to_string() }] } }],
+        "usageMetadata": {
+          "promptTokenCount": 30,
+          "cachedContentTokenCount": 10,
+          "candidatesTokenCount": 12,
+          "thoughtsTokenCount": 5,
+          "totalTokenCount": 47
+        }
+      })
+      .to_string(),
+    ),
+    ("POST", path) if path.contains(":generateContent") && generate_status == 429 => {
+      let mut response = MockResponse::json(
+        429,
+        json!({
+          "error": {
+            "code": 429,
+            "status": "RESOURCE_EXHAUSTED",
+            "message": "mock rate limit",
+            "details": [{ "@type": "type.googleapis.com/google.rpc.RetryInfo", "retryDelay": "90s" }]
+          }
+        })
+        .to_string(),
+      );
+      response.headers.push(("Retry-After".to_string(), "120".to_string()));
+      response
+    }
+    ("POST", path) if path.contains(":generateContent") && generate_status == 400 => {
+      MockResponse::json(400, invalid_key_error().to_string())
+    }
+    ("POST", path) if path.contains(":generateContent") => {
+      MockResponse::json(generate_status, r#"{"error":{"message":"mock outage"}}"#)
+    }
+    ("DELETE", path) if path.starts_with(&format!("/v1beta/{UPLOADED_FILE_NAME}")) => MockResponse::json(200, "{}"),
+    ("GET", path) if path.starts_with("/v1beta/models?") && generate_status == 400 => {
+      MockResponse::json(400, invalid_key_error().to_string())
+    }
+    ("GET", path) if path.starts_with("/v1beta/models?") && !path.contains("pageToken=") => MockResponse::json(
+      200,
+      json!({
+        "models": [
+          {
+            "name": "models/gemini-2.5-flash",
+            "displayName": "Gemini 2.5 Flash",
+            "inputTokenLimit": 1048576,
+            "outputTokenLimit": 65536,
+            "supportedGenerationMethods": ["generateContent", "countTokens"]
+          },
+          {
+            "name": "models/text-embedding-004",
+            "displayName": "Text Embedding 004",
+            "supportedGenerationMethods": ["embedContent"]
+          }
+        ],
+        "nextPageToken": "page-2"
+      })
+      .to_string(),
+    ),
+    ("GET", path) if path.starts_with("/v1beta/models?") => MockResponse::json(
+      200,
+      json!({
+        "models": [{
+          "name": "models/gemma-3-27b-it",
+          "displayName": "Gemma 3 27B",
+          "inputTokenLimit": 131072,
+          "outputTokenLimit": 8192,
+          "supportedGenerationMethods": ["generateContent"]
+        }]
+      })
+      .to_string(),
+    ),
+    _ => MockResponse::json(404, r#"{"error":{"message":"not found"}}"#),
+  }
+}
+
+struct Fixture {
+  state: AppState,
+  server: MockServer,
+  vault: PathBuf,
+  sources: PathBuf,
+  _dir: TempDir,
+}
+
+fn fixture(name: &str) -> Fixture {
+  fixture_with_server(name, MockServer::start(gemini_handler(200)))
+}
+
+fn fixture_with_server(name: &str, server: MockServer) -> Fixture {
+  // The keychain entry wins when present; either way the key only ever reaches the mock server.
+  std::env::set_var("GEMINI_API_KEY", "test-key");
+  let dir = TempDir::new(name);
+  let vault = dir.path().join("vault");
+  let sources = dir.path().join("sources");
+  std::fs::create_dir_all(&vault).expect("create vault");
+  std::fs::create_dir_all(&sources).expect("create sources");
+
+  let state = AppState::open(&dir.path().join("app")).expect("open app state");
+  let conn = state.conn().expect("connection");
+  let mut settings = repository::get_settings(&conn).expect("settings");
+  settings.vault_path = vault.to_string_lossy().to_string();
+  settings.write_mode = "filesystem_only".to_string();
+  settings.note_language = "en".to_string();
+  settings.gemini_base_url = format!("{}/", server.base_url);
+  repository::save_settings(&conn, &settings).expect("save settings");
+  repository::add_allowed_source_root(&conn, &sources.to_string_lossy()).expect("allow sources");
+
+  Fixture {
+    state,
+    server,
+    vault,
+    sources,
+    _dir: dir,
+  }
+}
+
+fn enqueue(fixture: &Fixture, file_paths: Vec<String>, text_content: Option<&str>) -> String {
+  crate::enqueue_job(
+    &fixture.state,
+    EnqueueIngestionRequest {
+      file_paths,
+      note_title: Some("Mock Capture".to_string()),
+      text_content: text_content.map(str::to_string),
+      ingest_mode: Some("copy".to_string()),
+      note_language: None,
+    },
+  )
+  .expect("enqueue job")
+  .job_id
+}
+
+fn job_status(fixture: &Fixture, job_id: &str) -> String {
+  let conn = fixture.state.conn().unwrap();
+  repository::find_job_with_assets(&conn, job_id).unwrap().expect("job").job.status
+}
+
+fn generate_requests(fixture: &Fixture) -> Vec<RecordedRequest> {
+  fixture
+    .server
+    .requests()
+    .into_iter()
+    .filter(|request| request.path.contains(":generateContent"))
+    .collect()
+}
+
+#[test]
+fn preview_and_publish_go_through_the_configured_endpoint() {
+  let fixture = fixture("text-flow");
+  let job_id = enqueue(&fixture, Vec::new(), Some("Notes from the integration test."));
+
+  let preview = crate::preview_job_note(&fixture.state, &job_id).expect("preview");
+  assert!(preview.markdown.contains("Mock summary of the capture."));
+  assert!(preview.markdown.contains("[[Mock Server]]"));
+
+  let calls = generate_requests(&fixture);
+  assert_eq!(calls.len(), 1);
+  let settings = repository::get_settings(&fixture.state.conn().unwrap()).unwrap();
+  assert_eq!(calls[0].path, format!("/v1beta/models/{}:generateContent", settings.gemini_model));
+  assert_eq!(calls[0].header("x-goog-api-key"), Some("test-key"));
+  assert!(calls[0].body_text().contains("Notes from the integration test."));
+
+  let published = crate::publish_job_note(&fixture.state, &job_id).expect("publish");
+  assert_eq!(published.method, "filesystem");
+  assert!(Path::new(&published.note_path).starts_with(&fixture.vault));
+  let written = std::fs::read_to_string(&published.note_path).expect("read published note");
+  assert_eq!(written, preview.markdown);
+  assert_eq!(generate_requests(&fixture).len(), 1, "publish should reuse the cached extraction");
+
+  let runs = repository::list_prompt_runs(&fixture.state.conn().unwrap(), &job_id).expect("prompt runs");
+  assert_eq!(runs.len(), 1);
+  assert_eq!(runs[0].total_tokens, Some(47));
+  assert!(runs[0].error.is_none());
+}
+
+#[test]
+fn captured_text_is_stored_as_a_hashed_text_asset() {
+  let fixture = fixture("text-capture");
+  let job_id = enqueue(&fixture, Vec::new(), Some("  Buy milk, call the plumber.\n"));
+
+  let conn = fixture.state.conn().unwrap();
+  let job = repository::find_job_with_assets(&conn, &job_id).unwrap().expect("job");
+  assert_eq!(job.job.status, "queued");
+  assert_eq!(job.assets.len(), 1);
+  let asset = &job.assets[0];
+  assert_eq!(asset.media_type, "text");
+  assert!(Path::new(&asset.storage_path).starts_with(&fixture.state.media_root));
+  assert_eq!(std::fs::read_to_string(&asset.storage_path).unwrap(), "Buy milk, call the plumber.");
+  assert_eq!(asset.size_bytes, 27);
+  assert_eq!(crate::ingestion::hash_asset(asset, None).unwrap(), asset.sha256);
+}
+
+#[test]
+fn calls_are_priced_and_totalled_per_job_and_month() {
+  let fixture = fixture("cost-flow");
+  let conn = fixture.state.conn().unwrap();
+  let mut settings = repository::get_settings(&conn).unwrap();
+  settings.gemini_model = "priced-test-model".to_string();
+  repository::save_settings(&conn, &settings).unwrap();
+  let price = crate::models::ModelPrice {
+    model: "gemini/priced-test-model".to_string(),
+    input_usd_per_mtok: 1.0,
+    output_usd_per_mtok: 4.0,
+    cached_input_usd_per_mtok: 0.25,
+    updated_at: 0,
+  };
+  repository::save_model_price(&conn, &price, 1).expect("save price");
+  let job_id = enqueue(&fixture, Vec::new(), Some("A capture worth pricing."));
+
+  crate::preview_job_note(&fixture.state, &job_id).expect("preview");
+
+  let runs = repository::list_prompt_runs(&conn, &job_id).expect("prompt runs");
+  assert_eq!(runs[0].cached_tokens, Some(10));
+  assert_eq!(runs[0].response_tokens, Some(17), "thinking tokens count as output");
+  // 20 uncached input at $1, 10 cached at $0.25 and 17 output at $4 per million tokens.
+  let expected = (20.0 + 2.5 + 68.0) / 1_000_000.0;
+  assert!((runs[0].cost_usd.expect("cost") - expected).abs() < 1e-12);
+
+  let per_job = repository::token_usage_by_job(&conn).expect("job usage");
+  assert_eq!(per_job.len(), 1);
+  assert_eq!(per_job[0].job_id, job_id);
+  assert_eq!(per_job[0].totals.calls, 1);
+  let per_month = repository::token_usage_by_month(&conn).expect("monthly usage");
+  assert_eq!(per_month.len(), 1);
+  assert_eq!(per_month[0].totals.total_tokens, 47);
+  assert!((per_month[0].totals.cost_usd - expected).abs() < 1e-12);
+}
+
+#[test]
+fn media_is_uploaded_referenced_and_deleted() {
+  let fixture = fixture("media-flow");
+  let image_path = fixture.sources.join("whiteboard.png");
+  image::RgbImage::from_pixel(32, 32, image::Rgb([40, 120, 200]))
+    .save(&image_path)
+    .expect("write test image");
+  let job_id = enqueue(&fixture, vec![image_path.to_string_lossy().to_string()], None);
+
+  let preview = crate::preview_job_note(&fixture.state, &job_id).expect("preview");
+  assert!(preview.markdown.contains("Mock summary of the capture."));
+
+  let requests = fixture.server.requests();
+  let start = requests
+    .iter()
+    .find(|request| request.path.starts_with("/upload/v1beta/files"))
+    .expect("upload started");
+  assert_eq!(start.header("X-Goog-Upload-Protocol"), Some("resumable"));
+  let finalize = requests
+    .iter()
+    .find(|request| request.path == "/upload-session")
+    .expect("upload finalized");
+  assert_eq!(finalize.header("X-Goog-Upload-Command"), Some("upload, finalize"));
+  assert!(!finalize.body.is_empty());
+
+  let generate = &generate_requests(&fixture)[0];
+  let body: Value = serde_json::from_slice(&generate.body).expect("generate body");
+  let file_uri = format!("{}/v1beta/{UPLOADED_FILE_NAME}", fixture.server.base_url);
+  assert!(body["contents"][0]["parts"]
+    .as_array()
+    .expect("parts")
+    .iter()
+    .any(|part| part["file_data"]["file_uri"] == file_uri.as_str()));
+
+  assert!(requests
+    .iter()
+    .any(|request| request.method == "DELETE" && request.path.starts_with(&format!("/v1beta/{UPLOADED_FILE_NAME}"))));
+  for request in requests.iter().filter(|request| request.path != "/upload-session") {
+    assert!(!request.path.contains("key="), "key leaked into {}", request.path);
+    assert_eq!(request.header("x-goog-api-key"), Some("test-key"), "{} {}", request.method, request.path);
+  }
+}
+
+#[test]
+fn failed_call_is_audited_and_the_note_falls_back() {
+  let fixture = fixture_with_server("failure-flow", MockServer::start(gemini_handler(503)));
+  let job_id = enqueue(&fixture, Vec::new(), Some("This capture meets an outage."));
+
+  let preview = crate::preview_job_note(&fixture.state, &job_id).expect("preview");
+  assert!(!preview.markdown.contains("Mock summary of the capture."));
+  assert!(preview.markdown.contains("Gemini summary unavailable"));
+
+  let runs = repository::list_prompt_runs(&fixture.state.conn().unwrap(), &job_id).expect("prompt runs");
+  assert_eq!(runs.len(), 1);
+  assert!(runs[0].error.as_deref().is_some_and(|error| error.contains("503")));
+
+  let published = crate::publish_job_note(&fixture.state, &job_id).expect("publish");
+  assert!(Path::new(&published.note_path).exists());
+  assert_eq!(generate_requests(&fixture).len(), 2, "a failed extraction is not cached");
+}
+
+#[test]
+fn settings_reject_an_endpoint_without_http_scheme() {
+  let fixture = fixture("settings");
+  let conn = fixture.state.conn().unwrap();
+  let mut settings = repository::get_settings(&conn).unwrap();
+  assert_eq!(settings.gemini_base_url, fixture.server.base_url, "trailing slash is trimmed");
+
+  settings.gemini_base_url = "generativelanguage.googleapis.com".to_string();
+  let error = repository::save_settings(&conn, &settings).expect_err("scheme is required");
+  assert!(error.contains("gemini_base_url"));
+}
+
+#[test]
+fn saved_settings_can_drop_source_folders_but_not_add_them() {
+  let fixture = fixture("source-roots");
+  let conn = fixture.state.conn().unwrap();
+  let mut settings = repository::get_settings(&conn).unwrap();
+  assert_eq!(settings.allowed_source_roots.len(), 1);
+
+  settings.allowed_source_roots.push("/".to_string());
+  let error = repository::save_settings(&conn, &settings).expect_err("webview cannot widen the allowlist");
+  assert!(error.contains("folder picker"));
+
+  settings.allowed_source_roots.clear();
+  repository::save_settings(&conn, &settings).expect("removing a folder is allowed");
+  assert!(repository::get_settings(&conn).unwrap().allowed_source_roots.is_empty());
+}
+
+#[test]
+fn jobs_wait_for_budget_and_resume_when_the_cap_is_raised() {
+  let fixture = fixture("budget-flow");
+  let conn = fixture.state.conn().unwrap();
+  let mut settings = repository::get_settings(&conn).unwrap();
+  settings.daily_token_budget = 40;
+  repository::save_settings(&conn, &settings).unwrap();
+
+  let first = enqueue(&fixture, Vec::new(), Some("This capture spends the daily budget."));
+  crate::preview_job_note(&fixture.state, &first).expect("preview");
+  let status = crate::budget::status(&conn, &settings, crate::time_now_ms()).expect("budget status");
+  assert_eq!(status.today.total_tokens, 47);
+  assert!(status.exceeded.as_deref().is_some_and(|reason| reason.contains("daily token budget")));
+
+  let second = enqueue(&fixture, Vec::new(), Some("This capture has to wait."));
+  crate::runner::tick(&fixture.state.db_pool).expect("runner pass");
+  assert_eq!(job_status(&fixture, &second), "waiting_budget");
+  let events = repository::list_job_events(&conn, &second).expect("job events");
+  assert_eq!(events[0].kind, "budget_wait");
+  assert_eq!(generate_requests(&fixture).len(), 1, "no call is made over budget");
+
+  let preview = crate::preview_job_note(&fixture.state, &second).expect("preview over budget");
+  assert!(preview.markdown.contains("Gemini summary unavailable"));
+  assert_eq!(generate_requests(&fixture).len(), 1, "preview is paused too");
+
+  settings.daily_token_budget = 0;
+  repository::save_settings(&conn, &settings).unwrap();
+  crate::runner::tick(&fixture.state.db_pool).expect("runner pass");
+  assert_eq!(job_status(&fixture, &second), "completed");
+  let kinds: Vec<_> = repository::list_job_events(&conn, &second)
+    .expect("job events")
+    .into_iter()
+    .map(|event| event.kind)
+    .collect();
+  assert!(kinds.contains(&"budget_resumed".to_string()));
+  assert_eq!(generate_requests(&fixture).len(), 2);
+}
+
+#[test]
+fn jobs_from_before_the_runner_are_not_extracted_automatically() {
+  let fixture = fixture("runner-upgrade");
+  let job_id = enqueue(&fixture, Vec::new(), Some("Captured before the upgrade."));
+  let conn = fixture.state.conn().unwrap();
+  conn
+    .execute("UPDATE ingestion_job SET auto_extract = 0 WHERE id = ?1", [&job_id])
+    .unwrap();
+
+  crate::runner::tick(&fixture.state.db_pool).expect("runner pass");
+
+  assert_eq!(job_status(&fixture, &job_id), "queued");
+  assert!(generate_requests(&fixture).is_empty());
+}
+
+#[test]
+fn a_previewed_job_is_not_billed_again_by_the_runner() {
+  let fixture = fixture("runner-claim");
+  let job_id = enqueue(&fixture, Vec::new(), Some("Previewed while queued."));
+  let conn = fixture.state.conn().unwrap();
+
+  assert!(repository::claim_queued_job(&conn, &job_id, crate::time_now_ms()).unwrap());
+  let preview = crate::preview_job_note(&fixture.state, &job_id).expect("preview");
+  assert!(!preview.markdown.contains("Mock summary of the capture."), "a claimed job is not extracted twice");
+  assert!(generate_requests(&fixture).is_empty());
+  repository::release_claimed_job(&conn, &job_id, "queued", crate::time_now_ms()).unwrap();
+
+  crate::preview_job_note(&fixture.state, &job_id).expect("preview");
+  assert_eq!(job_status(&fixture, &job_id), "queued", "the preview gives the job back");
+  crate::runner::tick(&fixture.state.db_pool).expect("runner pass");
+
+  assert_eq!(job_status(&fixture, &job_id), "completed");
+  assert_eq!(generate_requests(&fixture).len(), 1, "the runner reuses the previewed extraction");
+}
+
+#[test]
+fn rate_limited_jobs_are_retried_after_the_requested_delay() {
+  let fixture = fixture_with_server("rate-limit", MockServer::start(gemini_handler(429)));
+  let job_id = enqueue(&fixture, Vec::new(), Some("This capture meets a rate limit."));
+
+  crate::runner::tick(&fixture.state.db_pool).expect("runner pass");
+  assert_eq!(job_status(&fixture, &job_id), "queued");
+  let conn = fixture.state.conn().unwrap();
+  let events = repository::list_job_events(&conn, &job_id).expect("job events");
+  assert_eq!(events[0].kind, "retry_scheduled");
+  assert!(events[0].message.contains("retrying in 120s"), "Retry-After wins: {}", events[0].message);
+  let runs = repository::list_prompt_runs(&conn, &job_id).expect("prompt runs");
+  assert!(runs[0].error.as_deref().is_some_and(|error| error.contains("429")));
+
+  crate::runner::tick(&fixture.state.db_pool).expect("runner pass");
+  assert_eq!(generate_requests(&fixture).len(), 1, "the job waits out the delay");
+  assert_eq!(repository::list_ready_job_ids(&conn, crate::time_now_ms()).unwrap(), Vec::<String>::new());
+  let later = crate::time_now_ms() + 121_000;
+  assert_eq!(repository::list_ready_job_ids(&conn, later).unwrap(), vec![job_id]);
+}
+
+#[test]
+fn absurd_retry_delays_are_capped() {
+  let server = MockServer::start(|request: &RecordedRequest, _: &str| {
+    assert!(request.path.contains(":generateContent"));
+    let body = json!({
+      "error": {
+        "code": 429,
+        "status": "RESOURCE_EXHAUSTED",
+        "details": [{ "@type": "type.googleapis.com/google.rpc.RetryInfo", "retryDelay": "1e300s" }]
+      }
+    });
+    MockResponse::json(429, body.to_string())
+  });
+  let fixture = fixture_with_server("retry-cap", server);
+  let job_id = enqueue(&fixture, Vec::new(), Some("This capture is told to come back never."));
+
+  crate::runner::tick(&fixture.state.db_pool).expect("runner pass");
+
+  assert_eq!(job_status(&fixture, &job_id), "queued");
+  let events = repository::list_job_events(&fixture.state.conn().unwrap(), &job_id).expect("job events");
+  assert!(events[0].message.contains("retrying in 3600s"), "{}", events[0].message);
+}
+
+#[test]
+fn an_invalid_key_fails_the_job_without_retrying() {
+  let fixture = fixture_with_server("invalid-key", MockServer::start(gemini_handler(400)));
+  let job_id = enqueue(&fixture, Vec::new(), Some("This capture has the wrong key."));
+
+  crate::runner::tick(&fixture.state.db_pool).expect("runner pass");
+  assert_eq!(job_status(&fixture, &job_id), "failed");
+  let events = repository::list_job_events(&fixture.state.conn().unwrap(), &job_id).expect("job events");
+  assert_eq!(events[0].kind, "extraction_failed");
+  assert!(events[0].message.contains("API key not valid"));
+  assert_eq!(generate_requests(&fixture).len(), 1);
+}
+
+#[test]
+fn key_check_records_the_result_and_lists_generation_models() {
+  let fixture = fixture("key-check");
+
+  let check = crate::check_gemini_key(&fixture.state).expect("key check");
+  assert!(check.validation.ok);
+  let ids = check.models.iter().map(|model| model.id.as_str()).collect::<Vec<_>>();
+  assert_eq!(ids, ["gemini-2.5-flash", "gemma-3-27b-it"], "embedding models are left out, pages are followed");
+  assert_eq!(check.models[0].input_token_limit, Some(1_048_576));
+  assert!(check.models[0].assumed_input_modalities.contains(&"video".to_string()));
+
+  let conn = fixture.state.conn().unwrap();
+  assert_eq!(repository::list_gemini_models(&conn).unwrap().len(), 2);
+  let recorded = repository::find_key_validation(&conn, "gemini").unwrap().expect("validation");
+  assert!(recorded.ok);
+  assert_eq!(recorded.validated_at, check.validation.validated_at);
+  let request = &fixture.server.requests()[0];
+  assert!(!request.path.contains("key="), "key leaked into {}", request.path);
+  assert_eq!(request.header("x-goog-api-key"), Some("test-key"));
+
+  let mut settings = repository::get_settings(&conn).unwrap();
+  settings.gemini_model = "gemini-9-imaginary".to_string();
+  let error = repository::save_settings(&conn, &settings).expect_err("unlisted model");
+  assert!(error.contains("not available"), "{error}");
+  settings.gemini_model = "gemma-3-27b-it".to_string();
+  repository::save_settings(&conn, &settings).expect("listed model");
+}
+
+#[test]
+fn a_rejected_key_is_recorded_and_keeps_the_previous_model_list() {
+  let fixture = fixture_with_server("key-rejected", MockServer::start(gemini_handler(400)));
+  let mut conn = fixture.state.conn().unwrap();
+  let previous = crate::models::GeminiModelInfo {
+    id: "gemini-2.0-flash".to_string(),
+    display_name: "Gemini 2.0 Flash".to_string(),
+    description: None,
+    input_token_limit: None,
+    output_token_limit: None,
+    assumed_input_modalities: vec!["text".to_string()],
+  };
+  repository::replace_gemini_models(&mut conn, &[previous], 1).unwrap();
+
+  let check = crate::check_gemini_key(&fixture.state).expect("key check");
+  assert!(!check.validation.ok);
+  assert!(check.models.is_empty());
+  let message = check.validation.message.expect("failure message");
+  assert!(message.starts_with("key rejected"), "{message}");
+  let recorded = repository::find_key_validation(&conn, "gemini").unwrap().expect("validation");
+  assert_eq!(recorded.message.as_deref(), Some(message.as_str()));
+  assert_eq!(repository::list_gemini_models(&conn).unwrap().len(), 1);
+}
+
+#[test]
+fn retention_counts_from_the_publish_time() {
+  let fixture = fixture("retention");
+  let job_id = enqueue(&fixture, Vec::new(), Some("A capture kept for a while."));
+  let conn = fixture.state.conn().unwrap();
+  // An old capture that was only published just now.
+  conn
+    .execute("UPDATE ingestion_job SET created_at = 0 WHERE id = ?1", [&job_id])
+    .unwrap();
+  assert!(repository::list_evictable_assets(&conn, i64::MAX).unwrap().is_empty(), "unpublished jobs are kept");
+
+  crate::publish_job_note(&fixture.state, &job_id).expect("publish");
+  let published_at = conn
+    .query_row("SELECT MAX(created_at) FROM obsidian_note WHERE job_id = ?1", [&job_id], |row| {
+      row.get::<_, i64>(0)
+    })
+    .unwrap();
+  assert!(repository::list_evictable_assets(&conn, published_at).unwrap().is_empty());
+  let evictable = repository::list_evictable_assets(&conn, published_at + 1).unwrap();
+  assert_eq!(evictable.len(), 1);
+  assert_eq!(evictable[0].job_id, job_id);
+}
+
+#[test]
+fn evicted_assets_take_their_thumbnails_with_them() {
+  let fixture = fixture("thumbnails");
+  let conn = fixture.state.conn().unwrap();
+  let mut settings = repository::get_settings(&conn).unwrap();
+  settings.media_retention_days = 1;
+  repository::save_settings(&conn, &settings).unwrap();
+  let image_path = fixture.sources.join("photo.png");
+  image::RgbImage::from_pixel(64, 48, image::Rgb([40, 120, 200]))
+    .save(&image_path)
+    .expect("write test image");
+  let old_job = enqueue(&fixture, vec![image_path.to_string_lossy().to_string()], None);
+  let kept_job = enqueue(&fixture, vec![image_path.to_string_lossy().to_string()], None);
+  let thumbnail_of = |job_id: &str| {
+    let job = repository::find_job_with_assets(&conn, job_id).unwrap().expect("job");
+    job.assets[0].thumbnail_path.clone()
+  };
+  let old_thumbnail = thumbnail_of(&old_job).expect("thumbnail created");
+  let kept_thumbnail = thumbnail_of(&kept_job).expect("thumbnail created");
+  assert_ne!(old_thumbnail, kept_thumbnail, "each job gets its own thumbnail");
+
+  crate::publish_job_note(&fixture.state, &old_job).expect("publish");
+  conn
+    .execute("UPDATE obsidian_note SET created_at = 0 WHERE job_id = ?1", [&old_job])
+    .unwrap();
+  let report = crate::collect_garbage(&fixture.state, false).expect("collect garbage");
+
+  assert_eq!(report.evicted_assets, 1);
+  assert!(!std::path::Path::new(&old_thumbnail).exists());
+  assert_eq!(thumbnail_of(&old_job), None);
+  assert!(std::path::Path::new(&kept_thumbnail).exists());
+}
+
+/// Writes silence as 8 kHz, 8-bit mono PCM.
+fn write_silent_wav(path: &std::path::Path, seconds: u32) {
+  let data_len = 8_000 * seconds;
+  let mut bytes = Vec::with_capacity(44 + data_len as usize);
+  bytes.extend_from_slice(b"RIFF");
+  bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+  bytes.extend_from_slice(b"WAVEfmt ");
+  bytes.extend_from_slice(&16_u32.to_le_bytes());
+  bytes.extend_from_slice(&[1, 0, 1, 0]);
+  bytes.extend_from_slice(&8_000_u32.to_le_bytes());
+  bytes.extend_from_slice(&8_000_u32.to_le_bytes());
+  bytes.extend_from_slice(&[1, 0, 8, 0]);
+  bytes.extend_from_slice(b"data");
+  bytes.extend_from_slice(&data_len.to_le_bytes());
+  bytes.resize(44 + data_len as usize, 128);
+  std::fs::write(path, bytes).expect("write test recording");
+}
+
+#[test]
+fn long_recordings_are_summarized_chunk_by_chunk() {
+  let fixture = fixture("chunks");
+  let recording = fixture.sources.join("meeting.wav");
+  write_silent_wav(&recording, 11 * 60);
+  let job_id = enqueue(&fixture, vec![recording.to_string_lossy().to_string()], None);
+
+  crate::preview_job_note(&fixture.state, &job_id).expect("preview");
+
+  let bodies = generate_requests(&fixture)
+    .iter()
+    .map(|request| request.body_text())
+    .collect::<Vec<_>>();
+  assert_eq!(bodies.len(), 2, "one call per chunk");
+  assert!(bodies[0].contains("meeting.wav (part 1/2, 00:00:00-00:10:00)"));
+  assert!(bodies[1].contains("meeting.wav (part 2/2, 00:09:45-00:11:00)"));
+  let job = repository::find_job_with_assets(&fixture.state.conn().unwrap(), &job_id).unwrap().expect("job");
+  assert!(job.chunks.iter().all(|chunk| !bodies.iter().any(|body| body.contains(&chunk.storage_path))));
+  let conn = fixture.state.conn().unwrap();
+  assert_eq!(repository::list_prompt_runs(&conn, &job_id).unwrap().len(), 2);
+  let normalized = conn
+    .query_row("SELECT normalized_output FROM extraction_result WHERE job_id = ?1", [&job_id], |row| {
+      row.get::<_, String>(0)
+    })
+    .expect("one merged extraction");
+  let summary = serde_json::from_str::<serde_json::Value>(&normalized).unwrap()["summary"].clone();
+  assert_eq!(summary.as_str().unwrap().matches("\n\n").count(), 1, "one paragraph per chunk");
+}
+
+#[test]
+fn discarding_a_quarantined_move_keeps_the_source() {
+  let fixture = fixture("quarantine-discard");
+  let disguised = fixture.sources.join("holiday.png");
+  write_silent_wav(&disguised, 1);
+  crate::enqueue_job(
+    &fixture.state,
+    EnqueueIngestionRequest {
+      file_paths: vec![disguised.to_string_lossy().to_string()],
+      note_title: None,
+      text_content: None,
+      ingest_mode: Some("move".to_string()),
+      note_language: None,
+    },
+  )
+  .expect("enqueue job");
+  let conn = fixture.state.conn().unwrap();
+  let item = repository::list_quarantine_items(&conn, Some("pending")).unwrap().pop().expect("quarantined");
+  assert!(disguised.exists(), "quarantine holds a copy, not the user's file");
+
+  crate::discard_quarantined(&fixture.state, item.id).expect("discard");
+
+  assert!(disguised.exists());
+  assert!(!std::path::Path::new(&item.storage_path).exists());
+}
+
+#[test]
+fn a_derivative_is_sent_under_the_original_file_name() {
+  let fixture = fixture("derivative-label");
+  let conn = fixture.state.conn().unwrap();
+  let mut settings = repository::get_settings(&conn).unwrap();
+  settings.image_derivatives_enabled = true;
+  settings.image_max_dimension = 256;
+  repository::save_settings(&conn, &settings).unwrap();
+  let image_path = fixture.sources.join("large-photo.png");
+  image::RgbImage::from_pixel(800, 600, image::Rgb([200, 80, 40]))
+    .save(&image_path)
+    .expect("write test image");
+  let job_id = enqueue(&fixture, vec![image_path.to_string_lossy().to_string()], None);
+  let job = repository::find_job_with_assets(&conn, &job_id).unwrap().expect("job");
+  let derivative_path = job.assets[0].derivative_path.clone().expect("derivative created");
+
+  crate::preview_job_note(&fixture.state, &job_id).expect("preview");
+
+  let body = generate_requests(&fixture)[0].body_text();
+  assert!(body.contains(&format!("Attached file: {}", job.assets[0].original_path).replace('\\', "\\\\")));
+  assert!(!body.contains(&derivative_path), "storage paths stay out of the prompt");
+}
+
+#[test]
+fn a_new_vault_folder_does_not_invalidate_a_preview() {
+  let fixture = fixture("vault-change");
+  let job_id = enqueue(&fixture, Vec::new(), Some("Previewed before the vault changed."));
+
+  crate::preview_job_note(&fixture.state, &job_id).expect("preview");
+  // Publishing another capture first is the usual way the vault gains its "AI Captures" folder.
+  std::fs::create_dir_all(fixture.vault.join("AI Captures")).expect("create vault folder");
+  crate::publish_job_note(&fixture.state, &job_id).expect("publish");
+
+  assert_eq!(generate_requests(&fixture).len(), 1, "publish should reuse the previewed extraction");
+}
+
+#[test]
+fn a_job_claimed_for_a_preview_is_not_requeued_after_a_crash() {
+  let fixture = fixture("claim-crash");
+  let job_id = enqueue(&fixture, Vec::new(), Some("Published, then previewed again."));
+  crate::publish_job_note(&fixture.state, &job_id).expect("publish");
+  let conn = fixture.state.conn().unwrap();
+  let status = job_status(&fixture, &job_id);
+
+  // A crash between the claim and the release leaves the job `processing` until the next start.
+  let claimed = repository::claim_job_for_extraction(&conn, &job_id, crate::time_now_ms()).unwrap();
+  assert_eq!(claimed.as_deref(), Some(status.as_str()));
+  assert_eq!(repository::requeue_interrupted_jobs(&conn, crate::time_now_ms()).unwrap(), 1);
+
+  assert_eq!(job_status(&fixture, &job_id), status, "the job gets its own status back");
+  crate::runner::tick(&fixture.state.db_pool).expect("runner pass");
+  assert_eq!(generate_requests(&fixture).len(), 1, "the runner does not extract the job again");
+}
+
+#[test]
+fn a_fully_quarantined_batch_waits_for_approval_instead_of_sending_nothing() {
+  let fixture = fixture("quarantine-all");
+  let disguised = fixture.sources.join("holiday.png");
+  write_silent_wav(&disguised, 1);
+  let job_id = enqueue(&fixture, vec![disguised.to_string_lossy().to_string()], None);
+
+  assert_eq!(job_status(&fixture, &job_id), "failed");
+  crate::runner::tick(&fixture.state.db_pool).expect("runner pass");
+  assert!(generate_requests(&fixture).is_empty(), "a job without assets is never sent");
+
+  let conn = fixture.state.conn().unwrap();
+  let item = repository::list_quarantine_items(&conn, Some("pending")).unwrap().pop().expect("quarantined");
+  crate::approve_quarantined(&fixture.state, item.id).expect("approve");
+  assert!(repository::update_job_status(&conn, &job_id, "queued", crate::time_now_ms()).unwrap());
+  crate::runner::tick(&fixture.state.db_pool).expect("runner pass");
+  assert_eq!(generate_requests(&fixture).len(), 1, "the approved file is extracted once the job is retried");
+}
+
+#[test]
+fn an_unverifiable_asset_is_reported_without_aborting_the_check() {
+  let fixture = fixture("verify-unreadable");
+  enqueue(&fixture, Vec::new(), Some("Stored in the clear."));
+  let broken_job = enqueue(&fixture, Vec::new(), Some("Claims to be encrypted."));
+  let conn = fixture.state.conn().unwrap();
+  // Without a matching key in the keychain the second asset cannot be read back.
+  conn
+    .execute("UPDATE media_asset SET encrypted = 1 WHERE job_id = ?1", [&broken_job])
+    .unwrap();
+
+  let report = crate::verify_stored_media(&fixture.state, false).expect("verify");
+
+  assert_eq!(report.checked, 2);
+  assert_eq!(report.healthy, 1);
+  assert_eq!(report.issues.len(), 1);
+  assert_eq!(report.issues[0].job_id, broken_job);
+}
@@ -0,0 +1,172 @@
+/// Setting value meaning "write the note in whatever language the captured content is in".
+pub const SAME_AS_SOURCE: &str = "source";
+
+/// Fixed text of a note: section headings and the placeholder shown when no extraction is available.
+pub struct NoteLabels {
+  pub summary: &'static str,
+  pub key_insights: &'static str,
+  pub action_items: &'static str,
+  pub quotes: &'static str,
+  pub entities: &'static str,
+  pub source_files: &'static str,
+  pub fallback: [&'static str; 3],
+  pub text_capture: &'static str,
+  pub subtitles: &'static str,
+}
+
+struct Language {
+  code: &'static str,
+  name: &'static str,
+  labels: NoteLabels,
+}
+
+const LANGUAGES: [Language; 6] = [
+  Language {
+    code: "en",
+    name: "English",
+    labels: NoteLabels {
+      summary: "Summary",
+      key_insights: "Key Insights",
+      action_items: "Action Items",
+      quotes: "Quotes",
+      entities: "Entities",
+      source_files: "Source Files",
+      fallback: [
+        "Gemini summary unavailable; set Gemini API key in Runtime Settings.",
+        "Obsidian write path is write-mode aware.",
+        "Media assets are persisted in local app storage.",
+      ],
+      text_capture: "Text capture",
+      subtitles: "subtitles",
+    },
+  },
+  Language {
+    code: "pt",
+    name: "Portuguese",
+    labels: NoteLabels {
+      summary: "Resumo",
+      key_insights: "Principais Insights",
+      action_items: "Ações",
+      quotes: "Citações",
+      entities: "Entidades",
+      source_files: "Arquivos de Origem",
+      fallback: [
+        "Resumo do Gemini indisponível; configure a chave da API do Gemini em Runtime Settings.",
+        "A escrita no Obsidian respeita o modo de escrita configurado.",
+        "As mídias ficam guardadas no armazenamento local do app.",
+      ],
+      text_capture: "Captura de texto",
+      subtitles: "legendas",
+    },
+  },
+  Language {
+    code: "es",
+    name: "Spanish",
+    labels: NoteLabels {
+      summary: "Resumen",
+      key_insights: "Ideas Clave",
+      action_items: "Acciones",
+      quotes: "Citas",
+      entities: "Entidades",
+      source_files: "Archivos de Origen",
+      fallback: [
+        "Resumen de Gemini no disponible; configura la clave de API de Gemini en Runtime Settings.",
+        "La escritura en Obsidian respeta el modo de escritura configurado.",
+        "Los archivos multimedia se guardan en el almacenamiento local de la app.",
+      ],
+      text_capture: "Captura de texto",
+      subtitles: "subtítulos",
+    },
+  },
+  Language {
+    code: "fr",
+    name: "French",
+    labels: NoteLabels {
+      summary: "Résumé",
+      key_insights: "Points Clés",
+      action_items: "Actions",
+      quotes: "Citations",
+      entities: "Entités",
+      source_files: "Fichiers Sources",
+      fallback: [
+        "Résumé Gemini indisponible ; configurez la clé API Gemini dans Runtime Settings.",
+        "L'écriture dans Obsidian respecte le mode d'écriture configuré.",
+        "Les médias sont conservés dans le stockage local de l'application.",
+      ],
+      text_capture: "Capture de texte",
+      subtitles: "sous-titres",
+    },
+  },
+  Language {
+    code: "de",
+    name: "German",
+    labels: NoteLabels {
+      summary: "Zusammenfassung",
+      key_insights: "Wichtigste Erkenntnisse",
+      action_items: "Aufgaben",
+      quotes: "Zitate",
+      entities: "Entitäten",
+      source_files: "Quelldateien",
+      fallback: [
+        "Gemini-Zusammenfassung nicht verfügbar; Gemini-API-Schlüssel in Runtime Settings hinterlegen.",
+        "Das Schreiben in Obsidian folgt dem eingestellten Schreibmodus.",
+        "Medien werden im lokalen App-Speicher abgelegt.",
+      ],
+      text_capture: "Textaufnahme",
+      subtitles: "Untertitel",
+    },
+  },
+  Language {
+    code: "it",
+    name: "Italian",
+    labels: NoteLabels {
+      summary: "Riepilogo",
+      key_insights: "Punti Chiave",
+      action_items: "Azioni",
+      quotes: "Citazioni",
+      entities: "Entità",
+      source_files: "File di Origine",
+      fallback: [
+        "Riepilogo Gemini non disponibile; imposta la chiave API di Gemini in Runtime Settings.",
+        "La scrittura in Obsidian rispetta la modalità di scrittura configurata.",
+        "I contenuti multimediali sono salvati nell'archivio locale dell'app.",
+      ],
+      text_capture: "Acquisizione di testo",
+      subtitles: "sottotitoli",
+    },
+  },
+];
+
+fn find(code: &str) -> Option<&'static Language> {
+  let primary = code.split(['-', '_']).next().unwrap_or_default();
+  LANGUAGES
+    .iter()
+    .find(|language| language.code.eq_ignore_ascii_case(primary))
+}
+
+/// Accepts `source` or one of the supported language codes, returning the stored form.
+pub fn normalize(value: &str) -> Result<String, String> {
+  let value = value.trim().to_ascii_lowercase();
+  if value == SAME_AS_SOURCE || LANGUAGES.iter().any(|language| language.code == value) {
+    return Ok(value);
+  }
+  let supported = LANGUAGES.iter().map(|language| language.code).collect::<Vec<_>>();
+  Err(format!(
+    "note language must be '{SAME_AS_SOURCE}' or one of: {}",
+    supported.join(", ")
+  ))
+}
+
+/// Value of the `{{language}}` prompt variable.
+pub fn prompt_language(code: &str) -> String {
+  match find(code) {
+    Some(language) => language.name.to_string(),
+    None => "the same language as the source content".to_string(),
+  }
+}
+
+/// Labels for the note. With `source`, the language the extraction detected decides, else English.
+pub fn note_labels(code: &str, detected: Option<&str>) -> &'static NoteLabels {
+  let code = if code == SAME_AS_SOURCE { detected.unwrap_or_default() } else { code };
+  &find(code).unwrap_or(&LANGUAGES[0]).labels
+}
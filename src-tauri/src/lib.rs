@@ -1,19 +1,38 @@
 mod app_state;
+mod budget;
 mod db;
 mod gemini;
 mod ingestion;
+mod language;
+mod llm;
 mod models;
 mod obsidian;
+mod runner;
 mod secrets;
 
+use base64::{prelude::BASE64_STANDARD, Engine};
+use sha2::{Digest, Sha256};
 use tauri::{AppHandle, Manager, State};
+use std::collections::HashSet;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicU64, Ordering};
 
 use app_state::AppState;
 use db::repository;
+use ingestion::{
+  crypto::MediaKey, derivative::DerivativeOptions, policy::SourcePolicy, IngestOptions, PreparedAsset, SourceFile,
+};
+use llm::{
+  prompt::{self, PromptVariables},
+  Attachment, GenerationRequest, LlmProvider, ModelError, ModelErrorKind,
+};
+use tauri_plugin_dialog::DialogExt;
 use models::{
-  EnqueueIngestionRequest, EnqueueIngestionResponse, GeminiApiKeyStatus, JobDetails, JobStatus, JobSummary,
-  PreviewNoteResponse, PublishNoteResponse, SettingsPayload, UpdateJobResponse,
+  BudgetStatus, EnqueueIngestionRequest, EnqueueIngestionResponse, Extraction, ExtractionResult,
+  GarbageCollectionReport, GeminiApiKeyStatus, GeminiKeyCheck, GeminiModelInfo, IngestMode, JobAsset, JobDetails,
+  JobEvent, JobStatus, JobSummary, KeyValidation, PreviewNoteResponse, ModelPrice, PromptRun, PromptTemplate,
+  PublishNoteResponse, QuarantineItem, SettingsPayload, StorageUsageReport, SubtitleCue, TokenUsageReport,
+  UpdateJobResponse, VerifyMediaReport,
 };
 
 fn time_now_ms() -> i64 {
@@ -31,51 +50,562 @@ fn make_job_id(now: i64) -> String {
   format!("job-{now}-{sequence}")
 }
 
-fn build_note_markdown(job: &JobDetails, ai_summary: Option<&str>) -> String {
+fn push_list_section(markdown: &mut String, heading: &str, items: &[String], prefix: &str) {
+  if items.is_empty() {
+    return;
+  }
+  markdown.push_str(&format!("## {heading}\n"));
+  for item in items {
+    markdown.push_str(&format!("{prefix}{}\n", item.replace('\n', " ")));
+  }
+  markdown.push('\n');
+}
+
+/// The job's own language override, else the note language setting.
+fn job_note_language<'a>(job: &'a JobDetails, settings: &'a SettingsPayload) -> &'a str {
+  job.job.note_language.as_deref().unwrap_or(&settings.note_language)
+}
+
+fn build_note_markdown(job: &JobDetails, extraction: Option<&Extraction>, note_language: &str) -> String {
+  let detected = extraction
+    .map(|extraction| extraction.language.as_str())
+    .filter(|language| !language.is_empty());
+  let labels = language::note_labels(note_language, detected);
   let mut markdown = String::new();
   markdown.push_str("---\n");
   markdown.push_str(&format!("title: \"[AI Capture] {}\"\n", job.job.title));
-  markdown.push_str("tags: [ai-capture, obsidian-agent]\n");
+  let mut tags = vec!["ai-capture".to_string(), "obsidian-agent".to_string()];
+  if let Some(extraction) = extraction {
+    tags.extend(extraction.topics.iter().filter(|topic| !topic.is_empty()).cloned());
+  }
+  markdown.push_str(&format!("tags: [{}]\n", tags.join(", ")));
+  if let Some(language) = detected {
+    markdown.push_str(&format!("language: {language}\n"));
+  }
   markdown.push_str("---\n\n");
-  markdown.push_str("## Key Insights\n");
-  if let Some(summary) = ai_summary {
-    markdown.push_str(summary);
-    markdown.push_str("\n\n");
-  } else {
-    markdown.push_str("- Gemini summary unavailable; set Gemini API key in Runtime Settings.\n");
-    markdown.push_str("- Obsidian write path is write-mode aware.\n");
-    markdown.push_str("- Media assets are persisted in local app storage.\n\n");
+  match extraction {
+    Some(extraction) => {
+      if !extraction.summary.is_empty() {
+        markdown.push_str(&format!("## {}\n", labels.summary));
+        markdown.push_str(&extraction.summary);
+        markdown.push_str("\n\n");
+      }
+      push_list_section(&mut markdown, labels.key_insights, &extraction.key_points, "- ");
+      push_list_section(&mut markdown, labels.action_items, &extraction.action_items, "- [ ] ");
+      let quotes = extraction
+        .quotes
+        .iter()
+        .map(|quote| format!("\"{}\"", quote.trim_matches('"')))
+        .collect::<Vec<_>>();
+      push_list_section(&mut markdown, labels.quotes, &quotes, "- ");
+      let entities = extraction
+        .entities
+        .iter()
+        .map(|entity| format!("[[{}]] ({})", entity.name, entity.kind))
+        .collect::<Vec<_>>();
+      push_list_section(&mut markdown, labels.entities, &entities, "- ");
+    }
+    None => {
+      let fallback = labels.fallback.map(str::to_string);
+      push_list_section(&mut markdown, labels.key_insights, &fallback, "- ");
+    }
   }
-  markdown.push_str("## Source Files\n");
+  markdown.push_str(&format!("## {}\n", labels.source_files));
   for asset in &job.assets {
-    markdown.push_str(&format!("- {} ({})\n", asset.original_path, asset.media_type));
+    if asset.media_type == "text" {
+      markdown.push_str(&format!("- {} ({} bytes)\n", labels.text_capture, asset.size_bytes));
+    } else if asset.media_type == "subtitle" && asset.parent_asset_id.is_some() {
+      markdown.push_str(&format!("- {} ({})\n", asset.original_path, labels.subtitles));
+    } else if is_collapsed_duplicate(job, asset) {
+      continue;
+    } else {
+      let collapsed = job
+        .assets
+        .iter()
+        .filter(|other| other.near_duplicate_of == Some(asset.id))
+        .count();
+      let mut line = format!("- {} ({}", asset.original_path, asset.media_type);
+      if collapsed > 0 {
+        line.push_str(&format!(", {collapsed} near-duplicate(s) collapsed"));
+      }
+      if asset.near_duplicate_of.is_some() {
+        line.push_str(", near-duplicate of an earlier capture");
+      }
+      line.push_str(")\n");
+      markdown.push_str(&line);
+    }
   }
   markdown
 }
 
-fn generate_ai_summary(settings: &SettingsPayload, job: &JobDetails) -> Option<String> {
-  let api_key = secrets::resolve_gemini_api_key().ok().flatten()?;
-  let source_files = job
+/// Near-duplicates of another asset in the same job are folded into that asset's entry.
+fn is_collapsed_duplicate(job: &JobDetails, asset: &JobAsset) -> bool {
+  asset
+    .near_duplicate_of
+    .is_some_and(|original_id| job.assets.iter().any(|other| other.id == original_id))
+}
+
+/// Loads the media encryption key only when at least one of `assets` is stored encrypted.
+fn media_key_for(assets: &[JobAsset]) -> Result<Option<MediaKey>, String> {
+  if !assets.iter().any(|asset| asset.encrypted) {
+    return Ok(None);
+  }
+  secrets::read_media_encryption_key()?
+    .ok_or_else(|| "media encryption key is missing from the OS keychain".to_string())
+    .map(Some)
+}
+
+/// Links each newly hashed image to the closest earlier image, whether in this job or a previous one.
+fn flag_near_duplicates(conn: &app_state::DbConnection, job_id: &str) -> Result<(), String> {
+  let Some(job) = repository::find_job_with_assets(conn, job_id)? else {
+    return Ok(());
+  };
+  if job.assets.iter().all(|asset| asset.perceptual_hash.is_none()) {
+    return Ok(());
+  }
+
+  let known_hashes = repository::list_perceptual_hashes(conn)?;
+  for asset in &job.assets {
+    let Some(hash) = asset.perceptual_hash else {
+      continue;
+    };
+    if let Some(original_id) = ingestion::perceptual::find_near_duplicate(asset.id, hash, &known_hashes) {
+      repository::mark_near_duplicate(conn, asset.id, original_id)?;
+    }
+  }
+  Ok(())
+}
+
+fn format_timestamp(ms: i64) -> String {
+  let seconds = ms / 1000;
+  format!("{:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+/// A piece of stored media the summary draws on: the asset itself, its downscaled derivative, or one
+/// of its audio chunks.
+struct SourceEntry {
+  label: String,
+  storage_path: String,
+  mime_type: String,
+  encrypted: bool,
+  is_chunk: bool,
+}
+
+/// Long recordings are handed to the summarizer as their chunks, each labelled with the file name and its time
+/// range; [`extraction_passes`] sends every chunk in a call of its own.
+fn source_entries(job: &JobDetails, asset: &JobAsset) -> Vec<SourceEntry> {
+  let chunks = job
+    .chunks
+    .iter()
+    .filter(|chunk| chunk.asset_id == asset.id)
+    .collect::<Vec<_>>();
+  if chunks.is_empty() {
+    let (storage_path, mime_type) = match (&asset.derivative_path, &asset.derivative_mime_type) {
+      (Some(path), Some(mime_type)) => (path.clone(), mime_type.clone()),
+      _ => (asset.storage_path.clone(), asset.mime_type.clone()),
+    };
+    return vec![SourceEntry {
+      // The model and the note know the file by the name the user gave it, whichever copy is sent.
+      label: asset.original_path.clone(),
+      storage_path,
+      mime_type,
+      encrypted: asset.encrypted,
+      is_chunk: false,
+    }];
+  }
+
+  chunks
+    .iter()
+    .map(|chunk| SourceEntry {
+      label: format!(
+        "{} (part {}/{}, {}-{})",
+        asset.original_path,
+        chunk.chunk_index + 1,
+        chunks.len(),
+        format_timestamp(chunk.start_ms),
+        format_timestamp(chunk.end_ms)
+      ),
+      storage_path: chunk.storage_path.clone(),
+      mime_type: asset.mime_type.clone(),
+      encrypted: asset.encrypted,
+      is_chunk: true,
+    })
+    .collect()
+}
+
+/// Splits a job's entries into model calls: the first call takes every whole file together with the text
+/// captures and transcripts, and each recording chunk gets a call of its own, in recording order.
+fn extraction_passes(entries: Vec<SourceEntry>, has_text: bool) -> Vec<Vec<SourceEntry>> {
+  let (chunks, whole): (Vec<_>, Vec<_>) = entries.into_iter().partition(|entry| entry.is_chunk);
+  let mut passes = Vec::new();
+  if !whole.is_empty() || has_text || chunks.is_empty() {
+    passes.push(whole);
+  }
+  passes.extend(chunks.into_iter().map(|chunk| vec![chunk]));
+  passes
+}
+
+/// Hands each entry to the provider as an attachment. Best-effort: a file the provider cannot take is
+/// still named in the prompt, just without its content.
+fn attach_source_entries(
+  provider: &dyn LlmProvider,
+  entries: &[SourceEntry],
+  media_key: Option<&MediaKey>,
+) -> Vec<(String, Attachment)> {
+  let mut attached = Vec::new();
+  for entry in entries {
+    let result = ingestion::open_stored_reader(&entry.storage_path, entry.encrypted, media_key)
+      .and_then(|reader| provider.attach(&entry.label, &entry.mime_type, reader));
+    match result {
+      Ok(attachment) => attached.push((entry.label.clone(), attachment)),
+      Err(error) => log::warn!("sending '{}' to {} as a path only: {error}", entry.label, provider.name()),
+    }
+  }
+  attached
+}
+
+/// Renders a subtitle sidecar as `[HH:MM:SS] text` lines, keyed by the video it belongs to.
+fn timed_transcripts(conn: &app_state::DbConnection, job: &JobDetails) -> Vec<(String, String)> {
+  job
+    .assets
+    .iter()
+    .filter(|asset| asset.media_type == "subtitle")
+    .filter_map(|sidecar| {
+      let cues = repository::list_subtitle_cues(conn, sidecar.id).ok()?;
+      if cues.is_empty() {
+        return None;
+      }
+      let label = sidecar
+        .parent_asset_id
+        .and_then(|parent_id| job.assets.iter().find(|asset| asset.id == parent_id))
+        .unwrap_or(sidecar)
+        .original_path
+        .clone();
+      let text = cues
+        .iter()
+        .map(|cue| format!("[{}] {}", format_timestamp(cue.start_ms), cue.text.replace('\n', " ")))
+        .collect::<Vec<_>>()
+        .join("\n");
+      Some((label, text))
+    })
+    .collect()
+}
+
+/// One line per non-text asset with what the database knows about it, for the `media_metadata` variable.
+fn media_metadata(job: &JobDetails) -> String {
+  job
+    .assets
+    .iter()
+    .filter(|asset| asset.media_type != "text")
+    .filter(|asset| !is_collapsed_duplicate(job, asset))
+    .map(|asset| {
+      let mut line = format!(
+        "- {}: {}, {}, {} bytes",
+        asset.original_path, asset.media_type, asset.mime_type, asset.size_bytes
+      );
+      if let Some(duration_ms) = asset.duration_ms {
+        line.push_str(&format!(", duration {}", format_timestamp(duration_ms)));
+      }
+      line
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// The template chosen in settings, or the default one if that row has since disappeared.
+fn selected_prompt_template(
+  conn: &app_state::DbConnection,
+  settings: &SettingsPayload,
+) -> Result<PromptTemplate, String> {
+  if let Some(template) = repository::find_prompt_template(conn, settings.prompt_template_key.trim())? {
+    return Ok(template);
+  }
+  repository::find_prompt_template(conn, prompt::DEFAULT_TEMPLATE_KEY)?
+    .ok_or_else(|| "default prompt template is missing".to_string())
+}
+
+/// Identifies an extraction by everything that shapes it: model, prompt template, the job's prompt inputs, response
+/// schema and the content hashes of the job's assets. The vault context is left out on purpose: publishing creates
+/// folders in the vault, and that must not invalidate a preview the user already paid for.
+fn extraction_cache_key(
+  model: &str,
+  template: &PromptTemplate,
+  variables: &PromptVariables,
+  job: &JobDetails,
+) -> String {
+  let mut asset_hashes = job.assets.iter().map(|asset| asset.sha256.as_str()).collect::<Vec<_>>();
+  asset_hashes.sort_unstable();
+
+  let schema = llm::extraction::response_schema().to_string();
+  let mut hasher = Sha256::new();
+  for part in [
+    model,
+    &template.key,
+    &template.content,
+    &variables.title,
+    &variables.file_list,
+    &variables.media_metadata,
+    &variables.language,
+    &variables.text_captures,
+    &variables.transcripts,
+    &schema,
+  ] {
+    hasher.update(part.as_bytes());
+    hasher.update([0]);
+  }
+  for hash in asset_hashes {
+    hasher.update(hash.as_bytes());
+    hasher.update([0]);
+  }
+  format!("{:x}", hasher.finalize())
+}
+
+/// Why [`job_extraction`] has no result, in enough detail for the job runner to retry, park or fail the job.
+#[derive(Debug)]
+enum ExtractionError {
+  /// A usage budget is used up; the model was not called.
+  BudgetExceeded(String),
+  Model(ModelError),
+  Other(String),
+}
+
+impl From<String> for ExtractionError {
+  fn from(error: String) -> Self {
+    Self::Other(error)
+  }
+}
+
+impl std::fmt::Display for ExtractionError {
+  fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::BudgetExceeded(reason) => write!(formatter, "model calls are paused: {reason}"),
+      Self::Model(error) => write!(formatter, "{error}"),
+      Self::Other(error) => formatter.write_str(error),
+    }
+  }
+}
+
+/// Returns the job's extraction, calling the model only on a cache miss or when `regenerate` is set, so a
+/// preview and the later publish render the same result. Cache hits are served even when over budget.
+/// `claimed` is set by the job runner, which has already moved the job to `processing`.
+fn job_extraction(
+  conn: &mut app_state::DbConnection,
+  settings: &SettingsPayload,
+  job: &JobDetails,
+  regenerate: bool,
+  claimed: bool,
+) -> Result<ExtractionResult, ExtractionError> {
+  let media_key = media_key_for(&job.assets)?;
+  let transcripts = timed_transcripts(conn, job);
+  // Videos with a transcript are summarized from their timed text instead of the media itself.
+  let entries = job
     .assets
     .iter()
-    .map(|asset| asset.original_path.clone())
+    .filter(|asset| !matches!(asset.media_type.as_str(), "text" | "subtitle"))
+    .filter(|asset| !is_collapsed_duplicate(job, asset))
+    .filter(|asset| !transcripts.iter().any(|(label, _)| *label == asset.original_path))
+    .flat_map(|asset| source_entries(job, asset))
     .collect::<Vec<_>>();
-  let client = gemini::GeminiClient::new();
+  let source_files = entries.iter().map(|entry| entry.label.clone()).collect::<Vec<_>>();
+  let text_captures = job
+    .assets
+    .iter()
+    .filter(|asset| asset.media_type == "text")
+    .filter_map(|asset| ingestion::read_asset_to_string(asset, media_key.as_ref()).ok())
+    .collect::<Vec<_>>();
+  let template = selected_prompt_template(conn, settings)?;
+  let variables = PromptVariables {
+    title: job.job.title.clone(),
+    file_list: prompt::file_list(&source_files),
+    media_metadata: media_metadata(job),
+    language: language::prompt_language(job_note_language(job, settings)),
+    vault_context: obsidian::vault_context(settings),
+    text_captures: prompt::text_capture_sections(&text_captures),
+    transcripts: prompt::transcript_sections(&transcripts),
+  };
+  // Prefixed with the provider so a Gemini and a local model of the same name never share results.
+  let model = format!("{}/{}", settings.llm_provider, llm::model_for(settings));
+  let cache_key = extraction_cache_key(&model, &template, &variables, job);
+
+  if !regenerate {
+    match repository::find_cached_extraction(conn, &job.job.id, &cache_key) {
+      Ok(Some((cached, _))) if cached.job_id == job.job.id => return Ok(cached),
+      Ok(Some((cached, raw_output))) => {
+        match repository::insert_extraction_result(
+          conn,
+          &job.job.id,
+          &cached.model,
+          &cache_key,
+          &raw_output,
+          &cached.extraction,
+          time_now_ms(),
+        ) {
+          Ok(copied) => return Ok(copied),
+          Err(error) => log::warn!("failed to reuse cached extraction for job {}: {error}", job.job.id),
+        }
+      }
+      Ok(None) => {}
+      Err(error) => log::warn!("ignoring unreadable extraction cache for job {}: {error}", job.job.id),
+    }
+  }
+
+  // The job runner claims its jobs before calling in. Anyone else holds the job for the length of the model
+  // calls, so the runner cannot pick it up and bill the same extraction again meanwhile.
+  let previous_status = if claimed {
+    None
+  } else {
+    let status = repository::claim_job_for_extraction(conn, &job.job.id, time_now_ms())?
+      .ok_or_else(|| format!("job {} is already being extracted", job.job.id))?;
+    Some(status)
+  };
+  let result = (|| -> Result<ExtractionResult, ExtractionError> {
+    if let Some(reason) = budget::status(conn, settings, time_now_ms())?.exceeded {
+      return Err(ExtractionError::BudgetExceeded(reason));
+    }
+    let provider = llm::provider_for(settings)?;
+    let schema = llm::extraction::response_schema();
+    let has_text = !text_captures.is_empty() || !transcripts.is_empty();
+    let mut raw_outputs = Vec::new();
+    let mut parts = Vec::new();
+    for (index, pass) in extraction_passes(entries, has_text).into_iter().enumerate() {
+      if index > 0 {
+        if let Some(reason) = budget::status(conn, settings, time_now_ms())?.exceeded {
+          return Err(ExtractionError::BudgetExceeded(reason));
+        }
+      }
+      // Text captures and transcripts go with the first call only; later calls see a single chunk.
+      let pass_prompt = prompt::render(
+        &template.content,
+        &PromptVariables {
+          file_list: prompt::file_list(&pass.iter().map(|entry| entry.label.clone()).collect::<Vec<_>>()),
+          text_captures: if index == 0 { variables.text_captures.clone() } else { String::new() },
+          transcripts: if index == 0 { variables.transcripts.clone() } else { String::new() },
+          ..variables.clone()
+        },
+      );
+      let attachments = attach_source_entries(provider.as_ref(), &pass, media_key.as_ref());
+      let mut call = provider.generate(&GenerationRequest {
+        model: llm::model_for(settings),
+        prompt: &pass_prompt,
+        attachments: &attachments,
+        response_schema: Some(&schema),
+      });
+      let parsed = call.outcome.clone().and_then(|raw_output| {
+        llm::extraction::normalize(&raw_output)
+          .map(|extraction| (raw_output, extraction))
+          .map_err(|error| ModelError::new(ModelErrorKind::MalformedResponse, error))
+      });
+      if let Err(error) = &parsed {
+        call.outcome = Err(error.clone());
+      }
+      if let Err(error) =
+        repository::insert_prompt_run(conn, &job.job.id, &template.key, &model, &call, time_now_ms())
+      {
+        log::warn!("failed to audit model call for job {}: {error}", job.job.id);
+      }
+      for (label, attachment) in &attachments {
+        if let Err(error) = provider.release(attachment) {
+          log::warn!("failed to release {} copy of '{label}': {error}", provider.name());
+        }
+      }
+
+      let (raw_output, extraction) = parsed.map_err(ExtractionError::Model)?;
+      raw_outputs.push(raw_output);
+      parts.push(extraction);
+    }
+
+    // A job that took several calls keeps its raw replies as a JSON array, in call order.
+    let raw_output = match raw_outputs.as_slice() {
+      [single] => single.clone(),
+      _ => serde_json::to_string(&raw_outputs).map_err(|error| format!("failed to store model replies: {error}"))?,
+    };
+    Ok(repository::insert_extraction_result(
+      conn,
+      &job.job.id,
+      &model,
+      &cache_key,
+      &raw_output,
+      &llm::extraction::merge(parts),
+      time_now_ms(),
+    )?)
+  })();
+  if let Some(status) = previous_status {
+    if let Err(error) = repository::release_claimed_job(conn, &job.job.id, &status, time_now_ms()) {
+      log::warn!("job {}: {error}", job.job.id);
+    }
+  }
+  result
+}
 
-  client
-    .generate_job_summary(&api_key, &settings.gemini_model, &source_files)
+/// Extraction for rendering a note, which falls back to placeholder text when there is none.
+fn optional_job_extraction(
+  conn: &mut app_state::DbConnection,
+  settings: &SettingsPayload,
+  job: &JobDetails,
+) -> Option<ExtractionResult> {
+  job_extraction(conn, settings, job, false, false)
+    .inspect_err(|error| log::warn!("no extraction for job {}: {error}", job.job.id))
     .ok()
 }
 
 #[tauri::command]
-fn get_gemini_api_key_status() -> Result<GeminiApiKeyStatus, String> {
+fn get_gemini_api_key_status(state: State<'_, AppState>) -> Result<GeminiApiKeyStatus, String> {
   let source = secrets::get_gemini_api_key_source()?;
+  let conn = state.conn()?;
   Ok(GeminiApiKeyStatus {
     configured: !matches!(source, secrets::GeminiApiKeySource::Missing),
     source: source.as_str().to_string(),
+    last_validation: repository::find_key_validation(&conn, llm::PROVIDER_GEMINI)?,
   })
 }
 
+#[tauri::command]
+fn validate_gemini_api_key(state: State<'_, AppState>) -> Result<GeminiKeyCheck, String> {
+  check_gemini_key(&state)
+}
+
+/// Lists the models available to the stored key against the configured endpoint, recording the outcome and,
+/// on success, the model list.
+fn check_gemini_key(state: &AppState) -> Result<GeminiKeyCheck, String> {
+  let api_key = secrets::resolve_gemini_api_key()?.ok_or_else(|| "missing Gemini API key".to_string())?;
+  let mut conn = state.conn()?;
+  let settings = repository::get_settings(&conn)?;
+  let result = gemini::GeminiClient::new(&settings.gemini_base_url).list_models(&api_key);
+  let now = time_now_ms();
+  let (validation, models) = match result {
+    Ok(models) => {
+      repository::replace_gemini_models(&mut conn, &models, now)?;
+      let validation = KeyValidation {
+        ok: true,
+        message: None,
+        validated_at: now,
+      };
+      (validation, models)
+    }
+    Err(error) => {
+      let message = match error.kind {
+        ModelErrorKind::Auth => format!("key rejected: {error}"),
+        _ => error.to_string(),
+      };
+      let validation = KeyValidation {
+        ok: false,
+        message: Some(message),
+        validated_at: now,
+      };
+      (validation, Vec::new())
+    }
+  };
+  repository::save_key_validation(&conn, llm::PROVIDER_GEMINI, &validation)?;
+  Ok(GeminiKeyCheck { validation, models })
+}
+
+/// Models found by the last successful key check.
+#[tauri::command]
+fn list_gemini_models(state: State<'_, AppState>) -> Result<Vec<GeminiModelInfo>, String> {
+  let conn = state.conn()?;
+  repository::list_gemini_models(&conn)
+}
+
 #[tauri::command]
 fn save_gemini_api_key(api_key: String) -> Result<(), String> {
   secrets::save_gemini_api_key(&api_key)
@@ -87,28 +617,198 @@ fn clear_gemini_api_key() -> Result<(), String> {
 }
 
 #[tauri::command]
+fn has_openai_api_key() -> Result<bool, String> {
+  Ok(secrets::resolve_openai_api_key()?.is_some())
+}
+
+#[tauri::command]
+fn save_openai_api_key(api_key: String) -> Result<(), String> {
+  secrets::save_openai_api_key(&api_key)
+}
+
+#[tauri::command]
+fn clear_openai_api_key() -> Result<(), String> {
+  secrets::clear_openai_api_key()
+}
+
+/// Everything derived from freshly stored files: downscaled images, thumbnails, perceptual hashes, audio chunks
+/// and subtitle cues. Thumbnail names start with `name_prefix`.
+fn attach_derived_media(
+  state: &AppState,
+  settings: &SettingsPayload,
+  assets: &mut [PreparedAsset],
+  name_prefix: &str,
+  now: i64,
+  encryption_key: Option<&MediaKey>,
+) {
+  if settings.image_derivatives_enabled {
+    let derivative_options = DerivativeOptions {
+      max_dimension: settings.image_max_dimension as u32,
+      jpeg_quality: settings.image_jpeg_quality as u8,
+    };
+    ingestion::derivative::attach_image_derivatives(
+      assets,
+      &state.media_root,
+      now,
+      encryption_key,
+      &derivative_options,
+    );
+  }
+  ingestion::thumbnail::attach_thumbnails(assets, &state.thumbnail_root, name_prefix, encryption_key);
+  ingestion::perceptual::attach_perceptual_hashes(assets, encryption_key);
+  ingestion::audio::attach_audio_chunks(assets, &state.media_root, now, encryption_key);
+  ingestion::subtitles::attach_subtitle_cues(assets, encryption_key);
+}
+
+#[tauri::command(async)]
 fn enqueue_ingestion(
   state: State<'_, AppState>,
   request: EnqueueIngestionRequest,
 ) -> Result<EnqueueIngestionResponse, String> {
-  if request.file_paths.is_empty() {
-    return Err("enqueue_ingestion requires at least one file path".to_string());
+  enqueue_job(&state, request)
+}
+
+fn enqueue_job(state: &AppState, request: EnqueueIngestionRequest) -> Result<EnqueueIngestionResponse, String> {
+  let text_content = request
+    .text_content
+    .as_deref()
+    .map(str::trim)
+    .filter(|text| !text.is_empty());
+  if request.file_paths.is_empty() && text_content.is_none() {
+    return Err("enqueue_ingestion requires at least one file path or text content".to_string());
   }
 
+  let mut conn = state.conn()?;
+  let settings = repository::get_settings(&conn)?;
+  let ingest_mode = match request.ingest_mode.as_deref().map(str::trim) {
+    None | Some("") => IngestMode::parse(&settings.ingest_mode),
+    Some(value @ ("copy" | "move" | "reference")) => IngestMode::parse(value),
+    Some(other) => return Err(format!("unsupported ingest_mode '{other}'; expected copy, move, or reference")),
+  };
+  let note_language = match request.note_language.as_deref().map(str::trim) {
+    None | Some("") => None,
+    Some(value) => Some(language::normalize(value)?),
+  };
+  let options = IngestOptions {
+    mode: ingest_mode,
+    encryption_key: if settings.encrypt_media {
+      Some(secrets::get_or_create_media_encryption_key()?)
+    } else {
+      None
+    },
+    source_policy: SourcePolicy::new(&settings.allowed_source_roots, state.approved_sources()),
+  };
+
   let now = time_now_ms();
   let job_id = make_job_id(now);
-  let title = ingestion::build_job_title(request.note_title.as_deref(), request.file_paths.len());
-  let assets = ingestion::prepare_assets(&request.file_paths, &state.media_root, now)?;
+  let staging_dir = state.media_root.join(ingestion::archive::STAGING_DIR).join(&job_id);
 
-  let mut conn = state.conn()?;
-  repository::insert_job_with_assets(
-    &mut conn,
-    &job_id,
-    &title,
-    JobStatus::Queued.as_str(),
-    &assets,
-    now,
-  )?;
+  let result = (|| -> Result<(), String> {
+    // Archives can inflate far beyond their own size, so the quota is first checked against what they declare;
+    // the check after extraction catches entries that turn out larger than declared.
+    let mut declared_bytes = 0_u64;
+    let mut plain_sources = Vec::new();
+    for path in &request.file_paths {
+      if ingestion::archive::is_archive(path) {
+        options.source_policy.check(path)?;
+        declared_bytes = declared_bytes.saturating_add(ingestion::archive::declared_media_bytes(path)?);
+      } else {
+        plain_sources.push(SourceFile::from_path(path));
+      }
+    }
+    ingestion::storage::ensure_within_quota(
+      repository::stored_media_bytes(&conn)?,
+      ingestion::storage::incoming_bytes(&plain_sources, text_content, ingest_mode).saturating_add(declared_bytes),
+      settings.media_quota_bytes,
+    )?;
+
+    let mut sources = Vec::new();
+    for (index, path) in request.file_paths.iter().enumerate() {
+      if ingestion::archive::is_archive(path) {
+        let archive_dir = staging_dir.join(format!("archive-{index}"));
+        sources.extend(ingestion::archive::extract_archive(path, &archive_dir)?);
+      } else {
+        sources.push(SourceFile::from_path(path));
+      }
+    }
+    let explicit_paths = request.file_paths.iter().map(PathBuf::from).collect::<HashSet<_>>();
+    for path in &request.file_paths {
+      if ingestion::infer_media_type(path) == "video" {
+        let sidecars = ingestion::subtitles::find_sidecars(path)
+          .into_iter()
+          .filter(|sidecar| !explicit_paths.contains(&PathBuf::from(sidecar)))
+          .filter(|sidecar| options.source_policy.check(sidecar).is_ok());
+        sources.extend(sidecars.map(|sidecar| SourceFile::from_path(&sidecar)));
+      }
+    }
+
+    ingestion::storage::ensure_within_quota(
+      repository::stored_media_bytes(&conn)?,
+      ingestion::storage::incoming_bytes(&sources, text_content, ingest_mode),
+      settings.media_quota_bytes,
+    )?;
+
+    let title = ingestion::build_job_title(request.note_title.as_deref(), sources.len(), text_content.is_some());
+    let mut batch = if sources.is_empty() {
+      ingestion::PreparedBatch::default()
+    } else {
+      ingestion::prepare_assets(&sources, &state.media_root, now, &options)?
+    };
+    for file in &batch.quarantined {
+      log::warn!("quarantined '{}' for job {job_id}: {}", file.asset.original_path, file.reason);
+    }
+    // With every file quarantined there is nothing to extract until one is approved, so the job starts out failed
+    // instead of queued and the runner never sends an empty request.
+    let status = if batch.assets.is_empty() && text_content.is_none() {
+      if batch.quarantined.is_empty() {
+        return Err("the selected files contain nothing to ingest".to_string());
+      }
+      JobStatus::Failed
+    } else {
+      JobStatus::Queued
+    };
+    let encryption_key = options.encryption_key.as_ref();
+    let assets = &mut batch.assets;
+    attach_derived_media(state, &settings, assets, &job_id, now, encryption_key);
+    ingestion::subtitles::link_sidecars(assets);
+    if let Some(text) = text_content {
+      match ingestion::prepare_text_asset(text, &state.media_root, now, sources.len(), encryption_key) {
+        Ok(asset) => assets.push(asset),
+        Err(error) => {
+          batch.rollback(encryption_key);
+          return Err(error);
+        }
+      }
+    }
+
+    if let Err(error) = repository::insert_job_with_assets(
+      &mut conn,
+      &job_id,
+      &title,
+      status.as_str(),
+      note_language.as_deref(),
+      &batch,
+      now,
+    ) {
+      batch.rollback(encryption_key);
+      return Err(error);
+    }
+    if matches!(status, JobStatus::Failed) {
+      let message = "every file was quarantined; approve one and retry the job";
+      if let Err(error) = repository::insert_job_event(&conn, &job_id, "quarantined", message, now) {
+        log::warn!("failed to record quarantine event for job {job_id}: {error}");
+      }
+    }
+    if let Err(error) = flag_near_duplicates(&conn, &job_id) {
+      log::warn!("near-duplicate detection failed for job {job_id}: {error}");
+    }
+    Ok(())
+  })();
+
+  if staging_dir.exists() {
+    let _ = std::fs::remove_dir_all(&staging_dir);
+  }
+  result?;
 
   Ok(EnqueueIngestionResponse { job_id })
 }
@@ -152,12 +852,321 @@ fn cancel_job(state: State<'_, AppState>, job_id: String) -> Result<UpdateJobRes
   Ok(UpdateJobResponse { ok: changed })
 }
 
+#[tauri::command]
+fn verify_media(state: State<'_, AppState>, repair: bool) -> Result<VerifyMediaReport, String> {
+  verify_stored_media(&state, repair)
+}
+
+fn verify_stored_media(state: &AppState, repair: bool) -> Result<VerifyMediaReport, String> {
+  let conn = state.conn()?;
+  let assets = repository::list_media_assets(&conn)?;
+  // A missing key only makes the encrypted assets unverifiable; plain ones are still checked.
+  let (media_key, key_error) = match media_key_for(&assets) {
+    Ok(key) => (key, None),
+    Err(error) => (None, Some(error)),
+  };
+
+  let mut report = VerifyMediaReport {
+    checked: 0,
+    healthy: 0,
+    repaired: 0,
+    issues: Vec::new(),
+  };
+  for asset in &assets {
+    report.checked += 1;
+    let checked = match &key_error {
+      Some(error) if asset.encrypted => Err(error.clone()),
+      _ => ingestion::integrity::verify_asset(asset, repair, media_key.as_ref()),
+    };
+    match checked {
+      Ok(Some(issue)) => {
+        if issue.repaired {
+          report.repaired += 1;
+        }
+        report.issues.push(issue);
+      }
+      Ok(None) => report.healthy += 1,
+      Err(error) => report.issues.push(ingestion::integrity::unreadable_issue(asset, error)),
+    }
+  }
+
+  Ok(report)
+}
+
+#[tauri::command]
+fn get_storage_usage(state: State<'_, AppState>) -> Result<StorageUsageReport, String> {
+  let conn = state.conn()?;
+  let settings = repository::get_settings(&conn)?;
+  Ok(StorageUsageReport {
+    total_bytes: repository::stored_media_bytes(&conn)?,
+    quota_bytes: settings.media_quota_bytes,
+    per_job: repository::storage_usage_by_job(&conn)?,
+    per_month: repository::storage_usage_by_month(&conn)?,
+  })
+}
+
+#[tauri::command]
+fn get_token_usage(state: State<'_, AppState>) -> Result<TokenUsageReport, String> {
+  let conn = state.conn()?;
+  Ok(TokenUsageReport {
+    totals: repository::token_usage_totals(&conn)?,
+    per_job: repository::token_usage_by_job(&conn)?,
+    per_month: repository::token_usage_by_month(&conn)?,
+  })
+}
+
+#[tauri::command]
+fn get_budget_status(state: State<'_, AppState>) -> Result<BudgetStatus, String> {
+  let conn = state.conn()?;
+  let settings = repository::get_settings(&conn)?;
+  budget::status(&conn, &settings, time_now_ms())
+}
+
+#[tauri::command]
+fn list_job_events(state: State<'_, AppState>, job_id: String) -> Result<Vec<JobEvent>, String> {
+  let conn = state.conn()?;
+  repository::list_job_events(&conn, job_id.trim())
+}
+
+#[tauri::command]
+fn list_model_prices(state: State<'_, AppState>) -> Result<Vec<ModelPrice>, String> {
+  let conn = state.conn()?;
+  repository::list_model_prices(&conn)
+}
+
+#[tauri::command]
+fn save_model_price(state: State<'_, AppState>, price: ModelPrice) -> Result<ModelPrice, String> {
+  let conn = state.conn()?;
+  repository::save_model_price(&conn, &price, time_now_ms())
+}
+
+#[tauri::command]
+fn delete_model_price(state: State<'_, AppState>, model: String) -> Result<(), String> {
+  let conn = state.conn()?;
+  repository::delete_model_price(&conn, &model)
+}
+
+#[tauri::command]
+fn collect_media_garbage(state: State<'_, AppState>, dry_run: bool) -> Result<GarbageCollectionReport, String> {
+  collect_garbage(&state, dry_run)
+}
+
+fn collect_garbage(state: &AppState, dry_run: bool) -> Result<GarbageCollectionReport, String> {
+  let conn = state.conn()?;
+  let settings = repository::get_settings(&conn)?;
+  let now = time_now_ms();
+
+  let mut report = GarbageCollectionReport {
+    dry_run,
+    orphaned_files: 0,
+    orphaned_bytes: 0,
+    evicted_assets: 0,
+    evicted_bytes: 0,
+  };
+
+  if settings.media_retention_days > 0 {
+    let cutoff = now - settings.media_retention_days * 24 * 60 * 60 * 1000;
+    for asset in repository::list_evictable_assets(&conn, cutoff)? {
+      let stores_copy = IngestMode::parse(&asset.ingest_mode) != IngestMode::Reference;
+      if !dry_run {
+        if stores_copy {
+          ingestion::storage::evict_stored_copy(&state.media_root, &asset.storage_path)?;
+        }
+        if let Some(derivative_path) = &asset.derivative_path {
+          ingestion::storage::evict_stored_copy(&state.media_root, derivative_path)?;
+        }
+        if let Some(thumbnail_path) = &asset.thumbnail_path {
+          ingestion::storage::evict_stored_copy(&state.thumbnail_root, thumbnail_path)?;
+        }
+      }
+      for chunk in repository::list_media_chunks(&conn, Some(asset.id))? {
+        if !dry_run {
+          ingestion::storage::evict_stored_copy(&state.media_root, &chunk.storage_path)?;
+        }
+        report.evicted_bytes += chunk.size_bytes;
+      }
+      if !dry_run {
+        repository::mark_asset_evicted(&conn, asset.id, now)?;
+      }
+      report.evicted_assets += 1;
+      if stores_copy {
+        report.evicted_bytes += asset.size_bytes;
+      }
+      report.evicted_bytes += asset.derivative_size_bytes.unwrap_or(0);
+    }
+  }
+
+  let chunk_paths = repository::list_media_chunks(&conn, None)?
+    .into_iter()
+    .map(|chunk| chunk.storage_path);
+  let quarantine_paths = repository::list_quarantine_items(&conn, Some("pending"))?
+    .into_iter()
+    .map(|item| item.storage_path);
+  let assets = repository::list_media_assets(&conn)?;
+  let thumbnail_paths = assets
+    .iter()
+    .filter_map(|asset| asset.thumbnail_path.as_ref().map(PathBuf::from))
+    .collect::<HashSet<_>>();
+  let known_paths = assets
+    .into_iter()
+    .flat_map(|asset| std::iter::once(asset.storage_path).chain(asset.derivative_path))
+    .chain(chunk_paths)
+    .chain(quarantine_paths)
+    .map(PathBuf::from)
+    .collect::<HashSet<_>>();
+  let (orphaned_files, orphaned_bytes) =
+    ingestion::storage::collect_orphans(&state.media_root, &known_paths, dry_run)?;
+  let (orphaned_thumbnails, orphaned_thumbnail_bytes) =
+    ingestion::storage::collect_orphans(&state.thumbnail_root, &thumbnail_paths, dry_run)?;
+  report.orphaned_files = orphaned_files + orphaned_thumbnails;
+  report.orphaned_bytes = orphaned_bytes + orphaned_thumbnail_bytes;
+
+  Ok(report)
+}
+
+#[tauri::command]
+fn get_asset_thumbnail(state: State<'_, AppState>, asset_id: i64) -> Result<Option<String>, String> {
+  let conn = state.conn()?;
+  let Some(asset) = repository::find_media_asset(&conn, asset_id)? else {
+    return Err(format!("media asset {asset_id} not found"));
+  };
+  let Some(thumbnail_path) = &asset.thumbnail_path else {
+    return Ok(None);
+  };
+
+  let media_key = media_key_for(std::slice::from_ref(&asset))?;
+  let bytes = ingestion::thumbnail::read_thumbnail(thumbnail_path, asset.encrypted, media_key.as_ref())?;
+  Ok(Some(format!("data:image/jpeg;base64,{}", BASE64_STANDARD.encode(bytes))))
+}
+
+#[tauri::command]
+fn get_subtitle_cues(state: State<'_, AppState>, asset_id: i64) -> Result<Vec<SubtitleCue>, String> {
+  let conn = state.conn()?;
+  repository::list_subtitle_cues(&conn, asset_id)
+}
+
+#[tauri::command]
+fn list_quarantine(state: State<'_, AppState>, status: Option<String>) -> Result<Vec<QuarantineItem>, String> {
+  let conn = state.conn()?;
+  repository::list_quarantine_items(&conn, status.as_deref().map(str::trim).filter(|value| !value.is_empty()))
+}
+
+fn pending_quarantine_item(conn: &app_state::DbConnection, item_id: i64) -> Result<QuarantineItem, String> {
+  let item = repository::find_quarantine_item(conn, item_id)?
+    .ok_or_else(|| format!("quarantine item {item_id} not found"))?;
+  if item.status != "pending" {
+    return Err(format!("quarantine item {item_id} was already {}", item.status));
+  }
+  Ok(item)
+}
+
+#[tauri::command]
+fn approve_quarantine_item(state: State<'_, AppState>, item_id: i64) -> Result<UpdateJobResponse, String> {
+  approve_quarantined(&state, item_id)
+}
+
+/// Moves an approved file into its job and runs it through the same processing as a file that passed the checks.
+fn approve_quarantined(state: &AppState, item_id: i64) -> Result<UpdateJobResponse, String> {
+  let mut conn = state.conn()?;
+  let item = pending_quarantine_item(&conn, item_id)?;
+  let settings = repository::get_settings(&conn)?;
+  let encryption_key = if item.encrypted {
+    let key = secrets::read_media_encryption_key()?
+      .ok_or_else(|| "media encryption key is missing from the OS keychain".to_string())?;
+    Some(key)
+  } else {
+    None
+  };
+  let now = time_now_ms();
+
+  let mut asset = ingestion::quarantine::release(&item, &state.media_root, now)?;
+  let name_prefix = format!("{}-quarantine-{}", item.job_id, item.id);
+  attach_derived_media(
+    state,
+    &settings,
+    std::slice::from_mut(&mut asset),
+    &name_prefix,
+    now,
+    encryption_key.as_ref(),
+  );
+  if let Err(error) = repository::approve_quarantine_item(&mut conn, &item, &asset, now) {
+    ingestion::quarantine::restore(&asset, &item);
+    return Err(error);
+  }
+  if let Err(error) = flag_near_duplicates(&conn, &item.job_id) {
+    log::warn!("near-duplicate detection failed for job {}: {error}", item.job_id);
+  }
+  Ok(UpdateJobResponse { ok: true })
+}
+
+#[tauri::command]
+fn discard_quarantine_item(state: State<'_, AppState>, item_id: i64) -> Result<UpdateJobResponse, String> {
+  discard_quarantined(&state, item_id)
+}
+
+fn discard_quarantined(state: &AppState, item_id: i64) -> Result<UpdateJobResponse, String> {
+  let conn = state.conn()?;
+  let item = pending_quarantine_item(&conn, item_id)?;
+
+  ingestion::quarantine::discard(&item, &state.media_root)?;
+  repository::discard_quarantine_item(&conn, item.id, time_now_ms())?;
+  Ok(UpdateJobResponse { ok: true })
+}
+
 #[tauri::command]
 fn get_settings(state: State<'_, AppState>) -> Result<SettingsPayload, String> {
   let conn = state.conn()?;
   repository::get_settings(&conn)
 }
 
+const CAPTURE_EXTENSIONS: [&str; 11] = ["mp3", "wav", "m4a", "mp4", "jpg", "jpeg", "png", "heif", "srt", "vtt", "zip"];
+
+/// Opens the native file picker from the backend so the selection, not the webview, decides which
+/// files `enqueue_ingestion` may read. Subtitle sidecars of picked videos are approved along with them.
+#[tauri::command]
+async fn pick_capture_files(app: AppHandle, state: State<'_, AppState>) -> Result<Vec<String>, String> {
+  let Some(picked) = app
+    .dialog()
+    .file()
+    .set_title("Select files to ingest")
+    .add_filter("Media", &CAPTURE_EXTENSIONS)
+    .blocking_pick_files()
+  else {
+    return Ok(Vec::new());
+  };
+
+  let mut paths = Vec::new();
+  let mut approved = Vec::new();
+  for file in picked {
+    let path = file
+      .into_path()
+      .map_err(|error| format!("failed to resolve selected file: {error}"))?
+      .canonicalize()
+      .map_err(|error| format!("failed to canonicalize selected file: {error}"))?;
+    let path_string = path.to_string_lossy().to_string();
+    if ingestion::infer_media_type(&path_string) == "video" {
+      approved.extend(ingestion::subtitles::find_sidecars(&path_string).into_iter().map(PathBuf::from));
+    }
+    approved.push(path);
+    paths.push(path_string);
+  }
+  state.approve_sources(approved);
+  Ok(paths)
+}
+
+#[tauri::command]
+async fn pick_source_root(app: AppHandle, state: State<'_, AppState>) -> Result<Option<String>, String> {
+  let Some(folder) = app.dialog().file().set_title("Allow ingestion from folder").blocking_pick_folder() else {
+    return Ok(None);
+  };
+  let path = folder
+    .into_path()
+    .map_err(|error| format!("failed to resolve selected folder: {error}"))?;
+  // Saved here rather than by the webview, so only a folder the user actually picked can be allowlisted.
+  let conn = state.conn()?;
+  repository::add_allowed_source_root(&conn, &path.to_string_lossy()).map(Some)
+}
+
 #[tauri::command]
 fn save_settings(state: State<'_, AppState>, payload: SettingsPayload) -> Result<SettingsPayload, String> {
   let conn = state.conn()?;
@@ -166,39 +1175,103 @@ fn save_settings(state: State<'_, AppState>, payload: SettingsPayload) -> Result
 }
 
 #[tauri::command]
-fn preview_note(state: State<'_, AppState>, job_id: String) -> Result<PreviewNoteResponse, String> {
+fn list_prompt_templates(state: State<'_, AppState>) -> Result<Vec<PromptTemplate>, String> {
+  let conn = state.conn()?;
+  repository::list_prompt_templates(&conn)
+}
+
+#[tauri::command]
+fn save_prompt_template(state: State<'_, AppState>, key: String, content: String) -> Result<PromptTemplate, String> {
+  let conn = state.conn()?;
+  repository::save_prompt_template(&conn, &key, &content, time_now_ms())
+}
+
+#[tauri::command]
+fn delete_prompt_template(state: State<'_, AppState>, key: String) -> Result<UpdateJobResponse, String> {
   let conn = state.conn()?;
+  let ok = repository::delete_prompt_template(&conn, key.trim())?;
+  Ok(UpdateJobResponse { ok })
+}
+
+#[tauri::command]
+fn list_prompt_runs(state: State<'_, AppState>, job_id: String) -> Result<Vec<PromptRun>, String> {
+  let conn = state.conn()?;
+  repository::list_prompt_runs(&conn, job_id.trim())
+}
+
+// Model calls upload media and may wait minutes on processing, so these run off the main thread.
+#[tauri::command(async)]
+fn preview_note(state: State<'_, AppState>, job_id: String) -> Result<PreviewNoteResponse, String> {
+  preview_job_note(&state, &job_id)
+}
+
+fn preview_job_note(state: &AppState, job_id: &str) -> Result<PreviewNoteResponse, String> {
+  let mut conn = state.conn()?;
   let maybe_job = repository::find_job_with_assets(&conn, job_id.trim())?;
   let job = maybe_job.ok_or_else(|| "job not found".to_string())?;
   let settings = repository::get_settings(&conn)?;
-  let ai_summary = generate_ai_summary(&settings, &job);
+  let extraction = optional_job_extraction(&mut conn, &settings, &job);
   Ok(PreviewNoteResponse {
-    markdown: build_note_markdown(&job, ai_summary.as_deref()),
+    markdown: build_note_markdown(
+      &job,
+      extraction.as_ref().map(|stored| &stored.extraction),
+      job_note_language(&job, &settings),
+    ),
   })
 }
 
-#[tauri::command]
+/// Discards the cached extraction for the job's current inputs by asking the model again.
+#[tauri::command(async)]
+fn regenerate_note(state: State<'_, AppState>, job_id: String) -> Result<PreviewNoteResponse, String> {
+  let mut conn = state.conn()?;
+  let maybe_job = repository::find_job_with_assets(&conn, job_id.trim())?;
+  let job = maybe_job.ok_or_else(|| "job not found".to_string())?;
+  let settings = repository::get_settings(&conn)?;
+  let extraction = job_extraction(&mut conn, &settings, &job, true, false)
+    .map_err(|error| format!("extraction failed: {error}"))?;
+  Ok(PreviewNoteResponse {
+    markdown: build_note_markdown(&job, Some(&extraction.extraction), job_note_language(&job, &settings)),
+  })
+}
+
+#[tauri::command(async)]
 fn publish_note(
   _app: AppHandle,
   state: State<'_, AppState>,
   job_id: String,
 ) -> Result<PublishNoteResponse, String> {
-  let conn = state.conn()?;
+  publish_job_note(&state, &job_id)
+}
+
+fn publish_job_note(state: &AppState, job_id: &str) -> Result<PublishNoteResponse, String> {
+  let mut conn = state.conn()?;
   let maybe_job = repository::find_job_with_assets(&conn, job_id.trim())?;
   let job = maybe_job.ok_or_else(|| "job not found".to_string())?;
   let settings = repository::get_settings(&conn)?;
-  let ai_summary = generate_ai_summary(&settings, &job);
-  let markdown = build_note_markdown(&job, ai_summary.as_deref());
-  obsidian::publish_note(&settings, &job.job.title, &markdown)
+  let extraction = optional_job_extraction(&mut conn, &settings, &job);
+  let markdown = build_note_markdown(
+    &job,
+    extraction.as_ref().map(|stored| &stored.extraction),
+    job_note_language(&job, &settings),
+  );
+  let response = obsidian::publish_note(&settings, &job.job.title, &markdown)?;
+  repository::insert_obsidian_note(&conn, &job.job.id, &response.note_path, &response.method, time_now_ms())?;
+  Ok(response)
 }
 
+#[cfg(test)]
+mod integration_tests;
+#[cfg(test)]
+mod test_support;
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
   tauri::Builder::default()
     .setup(|app| {
       let state = AppState::initialize(app.handle())?;
+      runner::spawn(state.db_pool.clone());
       app.manage(state);
-      let gemini = gemini::GeminiClient::new();
+      let gemini = gemini::GeminiClient::new(gemini::DEFAULT_BASE_URL);
       log::info!("gemini module status: {}", gemini.model_health());
       if cfg!(debug_assertions) {
         app.handle().plugin(
@@ -216,12 +1289,38 @@ pub fn run() {
       get_job,
       retry_job,
       cancel_job,
+      verify_media,
+      get_storage_usage,
+      get_token_usage,
+      get_budget_status,
+      list_job_events,
+      list_model_prices,
+      save_model_price,
+      delete_model_price,
+      collect_media_garbage,
+      get_asset_thumbnail,
+      get_subtitle_cues,
+      list_quarantine,
+      approve_quarantine_item,
+      discard_quarantine_item,
+      pick_capture_files,
+      pick_source_root,
       get_settings,
       save_settings,
       get_gemini_api_key_status,
+      validate_gemini_api_key,
+      list_gemini_models,
       save_gemini_api_key,
       clear_gemini_api_key,
+      has_openai_api_key,
+      save_openai_api_key,
+      clear_openai_api_key,
+      list_prompt_templates,
+      save_prompt_template,
+      delete_prompt_template,
+      list_prompt_runs,
       preview_note,
+      regenerate_note,
       publish_note
     ])
     .run(tauri::generate_context!())
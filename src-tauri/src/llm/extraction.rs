@@ -0,0 +1,126 @@
+use serde_json::{json, Value};
+
+use crate::models::{ExtractedEntity, Extraction};
+
+const MAX_LIST_ITEMS: usize = 20;
+
+fn string_list(description: &str) -> Value {
+  json!({ "type": "array", "description": description, "items": { "type": "string" } })
+}
+
+/// JSON Schema for structured output, mirroring `models::Extraction`. Written in the strict subset both
+/// Gemini and OpenAI-style `json_schema` accept: every property required, no extra properties.
+pub fn response_schema() -> Value {
+  json!({
+    "type": "object",
+    "properties": {
+      "summary": { "type": "string", "description": "Two or three sentence overview of the batch." },
+      "key_points": string_list("Exactly 3 concise takeaways."),
+      "topics": string_list("Short lowercase topics usable as tags."),
+      "entities": {
+        "type": "array",
+        "items": {
+          "type": "object",
+          "properties": {
+            "name": { "type": "string" },
+            "kind": { "type": "string", "description": "person, organization, place, product or other." }
+          },
+          "required": ["name", "kind"],
+          "additionalProperties": false
+        }
+      },
+      "action_items": string_list("Concrete follow-ups mentioned or implied; empty if none."),
+      "quotes": string_list("Verbatim notable quotes; empty if none."),
+      "language": { "type": "string", "description": "BCP 47 code of the source content's main language." }
+    },
+    "required": ["summary", "key_points", "topics", "entities", "action_items", "quotes", "language"],
+    "additionalProperties": false
+  })
+}
+
+fn clean_list(items: Vec<String>) -> Vec<String> {
+  let mut cleaned = Vec::<String>::new();
+  for item in items.into_iter().map(|item| item.trim().to_string()) {
+    if !item.is_empty() && !cleaned.iter().any(|existing| existing.eq_ignore_ascii_case(&item)) {
+      cleaned.push(item);
+    }
+  }
+  cleaned.truncate(MAX_LIST_ITEMS);
+  cleaned
+}
+
+/// Topics become note tags, so they are lowercased and stripped to characters Obsidian accepts.
+fn tag_from_topic(topic: &str) -> String {
+  topic
+    .trim()
+    .to_lowercase()
+    .split_whitespace()
+    .collect::<Vec<_>>()
+    .join("-")
+    .chars()
+    .filter(|ch| ch.is_alphanumeric() || matches!(ch, '-' | '_' | '/'))
+    .collect()
+}
+
+/// Parses the model's JSON and tidies it: trims and de-duplicates lists, turns topics into tags and
+/// drops nameless entities. Schema-constrained output can still be sloppy, so this never trusts shape.
+pub fn normalize(raw_output: &str) -> Result<Extraction, String> {
+  let parsed: Extraction = serde_json::from_str(raw_output.trim())
+    .map_err(|error| format!("model extraction is not valid JSON for the schema: {error}"))?;
+
+  let mut entities = Vec::<ExtractedEntity>::new();
+  for entity in parsed.entities {
+    let name = entity.name.trim().to_string();
+    if name.is_empty() || entities.iter().any(|existing| existing.name.eq_ignore_ascii_case(&name)) {
+      continue;
+    }
+    let kind = entity.kind.trim().to_lowercase();
+    entities.push(ExtractedEntity {
+      name,
+      kind: if kind.is_empty() { "other".to_string() } else { kind },
+    });
+  }
+  entities.truncate(MAX_LIST_ITEMS);
+
+  Ok(Extraction {
+    summary: parsed.summary.trim().to_string(),
+    key_points: clean_list(parsed.key_points),
+    topics: clean_list(parsed.topics.iter().map(|topic| tag_from_topic(topic)).collect()),
+    entities,
+    action_items: clean_list(parsed.action_items),
+    quotes: clean_list(parsed.quotes),
+    language: parsed.language.trim().to_string(),
+  })
+}
+
+/// Combines the extractions of consecutive parts of one job, in order: summaries become paragraphs, lists are
+/// concatenated and de-duplicated, and the first reported language wins.
+pub fn merge(parts: Vec<Extraction>) -> Extraction {
+  let mut merged = Extraction::default();
+  for part in parts {
+    if !part.summary.is_empty() {
+      if !merged.summary.is_empty() {
+        merged.summary.push_str("\n\n");
+      }
+      merged.summary.push_str(&part.summary);
+    }
+    merged.key_points.extend(part.key_points);
+    merged.topics.extend(part.topics);
+    for entity in part.entities {
+      if !merged.entities.iter().any(|existing| existing.name.eq_ignore_ascii_case(&entity.name)) {
+        merged.entities.push(entity);
+      }
+    }
+    merged.action_items.extend(part.action_items);
+    merged.quotes.extend(part.quotes);
+    if merged.language.is_empty() {
+      merged.language = part.language;
+    }
+  }
+  merged.key_points = clean_list(merged.key_points);
+  merged.topics = clean_list(merged.topics);
+  merged.entities.truncate(MAX_LIST_ITEMS);
+  merged.action_items = clean_list(merged.action_items);
+  merged.quotes = clean_list(merged.quotes);
+  merged
+}
@@ -0,0 +1,201 @@
+use std::{fmt, io::Read, time::Duration};
+
+use serde_json::Value;
+
+use crate::{
+  gemini,
+  models::{ModelPrice, SettingsPayload},
+  secrets,
+};
+
+pub mod extraction;
+pub mod openai;
+pub mod prompt;
+
+pub const PROVIDER_GEMINI: &str = "gemini";
+pub const PROVIDER_OPENAI_COMPATIBLE: &str = "openai_compatible";
+
+/// Media made available to a model: uploaded to the provider and referenced by URI, or sent inline.
+#[derive(Debug, Clone)]
+pub enum Attachment {
+  Remote { name: String, uri: String, mime_type: String },
+  Inline { mime_type: String, data_base64: String },
+}
+
+pub struct GenerationRequest<'a> {
+  pub model: &'a str,
+  pub prompt: &'a str,
+  /// `(label, attachment)` pairs; each is introduced to the model by its label.
+  pub attachments: &'a [(String, Attachment)],
+  /// JSON Schema the reply must follow; `None` asks for plain text.
+  pub response_schema: Option<&'a Value>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TokenUsage {
+  pub prompt_tokens: Option<i64>,
+  /// Output tokens as billed, thinking tokens included.
+  pub response_tokens: Option<i64>,
+  /// Part of `prompt_tokens` served from the provider's context cache.
+  pub cached_tokens: Option<i64>,
+  pub total_tokens: Option<i64>,
+}
+
+impl TokenUsage {
+  /// Estimated cost in USD under `price`; `None` when the provider reported no token counts.
+  pub fn estimate_cost_usd(&self, price: &ModelPrice) -> Option<f64> {
+    if self.prompt_tokens.is_none() && self.response_tokens.is_none() {
+      return None;
+    }
+    let cached = self.cached_tokens.unwrap_or(0);
+    let uncached = (self.prompt_tokens.unwrap_or(0) - cached).max(0);
+    let micro_dollars = uncached as f64 * price.input_usd_per_mtok
+      + cached as f64 * price.cached_input_usd_per_mtok
+      + self.response_tokens.unwrap_or(0) as f64 * price.output_usd_per_mtok;
+    Some(micro_dollars / 1_000_000.0)
+  }
+}
+
+/// What went wrong with a model call, coarse enough for the job runner to decide between retrying later
+/// and giving up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelErrorKind {
+  /// The key is missing, invalid or lacks permission.
+  Auth,
+  /// A quota that does not refill within minutes, e.g. requests per day.
+  Quota,
+  /// Too many requests right now.
+  RateLimited,
+  /// The provider failed or is overloaded.
+  Server,
+  /// The provider could not be reached.
+  Network,
+  /// The prompt or the answer was blocked by safety filters.
+  SafetyBlocked,
+  /// The provider rejected the request itself; sending it again will not help.
+  InvalidRequest,
+  /// The reply arrived but held no usable output.
+  MalformedResponse,
+}
+
+impl ModelErrorKind {
+  pub fn from_status(status: u16) -> Self {
+    match status {
+      401 | 403 => Self::Auth,
+      429 => Self::RateLimited,
+      500..=599 => Self::Server,
+      _ => Self::InvalidRequest,
+    }
+  }
+
+  /// Whether the same request may succeed later without any change on our side.
+  pub fn is_transient(self) -> bool {
+    matches!(self, Self::Quota | Self::RateLimited | Self::Server | Self::Network)
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct ModelError {
+  pub kind: ModelErrorKind,
+  pub message: String,
+  /// How long the provider asked us to wait before trying again.
+  pub retry_after: Option<Duration>,
+}
+
+impl ModelError {
+  pub fn new(kind: ModelErrorKind, message: impl Into<String>) -> Self {
+    Self {
+      kind,
+      message: message.into(),
+      retry_after: None,
+    }
+  }
+}
+
+impl fmt::Display for ModelError {
+  fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+    formatter.write_str(&self.message)
+  }
+}
+
+/// Longest retry delay taken from a provider; anything beyond it is treated as this, so a bogus value can
+/// neither overflow a deadline nor park a job for years.
+pub const MAX_RETRY_AFTER: Duration = Duration::from_secs(60 * 60);
+
+/// Reads a `Retry-After` header given in seconds; the HTTP-date form is not used by model APIs.
+pub fn parse_retry_after(value: Option<&str>) -> Option<Duration> {
+  value?.trim().parse::<u64>().ok().map(|seconds| Duration::from_secs(seconds).min(MAX_RETRY_AFTER))
+}
+
+/// One generation request as sent and received, for the `prompt_run` audit log. Inline media is
+/// elided from `request_body`.
+#[derive(Debug)]
+pub struct ModelCall {
+  pub request_body: String,
+  pub response_body: Option<String>,
+  pub usage: TokenUsage,
+  pub latency_ms: i64,
+  /// The model's text output, or why there is none.
+  pub outcome: Result<String, ModelError>,
+}
+
+impl ModelCall {
+  pub fn failed(request_body: String, error: ModelError) -> Self {
+    Self {
+      request_body,
+      response_body: None,
+      usage: TokenUsage::default(),
+      latency_ms: 0,
+      outcome: Err(error),
+    }
+  }
+}
+
+/// A text-generation backend with multimodal input and structured output.
+pub trait LlmProvider {
+  /// Short identifier recorded with results, e.g. `gemini`.
+  fn name(&self) -> &'static str;
+
+  /// Makes a piece of media referenceable from `generate`. Fails for media the backend cannot take.
+  fn attach(&self, label: &str, mime_type: &str, reader: Box<dyn Read + Send>) -> Result<Attachment, String>;
+
+  /// Frees whatever `attach` left on the provider's side.
+  fn release(&self, attachment: &Attachment) -> Result<(), String>;
+
+  fn generate(&self, request: &GenerationRequest<'_>) -> ModelCall;
+}
+
+/// Model name configured for the selected provider.
+pub fn model_for(settings: &SettingsPayload) -> &str {
+  match settings.llm_provider.as_str() {
+    PROVIDER_OPENAI_COMPATIBLE => settings.openai_model.trim(),
+    _ => settings.gemini_model.trim(),
+  }
+}
+
+/// Builds the provider selected in settings, resolving its credentials.
+pub fn provider_for(settings: &SettingsPayload) -> Result<Box<dyn LlmProvider>, String> {
+  match settings.llm_provider.as_str() {
+    PROVIDER_OPENAI_COMPATIBLE => Ok(Box::new(openai::OpenAiCompatibleProvider::new(
+      &settings.openai_base_url,
+      secrets::resolve_openai_api_key()?,
+    ))),
+    _ => {
+      let api_key = secrets::resolve_gemini_api_key()?.ok_or_else(|| "missing Gemini API key".to_string())?;
+      Ok(Box::new(gemini::GeminiProvider::new(
+        &settings.gemini_base_url,
+        api_key,
+        u32::try_from(settings.gemini_requests_per_minute).unwrap_or(0),
+      )))
+    }
+  }
+}
+
+pub fn validate_provider(value: &str) -> Result<&str, String> {
+  match value.trim() {
+    provider @ (PROVIDER_GEMINI | PROVIDER_OPENAI_COMPATIBLE) => Ok(provider),
+    _ => Err(format!(
+      "llm_provider must be {PROVIDER_GEMINI} or {PROVIDER_OPENAI_COMPATIBLE}"
+    )),
+  }
+}
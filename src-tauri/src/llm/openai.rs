@@ -0,0 +1,199 @@
+use std::{io::Read, time::Instant};
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use reqwest::blocking::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::{
+  parse_retry_after, Attachment, GenerationRequest, LlmProvider, ModelCall, ModelError, ModelErrorKind, TokenUsage,
+  PROVIDER_OPENAI_COMPATIBLE,
+};
+
+/// Chat completions carry media inline, so anything bigger than this is sent as a path only.
+const MAX_INLINE_BYTES: u64 = 20 * 1024 * 1024;
+
+#[derive(Debug, Deserialize)]
+struct ChatMessage {
+  content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChoice {
+  message: ChatMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct PromptTokensDetails {
+  cached_tokens: Option<i64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ChatUsage {
+  prompt_tokens: Option<i64>,
+  completion_tokens: Option<i64>,
+  total_tokens: Option<i64>,
+  prompt_tokens_details: Option<PromptTokensDetails>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+  choices: Vec<ChatChoice>,
+  usage: Option<ChatUsage>,
+}
+
+/// `input_audio` only takes these two formats.
+fn audio_format(mime_type: &str) -> Option<&'static str> {
+  match mime_type {
+    "audio/wav" | "audio/x-wav" | "audio/wave" => Some("wav"),
+    "audio/mpeg" | "audio/mp3" => Some("mp3"),
+    _ => None,
+  }
+}
+
+/// `LlmProvider` for servers speaking the OpenAI chat completions API: OpenAI itself, Ollama, LM Studio,
+/// vLLM and the like. Images and WAV/MP3 audio are sent inline; other media is not supported.
+pub struct OpenAiCompatibleProvider {
+  http: Client,
+  base_url: String,
+  api_key: Option<String>,
+}
+
+impl OpenAiCompatibleProvider {
+  pub fn new(base_url: &str, api_key: Option<String>) -> Self {
+    Self {
+      http: Client::new(),
+      base_url: base_url.trim().trim_end_matches('/').to_string(),
+      api_key,
+    }
+  }
+
+  fn request_body(&self, request: &GenerationRequest<'_>, redact: bool) -> Value {
+    let mut content = Vec::<Value>::new();
+    for (label, attachment) in request.attachments {
+      let Attachment::Inline { mime_type, data_base64 } = attachment else {
+        continue;
+      };
+      let data = if redact {
+        format!("<{} base64 characters omitted>", data_base64.len())
+      } else {
+        data_base64.clone()
+      };
+      content.push(json!({ "type": "text", "text": format!("Attached file: {label}") }));
+      content.push(match audio_format(mime_type) {
+        Some(format) => json!({ "type": "input_audio", "input_audio": { "data": data, "format": format } }),
+        None => json!({ "type": "image_url", "image_url": { "url": format!("data:{mime_type};base64,{data}") } }),
+      });
+    }
+    content.push(json!({ "type": "text", "text": request.prompt }));
+
+    let mut body = json!({
+      "model": request.model.trim(),
+      "messages": [{ "role": "user", "content": content }],
+      "temperature": 0.2
+    });
+    if let Some(schema) = request.response_schema {
+      body["response_format"] = json!({
+        "type": "json_schema",
+        "json_schema": { "name": "extraction", "schema": schema, "strict": true }
+      });
+    }
+    body
+  }
+}
+
+impl LlmProvider for OpenAiCompatibleProvider {
+  fn name(&self) -> &'static str {
+    PROVIDER_OPENAI_COMPATIBLE
+  }
+
+  fn attach(&self, _label: &str, mime_type: &str, reader: Box<dyn Read + Send>) -> Result<Attachment, String> {
+    if !mime_type.starts_with("image/") && audio_format(mime_type).is_none() {
+      return Err(format!("{mime_type} cannot be sent to an OpenAI-compatible backend"));
+    }
+    let mut bytes = Vec::new();
+    reader
+      .take(MAX_INLINE_BYTES + 1)
+      .read_to_end(&mut bytes)
+      .map_err(|error| format!("failed to read media for inline upload: {error}"))?;
+    if bytes.len() as u64 > MAX_INLINE_BYTES {
+      return Err(format!("media exceeds the {MAX_INLINE_BYTES} byte inline limit"));
+    }
+    Ok(Attachment::Inline {
+      mime_type: mime_type.to_string(),
+      data_base64: BASE64_STANDARD.encode(bytes),
+    })
+  }
+
+  fn release(&self, _attachment: &Attachment) -> Result<(), String> {
+    Ok(())
+  }
+
+  fn generate(&self, request: &GenerationRequest<'_>) -> ModelCall {
+    let body = self.request_body(request, false);
+    let audited_body = self.request_body(request, true).to_string();
+    if self.base_url.is_empty() {
+      let error = ModelError::new(ModelErrorKind::InvalidRequest, "OpenAI-compatible base URL is not configured");
+      return ModelCall::failed(audited_body, error);
+    }
+
+    let mut http_request = self.http.post(format!("{}/chat/completions", self.base_url)).json(&body);
+    if let Some(api_key) = self.api_key.as_deref() {
+      http_request = http_request.bearer_auth(api_key);
+    }
+    let started = Instant::now();
+    let response = match http_request.send() {
+      Ok(response) => response,
+      Err(error) => {
+        let error = ModelError::new(ModelErrorKind::Network, format!("failed to call OpenAI-compatible API: {error}"));
+        let mut call = ModelCall::failed(audited_body, error);
+        call.latency_ms = started.elapsed().as_millis() as i64;
+        return call;
+      }
+    };
+    let status = response.status();
+    let retry_after = response
+      .headers()
+      .get(reqwest::header::RETRY_AFTER)
+      .and_then(|value| parse_retry_after(value.to_str().ok()));
+    let response_body = response
+      .text()
+      .unwrap_or_else(|_| "unable to read response body".to_string());
+    let mut call = ModelCall::failed(audited_body, ModelError::new(ModelErrorKind::Server, String::new()));
+    call.latency_ms = started.elapsed().as_millis() as i64;
+    call.response_body = Some(response_body.clone());
+
+    if !status.is_success() {
+      call.outcome = Err(ModelError {
+        kind: ModelErrorKind::from_status(status.as_u16()),
+        message: format!("OpenAI-compatible API returned {status}: {response_body}"),
+        retry_after,
+      });
+      return call;
+    }
+
+    call.outcome = serde_json::from_str::<ChatResponse>(&response_body)
+      .map_err(|error| {
+        let message = format!("failed to parse OpenAI-compatible API response: {error}");
+        ModelError::new(ModelErrorKind::MalformedResponse, message)
+      })
+      .and_then(|payload| {
+        let usage = payload.usage.unwrap_or_default();
+        call.usage = TokenUsage {
+          prompt_tokens: usage.prompt_tokens,
+          response_tokens: usage.completion_tokens,
+          cached_tokens: usage.prompt_tokens_details.and_then(|details| details.cached_tokens),
+          total_tokens: usage.total_tokens,
+        };
+        payload
+          .choices
+          .into_iter()
+          .find_map(|choice| choice.message.content)
+          .ok_or_else(|| {
+            let message = "OpenAI-compatible API response did not contain text output";
+            ModelError::new(ModelErrorKind::MalformedResponse, message)
+          })
+      });
+    call
+  }
+}
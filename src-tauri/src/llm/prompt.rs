@@ -0,0 +1,123 @@
+/// Template used when settings point at nothing else; it is always seeded and cannot be deleted.
+pub const DEFAULT_TEMPLATE_KEY: &str = "default_extraction";
+
+const DEFAULT_TEMPLATE: &str = include_str!("../prompts/default_extraction.md");
+
+const MEETING_TEMPLATE: &str = include_str!("../prompts/meeting_notes.md");
+
+/// Templates inserted on startup when missing; user edits to them are kept.
+pub const SEEDED_TEMPLATES: [(&str, &str); 2] = [
+  (DEFAULT_TEMPLATE_KEY, DEFAULT_TEMPLATE),
+  ("meeting_notes", MEETING_TEMPLATE),
+];
+
+/// Placeholders a template may use, written as `{{name}}`.
+pub const VARIABLES: [&str; 7] = [
+  "title",
+  "file_list",
+  "media_metadata",
+  "language",
+  "vault_context",
+  "text_captures",
+  "transcripts",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct PromptVariables {
+  pub title: String,
+  pub file_list: String,
+  pub media_metadata: String,
+  pub language: String,
+  pub vault_context: String,
+  pub text_captures: String,
+  pub transcripts: String,
+}
+
+impl PromptVariables {
+  fn value(&self, name: &str) -> Option<&str> {
+    let value = match name {
+      "title" => &self.title,
+      "file_list" => &self.file_list,
+      "media_metadata" => &self.media_metadata,
+      "language" => &self.language,
+      "vault_context" => &self.vault_context,
+      "text_captures" => &self.text_captures,
+      "transcripts" => &self.transcripts,
+      _ => return None,
+    };
+    Some(value)
+  }
+}
+
+/// Names of the `{{placeholder}}`s in a template, in order.
+fn placeholders(template: &str) -> Vec<&str> {
+  let mut names = Vec::new();
+  let mut rest = template;
+  while let Some(start) = rest.find("{{") {
+    let Some(length) = rest[start + 2..].find("}}") else {
+      break;
+    };
+    names.push(rest[start + 2..start + 2 + length].trim());
+    rest = &rest[start + 2 + length + 2..];
+  }
+  names
+}
+
+pub fn validate(template: &str) -> Result<(), String> {
+  if template.trim().is_empty() {
+    return Err("prompt template content is required".to_string());
+  }
+  if let Some(unknown) = placeholders(template)
+    .into_iter()
+    .find(|name| !VARIABLES.contains(name))
+  {
+    return Err(format!(
+      "unknown prompt variable '{{{{{unknown}}}}}'; expected one of: {}",
+      VARIABLES.join(", ")
+    ));
+  }
+  Ok(())
+}
+
+/// Substitutes every known placeholder; anything else is left verbatim so the prompt stays inspectable.
+pub fn render(template: &str, variables: &PromptVariables) -> String {
+  let mut rendered = String::with_capacity(template.len());
+  let mut rest = template;
+  while let Some(start) = rest.find("{{") {
+    let Some(length) = rest[start + 2..].find("}}") else {
+      break;
+    };
+    let end = start + 2 + length + 2;
+    rendered.push_str(&rest[..start]);
+    match variables.value(rest[start + 2..start + 2 + length].trim()) {
+      Some(value) => rendered.push_str(value),
+      None => rendered.push_str(&rest[start..end]),
+    }
+    rest = &rest[end..];
+  }
+  rendered.push_str(rest);
+  rendered
+}
+
+pub fn file_list(items: &[String]) -> String {
+  items
+    .iter()
+    .map(|item| format!("- {item}"))
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// Each capture as a fenced section with a leading blank line, so an empty list renders as nothing.
+pub fn text_capture_sections(text_captures: &[String]) -> String {
+  text_captures
+    .iter()
+    .map(|text| format!("\n\nText capture:\n---\n{}\n---", text.trim()))
+    .collect()
+}
+
+pub fn transcript_sections(transcripts: &[(String, String)]) -> String {
+  transcripts
+    .iter()
+    .map(|(source, transcript)| format!("\n\nTimed transcript of {source}:\n---\n{transcript}\n---"))
+    .collect()
+}
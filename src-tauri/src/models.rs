@@ -8,6 +8,8 @@ pub enum JobStatus {
   Completed,
   Failed,
   Cancelled,
+  /// Parked by the job runner until a usage budget allows model calls again.
+  WaitingBudget,
 }
 
 impl JobStatus {
@@ -18,6 +20,7 @@ impl JobStatus {
       JobStatus::Completed => "completed",
       JobStatus::Failed => "failed",
       JobStatus::Cancelled => "cancelled",
+      JobStatus::WaitingBudget => "waiting_budget",
     }
   }
 }
@@ -40,10 +43,44 @@ impl WriteMode {
   }
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IngestMode {
+  Copy,
+  Move,
+  Reference,
+}
+
+impl IngestMode {
+  pub fn parse(value: &str) -> Self {
+    match value.trim() {
+      "move" => IngestMode::Move,
+      "reference" => IngestMode::Reference,
+      _ => IngestMode::Copy,
+    }
+  }
+
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      IngestMode::Copy => "copy",
+      IngestMode::Move => "move",
+      IngestMode::Reference => "reference",
+    }
+  }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct EnqueueIngestionRequest {
+  #[serde(default)]
   pub file_paths: Vec<String>,
   pub note_title: Option<String>,
+  #[serde(default)]
+  pub text_content: Option<String>,
+  #[serde(default)]
+  pub ingest_mode: Option<String>,
+  /// Language code or `source`; falls back to the note language setting.
+  #[serde(default)]
+  pub note_language: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -59,6 +96,9 @@ pub struct JobSummary {
   pub created_at: i64,
   pub updated_at: i64,
   pub asset_count: i64,
+  pub thumbnail_asset_id: Option<i64>,
+  /// Per-job override of the note language setting.
+  pub note_language: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -71,12 +111,112 @@ pub struct JobAsset {
   pub mime_type: String,
   pub size_bytes: i64,
   pub sha256: String,
+  pub ingest_mode: String,
+  pub encrypted: bool,
+  pub archive_path: Option<String>,
+  pub archive_entry: Option<String>,
+  pub derivative_path: Option<String>,
+  pub derivative_mime_type: Option<String>,
+  pub derivative_size_bytes: Option<i64>,
+  pub thumbnail_path: Option<String>,
+  pub perceptual_hash: Option<i64>,
+  pub near_duplicate_of: Option<i64>,
+  pub duration_ms: Option<i64>,
+  pub parent_asset_id: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MediaChunk {
+  pub id: i64,
+  pub asset_id: i64,
+  pub chunk_index: i64,
+  pub start_ms: i64,
+  pub end_ms: i64,
+  pub storage_path: String,
+  pub size_bytes: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SubtitleCue {
+  pub id: i64,
+  pub asset_id: i64,
+  pub cue_index: i64,
+  pub start_ms: i64,
+  pub end_ms: i64,
+  pub text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuarantineItem {
+  pub id: i64,
+  pub job_id: String,
+  pub original_path: String,
+  pub storage_path: String,
+  pub media_type: String,
+  pub mime_type: String,
+  pub size_bytes: i64,
+  pub sha256: String,
+  pub ingest_mode: String,
+  pub encrypted: bool,
+  pub reason: String,
+  pub status: String,
+  pub created_at: i64,
+  pub resolved_at: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct JobDetails {
   pub job: JobSummary,
   pub assets: Vec<JobAsset>,
+  pub chunks: Vec<MediaChunk>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MediaIssue {
+  pub asset_id: i64,
+  pub job_id: String,
+  pub storage_path: String,
+  pub kind: String,
+  pub detail: String,
+  pub repaired: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VerifyMediaReport {
+  pub checked: i64,
+  pub healthy: i64,
+  pub repaired: i64,
+  pub issues: Vec<MediaIssue>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JobStorageUsage {
+  pub job_id: String,
+  pub title: String,
+  pub bytes: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MonthlyStorageUsage {
+  pub month: String,
+  pub bytes: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StorageUsageReport {
+  pub total_bytes: i64,
+  pub quota_bytes: i64,
+  pub per_job: Vec<JobStorageUsage>,
+  pub per_month: Vec<MonthlyStorageUsage>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GarbageCollectionReport {
+  pub dry_run: bool,
+  pub orphaned_files: i64,
+  pub orphaned_bytes: i64,
+  pub evicted_assets: i64,
+  pub evicted_bytes: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -90,12 +230,70 @@ pub struct SettingsPayload {
   pub obsidian_cli_path: String,
   pub gemini_model: String,
   pub write_mode: String,
+  pub media_quota_bytes: i64,
+  pub media_retention_days: i64,
+  pub ingest_mode: String,
+  pub encrypt_media: bool,
+  pub image_derivatives_enabled: bool,
+  pub image_max_dimension: i64,
+  pub image_jpeg_quality: i64,
+  /// Folders the backend may ingest from without a per-file dialog selection.
+  pub allowed_source_roots: Vec<String>,
+  /// Key of the `prompt_template` row used to render extraction prompts.
+  pub prompt_template_key: String,
+  /// Language code for generated notes, or `source` to follow the captured content.
+  pub note_language: String,
+  /// Backend used for extractions: `gemini` or `openai_compatible`.
+  pub llm_provider: String,
+  /// Base URL of the OpenAI-compatible server, e.g. `http://localhost:11434/v1` for Ollama.
+  pub openai_base_url: String,
+  pub openai_model: String,
+  /// Root of the Gemini API, replaceable by a proxy, regional gateway or local stand-in.
+  pub gemini_base_url: String,
+  /// Usage caps per UTC day and month; zero means unlimited.
+  pub daily_token_budget: i64,
+  pub monthly_token_budget: i64,
+  pub daily_cost_budget_usd: f64,
+  pub monthly_cost_budget_usd: f64,
+  /// Client-side cap on Gemini generation calls per model; zero disables it.
+  pub gemini_requests_per_minute: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GeminiApiKeyStatus {
   pub configured: bool,
   pub source: String,
+  pub last_validation: Option<KeyValidation>,
+}
+
+/// Outcome of checking an API key against its provider.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct KeyValidation {
+  pub ok: bool,
+  /// Why the check failed; `None` when it passed.
+  pub message: Option<String>,
+  pub validated_at: i64,
+}
+
+/// A Gemini model that can run `generateContent` for the configured key.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GeminiModelInfo {
+  /// Name as used in `gemini_model`, without the `models/` prefix.
+  pub id: String,
+  pub display_name: String,
+  pub description: Option<String>,
+  pub input_token_limit: Option<i64>,
+  pub output_token_limit: Option<i64>,
+  /// Guessed from the model family because `models.list` does not report modalities. Shown to the user as a
+  /// hint only; nothing is accepted or rejected based on it.
+  pub assumed_input_modalities: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GeminiKeyCheck {
+  pub validation: KeyValidation,
+  /// Models available to the key; empty when the check failed.
+  pub models: Vec<GeminiModelInfo>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -108,3 +306,125 @@ pub struct PublishNoteResponse {
   pub note_path: String,
   pub method: String,
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PromptTemplate {
+  pub id: i64,
+  pub key: String,
+  pub content: String,
+  pub updated_at: i64,
+}
+
+/// Audit record of one model request: what was sent, what came back and what it cost in tokens.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PromptRun {
+  pub id: i64,
+  pub job_id: String,
+  pub prompt_template_key: String,
+  pub model: String,
+  pub prompt_body: String,
+  pub response_body: Option<String>,
+  pub latency_ms: Option<i64>,
+  pub prompt_tokens: Option<i64>,
+  pub response_tokens: Option<i64>,
+  pub total_tokens: Option<i64>,
+  pub error: Option<String>,
+  pub created_at: i64,
+  pub cached_tokens: Option<i64>,
+  /// Estimate from the `model_price` row at the time of the call; `None` for unpriced models.
+  pub cost_usd: Option<f64>,
+}
+
+/// USD per million tokens for one model label, as recorded in `prompt_run.model` (`provider/model`).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModelPrice {
+  pub model: String,
+  pub input_usd_per_mtok: f64,
+  pub output_usd_per_mtok: f64,
+  pub cached_input_usd_per_mtok: f64,
+  #[serde(default)]
+  pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TokenTotals {
+  pub calls: i64,
+  pub prompt_tokens: i64,
+  pub response_tokens: i64,
+  pub cached_tokens: i64,
+  pub total_tokens: i64,
+  pub cost_usd: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JobTokenUsage {
+  pub job_id: String,
+  pub title: String,
+  pub totals: TokenTotals,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MonthlyTokenUsage {
+  pub month: String,
+  pub totals: TokenTotals,
+}
+
+/// Usage in the current budget windows and, when a cap is reached, which one.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BudgetStatus {
+  pub today: TokenTotals,
+  pub this_month: TokenTotals,
+  pub exceeded: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JobEvent {
+  pub id: i64,
+  pub job_id: String,
+  pub kind: String,
+  pub message: String,
+  pub created_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TokenUsageReport {
+  pub totals: TokenTotals,
+  pub per_job: Vec<JobTokenUsage>,
+  pub per_month: Vec<MonthlyTokenUsage>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ExtractedEntity {
+  pub name: String,
+  /// Free-form category such as person, organization, place or product.
+  #[serde(default)]
+  pub kind: String,
+}
+
+/// Typed result of a structured extraction; stored as `normalized_output` and rendered into notes.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Extraction {
+  #[serde(default)]
+  pub summary: String,
+  #[serde(default)]
+  pub key_points: Vec<String>,
+  #[serde(default)]
+  pub topics: Vec<String>,
+  #[serde(default)]
+  pub entities: Vec<ExtractedEntity>,
+  #[serde(default)]
+  pub action_items: Vec<String>,
+  #[serde(default)]
+  pub quotes: Vec<String>,
+  #[serde(default)]
+  pub language: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExtractionResult {
+  pub id: i64,
+  pub job_id: String,
+  pub model: String,
+  pub extraction: Extraction,
+  pub created_at: i64,
+}
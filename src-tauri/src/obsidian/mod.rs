@@ -128,6 +128,38 @@ fn try_cli_write(settings: &SettingsPayload, vault_path: &Path, title: &str, mar
   Err(format!("failed to publish through Obsidian CLI candidates: {}", errors.join(" | ")))
 }
 
+const MAX_CONTEXT_FOLDERS: usize = 20;
+
+/// Short description of the target vault for prompts: its name and top-level folders, so the model can
+/// pick topics that fit the existing structure. Empty when no vault can be resolved.
+pub fn vault_context(settings: &SettingsPayload) -> String {
+  let Ok(vault_path) = resolve_vault_path(settings) else {
+    return String::new();
+  };
+  let name = vault_path
+    .file_name()
+    .map(|name| name.to_string_lossy().to_string())
+    .unwrap_or_default();
+
+  let mut folders = fs::read_dir(&vault_path)
+    .map(|entries| {
+      entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+        .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+        .filter(|folder| !folder.starts_with('.'))
+        .collect::<Vec<_>>()
+    })
+    .unwrap_or_default();
+  folders.sort();
+  folders.truncate(MAX_CONTEXT_FOLDERS);
+
+  if folders.is_empty() {
+    return format!("Vault: {name}");
+  }
+  format!("Vault: {name}\nTop-level folders: {}", folders.join(", "))
+}
+
 pub fn publish_note(settings: &SettingsPayload, title: &str, markdown: &str) -> Result<PublishNoteResponse, String> {
   let vault_path = resolve_vault_path(settings)?;
   let write_mode = WriteMode::parse(&settings.write_mode);
@@ -0,0 +1,132 @@
+use std::{thread, time::Duration};
+
+use crate::{
+  app_state::{DbConnection, DbPool},
+  budget,
+  db::repository,
+  job_extraction,
+  llm::{self, ModelError, ModelErrorKind},
+  models::JobStatus,
+  time_now_ms, ExtractionError,
+};
+
+/// How often the runner looks for queued work and re-checks parked jobs against the budget.
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+/// Retries after transient model errors before a job is marked failed.
+const MAX_RETRIES: i64 = 5;
+
+/// Wait before retrying when the provider did not say how long.
+fn default_retry_delay(kind: ModelErrorKind) -> Duration {
+  match kind {
+    ModelErrorKind::Quota => Duration::from_secs(60 * 60),
+    ModelErrorKind::RateLimited => Duration::from_secs(60),
+    _ => Duration::from_secs(2 * 60),
+  }
+}
+
+/// Starts the background job runner. Jobs left `processing` by a previous session are queued again.
+pub fn spawn(pool: DbPool) {
+  thread::spawn(move || {
+    match pool.get() {
+      Ok(conn) => match repository::requeue_interrupted_jobs(&conn, time_now_ms()) {
+        Ok(0) => {}
+        Ok(count) => log::info!("requeued {count} interrupted jobs"),
+        Err(error) => log::warn!("{error}"),
+      },
+      Err(error) => log::warn!("job runner could not borrow a connection: {error}"),
+    }
+    loop {
+      if let Err(error) = tick(&pool) {
+        log::warn!("job runner pass failed: {error}");
+      }
+      thread::sleep(POLL_INTERVAL);
+    }
+  });
+}
+
+fn record_event(conn: &DbConnection, job_id: &str, kind: &str, message: &str) {
+  if let Err(error) = repository::insert_job_event(conn, job_id, kind, message, time_now_ms()) {
+    log::warn!("job {job_id}: {error}");
+  }
+}
+
+/// Moves `job_id` to `status` and records why. A job changed underneath the runner, e.g. cancelled from
+/// the UI, is left as it is.
+fn transition(conn: &DbConnection, job_id: &str, status: JobStatus, kind: &str, message: &str) {
+  match repository::update_job_status(conn, job_id, status.as_str(), time_now_ms()) {
+    Ok(true) => record_event(conn, job_id, kind, message),
+    Ok(false) => {}
+    Err(error) => log::warn!("job {job_id}: {error}"),
+  }
+}
+
+/// Queues the job again after a transient model error, or fails it once its retries are used up.
+fn retry_later(conn: &DbConnection, job_id: &str, error: &ModelError) {
+  let delay = error.retry_after.unwrap_or_else(|| default_retry_delay(error.kind));
+  let next_attempt_at = time_now_ms() + delay.as_millis() as i64;
+  match repository::schedule_job_retry(conn, job_id, MAX_RETRIES, next_attempt_at, time_now_ms()) {
+    Ok(true) => {
+      let message = format!("{error} (retrying in {}s)", delay.as_secs());
+      record_event(conn, job_id, "retry_scheduled", &message);
+    }
+    Ok(false) => {
+      let message = format!("giving up after {MAX_RETRIES} retries: {error}");
+      transition(conn, job_id, JobStatus::Failed, "extraction_failed", &message);
+    }
+    Err(error) => log::warn!("job {job_id}: {error}"),
+  }
+}
+
+/// One pass: releases parked jobs when the budget allows, then runs the AI step of every queued job that
+/// is due, parking the rest as soon as a cap is reached.
+pub fn tick(pool: &DbPool) -> Result<(), String> {
+  let mut conn = pool
+    .get()
+    .map_err(|error| format!("failed to borrow sqlite connection: {error}"))?;
+  let settings = repository::get_settings(&conn)?;
+
+  if budget::status(&conn, &settings, time_now_ms())?.exceeded.is_none() {
+    for job_id in repository::list_job_ids_with_status(&conn, JobStatus::WaitingBudget.as_str())? {
+      transition(&conn, &job_id, JobStatus::Queued, "budget_resumed", "usage budget available again");
+    }
+  }
+
+  // Without a key there is nothing to run the jobs with; they stay queued until one is configured.
+  if llm::provider_for(&settings).is_err() {
+    return Ok(());
+  }
+  for job_id in repository::list_ready_job_ids(&conn, time_now_ms())? {
+    if let Some(reason) = budget::status(&conn, &settings, time_now_ms())?.exceeded {
+      transition(&conn, &job_id, JobStatus::WaitingBudget, "budget_wait", &reason);
+      continue;
+    }
+    // A preview started from the UI may have claimed the job since it was listed.
+    match repository::claim_queued_job(&conn, &job_id, time_now_ms()) {
+      Ok(true) => {}
+      Ok(false) => continue,
+      Err(error) => {
+        log::warn!("job {job_id}: {error}");
+        continue;
+      }
+    }
+    let Some(job) = repository::find_job_with_assets(&conn, &job_id)? else {
+      continue;
+    };
+
+    match job_extraction(&mut conn, &settings, &job, false, true) {
+      Ok(_) => transition(&conn, &job_id, JobStatus::Completed, "extraction_completed", "extraction ready"),
+      Err(ExtractionError::BudgetExceeded(reason)) => {
+        transition(&conn, &job_id, JobStatus::WaitingBudget, "budget_wait", &reason)
+      }
+      Err(ExtractionError::Model(error)) if error.kind.is_transient() => {
+        retry_later(&conn, &job_id, &error);
+        // The rest of the queue would only run into the same limit.
+        if matches!(error.kind, ModelErrorKind::RateLimited | ModelErrorKind::Quota) {
+          break;
+        }
+      }
+      Err(error) => transition(&conn, &job_id, JobStatus::Failed, "extraction_failed", &error.to_string()),
+    }
+  }
+  Ok(())
+}
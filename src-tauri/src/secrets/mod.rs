@@ -1,7 +1,11 @@
 use keyring::Entry;
 
+use crate::ingestion::crypto::{self, MediaKey};
+
 const SERVICE_NAME: &str = "com.israeltoledo.obsidianaiagent";
 const GEMINI_KEY_ENTRY: &str = "gemini_api_key";
+const MEDIA_KEY_ENTRY: &str = "media_encryption_key";
+const OPENAI_KEY_ENTRY: &str = "openai_api_key";
 
 #[derive(Debug, Clone, Copy)]
 pub enum GeminiApiKeySource {
@@ -86,3 +90,70 @@ pub fn clear_gemini_api_key() -> Result<(), String> {
     Err(error) => Err(format!("failed to clear Gemini API key from keychain: {error}")),
   }
 }
+
+fn openai_entry() -> Result<Entry, String> {
+  Entry::new(SERVICE_NAME, OPENAI_KEY_ENTRY)
+    .map_err(|error| format!("failed to initialize keychain entry: {error}"))
+}
+
+/// Key for the OpenAI-compatible backend. Optional: local servers such as Ollama accept unauthenticated
+/// requests, so a missing key is not an error.
+pub fn resolve_openai_api_key() -> Result<Option<String>, String> {
+  let entry = openai_entry()?;
+  match entry.get_password() {
+    Ok(secret) if !secret.trim().is_empty() => return Ok(Some(secret.trim().to_string())),
+    Ok(_) | Err(keyring::Error::NoEntry) => {}
+    Err(error) => return Err(format!("failed to read OpenAI API key from keychain: {error}")),
+  }
+  Ok(
+    std::env::var("OPENAI_API_KEY")
+      .ok()
+      .map(|value| value.trim().to_string())
+      .filter(|value| !value.is_empty()),
+  )
+}
+
+pub fn save_openai_api_key(value: &str) -> Result<(), String> {
+  let trimmed = value.trim();
+  if trimmed.is_empty() {
+    return Err("OpenAI API key cannot be empty".to_string());
+  }
+
+  openai_entry()?
+    .set_password(trimmed)
+    .map_err(|error| format!("failed to save OpenAI API key to keychain: {error}"))
+}
+
+pub fn clear_openai_api_key() -> Result<(), String> {
+  match openai_entry()?.delete_credential() {
+    Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+    Err(error) => Err(format!("failed to clear OpenAI API key from keychain: {error}")),
+  }
+}
+
+fn media_key_entry() -> Result<Entry, String> {
+  Entry::new(SERVICE_NAME, MEDIA_KEY_ENTRY)
+    .map_err(|error| format!("failed to initialize keychain entry: {error}"))
+}
+
+pub fn read_media_encryption_key() -> Result<Option<MediaKey>, String> {
+  let entry = media_key_entry()?;
+  match entry.get_password() {
+    Ok(secret) => crypto::decode_key(&secret).map(Some),
+    Err(keyring::Error::NoEntry) => Ok(None),
+    Err(error) => Err(format!("failed to read media encryption key from keychain: {error}")),
+  }
+}
+
+/// Returns the media encryption key, generating and storing one on first use.
+pub fn get_or_create_media_encryption_key() -> Result<MediaKey, String> {
+  if let Some(key) = read_media_encryption_key()? {
+    return Ok(key);
+  }
+
+  let key = crypto::generate_key();
+  media_key_entry()?
+    .set_password(&crypto::encode_key(&key))
+    .map_err(|error| format!("failed to save media encryption key to keychain: {error}"))?;
+  Ok(key)
+}
@@ -0,0 +1,28 @@
+//! Helpers shared by the unit and integration tests.
+
+use std::path::{Path, PathBuf};
+
+/// Scratch directory removed when dropped.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+  pub fn new(name: &str) -> Self {
+    let nanos = std::time::SystemTime::now()
+      .duration_since(std::time::UNIX_EPOCH)
+      .map(|duration| duration.as_nanos())
+      .unwrap_or(0);
+    let path = std::env::temp_dir().join(format!("obsidian-ai-agent-{name}-{}-{nanos}", std::process::id()));
+    std::fs::create_dir_all(&path).expect("create temp dir");
+    Self(path.canonicalize().expect("canonicalize temp dir"))
+  }
+
+  pub fn path(&self) -> &Path {
+    &self.0
+  }
+}
+
+impl Drop for TempDir {
+  fn drop(&mut self) {
+    let _ = std::fs::remove_dir_all(&self.0);
+  }
+}
@@ -1,8 +1,7 @@
 import { useEffect, useMemo, useState } from "react"
-import { useMutation, useQuery, useQueryClient } from "@tanstack/react-query"
-import { open } from "@tauri-apps/plugin-dialog"
+import { useMutation, useQueries, useQuery, useQueryClient } from "@tanstack/react-query"
 import { motion } from "framer-motion"
-import { Bot, BrainCircuit, Database, GitBranch, Sparkles, Telescope, Waves } from "lucide-react"
+import { Bot, BrainCircuit, ClipboardPaste, Database, GitBranch, Sparkles, Telescope, Waves } from "lucide-react"
 import { toast } from "sonner"
 
 import { Badge } from "@/components/ui/badge"
@@ -11,19 +10,44 @@ import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card"
 import { Separator } from "@/components/ui/separator"
 import { Tabs, TabsContent, TabsList, TabsTrigger } from "@/components/ui/tabs"
 import { IngestionQueue, type QueueItem } from "@/features/ingestion/ingestion-queue"
+import { QuarantinePanel } from "@/features/ingestion/quarantine-panel"
 import { InsightGrid } from "@/features/jobs/insight-grid"
 import { NotePreview } from "@/features/notes/note-preview"
+import { PromptRunList } from "@/features/notes/prompt-run-list"
+import { ModelCostPanel } from "@/features/settings/model-cost-panel"
+import { PromptTemplatePanel } from "@/features/settings/prompt-template-panel"
 import { SettingsPanel } from "@/features/settings/settings-panel"
 import {
+  approveQuarantineItem,
   clearGeminiApiKey,
+  clearOpenAiApiKey,
+  deleteModelPrice,
+  deletePromptTemplate,
+  discardQuarantineItem,
   enqueueIngestion,
+  getAssetThumbnail,
   getGeminiApiKeyStatus,
+  hasOpenAiApiKey,
   getSettings,
+  getBudgetStatus,
+  getTokenUsage,
+  listGeminiModels,
   listJobs,
+  listModelPrices,
+  listPromptRuns,
+  listPromptTemplates,
+  listQuarantine,
+  pickCaptureFiles,
+  pickSourceRoot,
   previewNote,
   publishNote,
+  regenerateNote,
   saveGeminiApiKey,
+  saveModelPrice,
+  saveOpenAiApiKey,
+  savePromptTemplate,
   saveSettings,
+  validateGeminiApiKey,
 } from "@/lib/tauri-client"
 import type { SettingsPayload } from "@/lib/tauri-contracts"
 import type { InsightCard } from "@/lib/types"
@@ -42,10 +66,30 @@ const defaultSettings: SettingsPayload = {
   obsidian_cli_path: "obsidian",
   gemini_model: "gemini-2.5-flash",
   write_mode: "cli_fallback",
+  media_quota_bytes: 0,
+  media_retention_days: 0,
+  ingest_mode: "copy",
+  encrypt_media: false,
+  image_derivatives_enabled: false,
+  image_max_dimension: 2048,
+  image_jpeg_quality: 82,
+  allowed_source_roots: [],
+  prompt_template_key: "default_extraction",
+  note_language: "pt",
+  llm_provider: "gemini",
+  openai_base_url: "http://localhost:11434/v1",
+  openai_model: "llama3.2",
+  gemini_base_url: "https://generativelanguage.googleapis.com",
+  daily_token_budget: 0,
+  monthly_token_budget: 0,
+  daily_cost_budget_usd: 0,
+  monthly_cost_budget_usd: 0,
+  gemini_requests_per_minute: 10,
 }
 
 const statusToQueue: Record<string, QueueItem["status"]> = {
   queued: "queued",
+  waiting_budget: "waiting_budget",
   processing: "processing",
   completed: "completed",
   failed: "failed",
@@ -62,16 +106,37 @@ export function DashboardShell() {
     refetchInterval: 4000,
   })
 
+  const quarantineQuery = useQuery({
+    queryKey: ["quarantine"],
+    queryFn: () => listQuarantine("pending"),
+    refetchInterval: 4000,
+  })
+
   const settingsQuery = useQuery({
     queryKey: ["settings"],
     queryFn: getSettings,
   })
 
+  const promptTemplatesQuery = useQuery({
+    queryKey: ["prompt-templates"],
+    queryFn: listPromptTemplates,
+  })
+
   const geminiKeyStatusQuery = useQuery({
     queryKey: ["gemini-api-key-status"],
     queryFn: getGeminiApiKeyStatus,
   })
 
+  const geminiModelsQuery = useQuery({
+    queryKey: ["gemini-models"],
+    queryFn: listGeminiModels,
+  })
+
+  const openAiKeyQuery = useQuery({
+    queryKey: ["openai-api-key-status"],
+    queryFn: hasOpenAiApiKey,
+  })
+
   useEffect(() => {
     if (!selectedJobId && jobsQuery.data?.[0]) {
       setSelectedJobId(jobsQuery.data[0].id)
@@ -84,6 +149,29 @@ export function DashboardShell() {
     enabled: Boolean(selectedJobId),
   })
 
+  const promptRunsQuery = useQuery({
+    // Previews may call the model, so refetch whenever a preview lands.
+    queryKey: ["prompt-runs", selectedJobId, previewQuery.dataUpdatedAt],
+    queryFn: () => listPromptRuns(selectedJobId!),
+    enabled: Boolean(selectedJobId),
+  })
+
+  const tokenUsageQuery = useQuery({
+    queryKey: ["token-usage", previewQuery.dataUpdatedAt],
+    queryFn: getTokenUsage,
+  })
+
+  const budgetStatusQuery = useQuery({
+    queryKey: ["budget-status", previewQuery.dataUpdatedAt],
+    queryFn: getBudgetStatus,
+    refetchInterval: 15000,
+  })
+
+  const modelPricesQuery = useQuery({
+    queryKey: ["model-prices"],
+    queryFn: listModelPrices,
+  })
+
   const enqueueMutation = useMutation({
     mutationFn: enqueueIngestion,
     onSuccess: (response) => {
@@ -96,10 +184,24 @@ export function DashboardShell() {
     },
   })
 
+  const resolveQuarantineMutation = useMutation({
+    mutationFn: ({ itemId, approve }: { itemId: number; approve: boolean }) =>
+      approve ? approveQuarantineItem(itemId) : discardQuarantineItem(itemId),
+    onSuccess: (_, { approve }) => {
+      queryClient.invalidateQueries({ queryKey: ["quarantine"] })
+      queryClient.invalidateQueries({ queryKey: ["jobs"] })
+      toast.success(approve ? "Quarantined file added to its job" : "Quarantined file discarded")
+    },
+    onError: (error) => {
+      toast.error(`Failed to resolve quarantined file: ${String(error)}`)
+    },
+  })
+
   const saveSettingsMutation = useMutation({
     mutationFn: saveSettings,
     onSuccess: (payload) => {
       queryClient.setQueryData(["settings"], payload)
+      queryClient.invalidateQueries({ queryKey: ["budget-status"] })
       toast.success("Settings saved")
     },
     onError: (error) => {
@@ -107,17 +209,75 @@ export function DashboardShell() {
     },
   })
 
+  const savePromptTemplateMutation = useMutation({
+    mutationFn: ({ key, content }: { key: string; content: string }) => savePromptTemplate(key, content),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["prompt-templates"] })
+      toast.success("Prompt template saved")
+    },
+    onError: (error) => {
+      toast.error(`Failed to save prompt template: ${String(error)}`)
+    },
+  })
+
+  const deletePromptTemplateMutation = useMutation({
+    mutationFn: deletePromptTemplate,
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["prompt-templates"] })
+      queryClient.invalidateQueries({ queryKey: ["settings"] })
+      toast.success("Prompt template deleted")
+    },
+    onError: (error) => {
+      toast.error(`Failed to delete prompt template: ${String(error)}`)
+    },
+  })
+
+  const saveModelPriceMutation = useMutation({
+    mutationFn: saveModelPrice,
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["model-prices"] })
+      toast.success("Model price saved")
+    },
+    onError: (error) => {
+      toast.error(`Failed to save model price: ${String(error)}`)
+    },
+  })
+
+  const deleteModelPriceMutation = useMutation({
+    mutationFn: deleteModelPrice,
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["model-prices"] })
+      toast.success("Model price removed")
+    },
+    onError: (error) => {
+      toast.error(`Failed to remove model price: ${String(error)}`)
+    },
+  })
+
   const publishMutation = useMutation({
     mutationFn: (jobId: string) => publishNote(jobId),
     onSuccess: (response) => {
       toast.success(`Note published via ${response.method}`)
       queryClient.invalidateQueries({ queryKey: ["jobs"] })
+      queryClient.invalidateQueries({ queryKey: ["prompt-runs"] })
     },
     onError: (error) => {
       toast.error(`Failed to publish note: ${String(error)}`)
     },
   })
 
+  const regenerateMutation = useMutation({
+    mutationFn: (jobId: string) => regenerateNote(jobId),
+    onSuccess: (response, jobId) => {
+      queryClient.setQueryData(["preview-note", jobId], response)
+      queryClient.invalidateQueries({ queryKey: ["prompt-runs", jobId] })
+      toast.success("Note regenerated")
+    },
+    onError: (error) => {
+      toast.error(`Failed to regenerate note: ${String(error)}`)
+    },
+  })
+
   const saveGeminiApiKeyMutation = useMutation({
     mutationFn: (apiKey: string) => saveGeminiApiKey(apiKey),
     onSuccess: () => {
@@ -129,6 +289,22 @@ export function DashboardShell() {
     },
   })
 
+  const validateGeminiApiKeyMutation = useMutation({
+    mutationFn: validateGeminiApiKey,
+    onSuccess: (check) => {
+      queryClient.invalidateQueries({ queryKey: ["gemini-api-key-status"] })
+      if (check.validation.ok) {
+        queryClient.setQueryData(["gemini-models"], check.models)
+        toast.success(`Gemini API key works: ${check.models.length} models available`)
+      } else {
+        toast.error(`Gemini API key check failed: ${check.validation.message ?? "unknown error"}`)
+      }
+    },
+    onError: (error) => {
+      toast.error(`Failed to check Gemini API key: ${String(error)}`)
+    },
+  })
+
   const clearGeminiApiKeyMutation = useMutation({
     mutationFn: clearGeminiApiKey,
     onSuccess: () => {
@@ -140,6 +316,55 @@ export function DashboardShell() {
     },
   })
 
+  const saveOpenAiApiKeyMutation = useMutation({
+    mutationFn: (apiKey: string) => saveOpenAiApiKey(apiKey),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["openai-api-key-status"] })
+      toast.success("OpenAI-compatible API key saved in OS keychain")
+    },
+    onError: (error) => {
+      toast.error(`Failed to save OpenAI-compatible API key: ${String(error)}`)
+    },
+  })
+
+  const clearOpenAiApiKeyMutation = useMutation({
+    mutationFn: clearOpenAiApiKey,
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["openai-api-key-status"] })
+      toast.success("OpenAI-compatible API key cleared from OS keychain")
+    },
+    onError: (error) => {
+      toast.error(`Failed to clear OpenAI-compatible API key: ${String(error)}`)
+    },
+  })
+
+  const thumbnailAssetIds = useMemo(
+    () =>
+      (jobsQuery.data ?? [])
+        .map((job) => job.thumbnail_asset_id)
+        .filter((assetId): assetId is number => assetId !== null),
+    [jobsQuery.data]
+  )
+
+  const thumbnailQueries = useQueries({
+    queries: thumbnailAssetIds.map((assetId) => ({
+      queryKey: ["asset-thumbnail", assetId],
+      queryFn: () => getAssetThumbnail(assetId),
+      staleTime: Infinity,
+    })),
+  })
+
+  const thumbnailUrls = useMemo(() => {
+    const urls = new Map<number, string>()
+    thumbnailAssetIds.forEach((assetId, index) => {
+      const url = thumbnailQueries[index]?.data
+      if (url) {
+        urls.set(assetId, url)
+      }
+    })
+    return urls
+  }, [thumbnailAssetIds, thumbnailQueries])
+
   const queueItems = useMemo<QueueItem[]>(
     () =>
       (jobsQuery.data ?? []).map((job) => ({
@@ -148,8 +373,9 @@ export function DashboardShell() {
         detail: `${job.asset_count} assets`,
         mediaType: "mixed",
         status: statusToQueue[job.status] ?? "queued",
+        thumbnailUrl: job.thumbnail_asset_id !== null ? thumbnailUrls.get(job.thumbnail_asset_id) : null,
       })),
-    [jobsQuery.data]
+    [jobsQuery.data, thumbnailUrls]
   )
 
   const metrics = useMemo(() => {
@@ -211,12 +437,10 @@ export function DashboardShell() {
   )
 
   const onStartCapture = async () => {
-    const selection = await open({
-      multiple: true,
-      filters: [{ name: "Media", extensions: ["mp3", "wav", "m4a", "mp4", "jpg", "jpeg", "png", "heif"] }],
+    const filePaths = await pickCaptureFiles().catch((error: unknown) => {
+      toast.error(error instanceof Error ? error.message : String(error))
+      return []
     })
-
-    const filePaths = Array.isArray(selection) ? selection : selection ? [selection] : []
     if (filePaths.length === 0) {
       return
     }
@@ -226,6 +450,19 @@ export function DashboardShell() {
     })
   }
 
+  const onCaptureClipboard = async () => {
+    const text = await navigator.clipboard.readText().catch(() => "")
+    if (!text.trim()) {
+      toast.error("Clipboard does not contain text")
+      return
+    }
+
+    enqueueMutation.mutate({
+      file_paths: [],
+      text_content: text,
+    })
+  }
+
   const onPublish = () => {
     if (!selectedJobId) {
       return
@@ -269,10 +506,16 @@ export function DashboardShell() {
                     from audio, video, and images
                   </h2>
                 </div>
-                <Button className="gap-2 bg-primary/90 hover:bg-primary" onClick={onStartCapture}>
-                  <Sparkles className="size-4" />
-                  {enqueueMutation.isPending ? "Queuing..." : "Start New Capture"}
-                </Button>
+                <div className="flex gap-2">
+                  <Button variant="outline" className="gap-2" onClick={onCaptureClipboard}>
+                    <ClipboardPaste className="size-4" />
+                    Paste Text
+                  </Button>
+                  <Button className="gap-2 bg-primary/90 hover:bg-primary" onClick={onStartCapture}>
+                    <Sparkles className="size-4" />
+                    {enqueueMutation.isPending ? "Queuing..." : "Start New Capture"}
+                  </Button>
+                </div>
               </div>
               <div className="grid gap-3 md:grid-cols-3">
                 {metrics.map((metric) => (
@@ -300,6 +543,12 @@ export function DashboardShell() {
             </TabsList>
             <TabsContent value="pipeline" className="space-y-3">
               <IngestionQueue items={queueItems} />
+              <QuarantinePanel
+                items={quarantineQuery.data ?? []}
+                isResolving={resolveQuarantineMutation.isPending}
+                onApprove={(itemId) => resolveQuarantineMutation.mutate({ itemId, approve: true })}
+                onDiscard={(itemId) => resolveQuarantineMutation.mutate({ itemId, approve: false })}
+              />
             </TabsContent>
             <TabsContent value="insights" className="space-y-3">
               <InsightGrid items={insightItems} />
@@ -309,9 +558,12 @@ export function DashboardShell() {
                 markdown={previewQuery.data?.markdown ?? "Select a queued/completed job to generate note preview."}
                 canPublish={Boolean(selectedJobId)}
                 isPublishing={publishMutation.isPending}
+                isRegenerating={regenerateMutation.isPending}
                 onRefresh={() => previewQuery.refetch()}
+                onRegenerate={() => selectedJobId && regenerateMutation.mutate(selectedJobId)}
                 onPublish={onPublish}
               />
+              <PromptRunList runs={promptRunsQuery.data ?? []} />
             </TabsContent>
           </Tabs>
 
@@ -367,6 +619,35 @@ export function DashboardShell() {
             onSave={(payload) => saveSettingsMutation.mutate(payload)}
             onSaveGeminiKey={(apiKey) => saveGeminiApiKeyMutation.mutate(apiKey)}
             onClearGeminiKey={() => clearGeminiApiKeyMutation.mutate()}
+            geminiKeyValidation={geminiKeyStatusQuery.data?.last_validation ?? null}
+            geminiModels={geminiModelsQuery.data ?? []}
+            isValidatingGeminiKey={validateGeminiApiKeyMutation.isPending}
+            onValidateGeminiKey={() => validateGeminiApiKeyMutation.mutate()}
+            openAiApiKeyConfigured={openAiKeyQuery.data ?? false}
+            isSavingOpenAiKey={saveOpenAiApiKeyMutation.isPending}
+            isClearingOpenAiKey={clearOpenAiApiKeyMutation.isPending}
+            onSaveOpenAiKey={(apiKey) => saveOpenAiApiKeyMutation.mutate(apiKey)}
+            onClearOpenAiKey={() => clearOpenAiApiKeyMutation.mutate()}
+            onPickSourceRoot={pickSourceRoot}
+            promptTemplateKeys={(promptTemplatesQuery.data ?? []).map((template) => template.key)}
+          />
+
+          <PromptTemplatePanel
+            templates={promptTemplatesQuery.data ?? []}
+            activeKey={(settingsQuery.data ?? defaultSettings).prompt_template_key}
+            isSaving={savePromptTemplateMutation.isPending}
+            isDeleting={deletePromptTemplateMutation.isPending}
+            onSave={(key, content) => savePromptTemplateMutation.mutate({ key, content })}
+            onDelete={(key) => deletePromptTemplateMutation.mutate(key)}
+          />
+
+          <ModelCostPanel
+            usage={tokenUsageQuery.data}
+            budget={budgetStatusQuery.data}
+            prices={modelPricesQuery.data ?? []}
+            isSaving={saveModelPriceMutation.isPending}
+            onSavePrice={(price) => saveModelPriceMutation.mutate(price)}
+            onDeletePrice={(model) => deleteModelPriceMutation.mutate(model)}
           />
 
           <Card className="border-border bg-sidebar/70">
@@ -10,7 +10,8 @@ export interface QueueItem {
   label: string
   mediaType: "audio" | "video" | "image" | "mixed"
   detail: string
-  status: "queued" | "processing" | "completed" | "failed" | "cancelled"
+  status: "queued" | "waiting_budget" | "processing" | "completed" | "failed" | "cancelled"
+  thumbnailUrl?: string | null
 }
 
 const mediaIcon = {
@@ -22,6 +23,7 @@ const mediaIcon = {
 
 const statusTone = {
   queued: "bg-secondary text-secondary-foreground border-border",
+  waiting_budget: "bg-amber-400/10 text-amber-200 border-amber-400/40",
   processing: "bg-primary/15 text-primary border-primary/40",
   completed: "bg-emerald-400/10 text-emerald-200 border-emerald-400/40",
   failed: "bg-rose-500/10 text-rose-200 border-rose-500/40",
@@ -51,9 +53,17 @@ export function IngestionQueue({ items }: { items: QueueItem[] }) {
               className="flex items-center justify-between border border-border/80 bg-background/70 px-3 py-2"
             >
               <div className="flex items-center gap-3">
-                <div className="border border-primary/40 bg-primary/15 p-2">
-                  <Icon className="size-4 text-primary" />
-                </div>
+                {item.thumbnailUrl ? (
+                  <img
+                    src={item.thumbnailUrl}
+                    alt=""
+                    className="size-9 border border-primary/40 object-cover"
+                  />
+                ) : (
+                  <div className="border border-primary/40 bg-primary/15 p-2">
+                    <Icon className="size-4 text-primary" />
+                  </div>
+                )}
                 <div>
                   <p className="text-sm font-medium">{item.label}</p>
                   <p className="text-xs text-muted-foreground">{item.detail}</p>
@@ -68,7 +78,7 @@ export function IngestionQueue({ items }: { items: QueueItem[] }) {
                 ) : item.status === "completed" ? (
                   <CheckCircle2 className="size-3" />
                 ) : null}
-                {item.status}
+                {item.status === "waiting_budget" ? "waiting for budget" : item.status}
               </Badge>
             </motion.div>
           )
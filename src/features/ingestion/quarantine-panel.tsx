@@ -0,0 +1,70 @@
+import { ShieldAlert, ShieldCheck, Trash2 } from "lucide-react"
+
+import { Badge } from "@/components/ui/badge"
+import { Button } from "@/components/ui/button"
+import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card"
+import type { QuarantineItem } from "@/lib/tauri-contracts"
+
+interface QuarantinePanelProps {
+  items: QuarantineItem[]
+  isResolving: boolean
+  onApprove: (itemId: number) => void
+  onDiscard: (itemId: number) => void
+}
+
+export function QuarantinePanel({ items, isResolving, onApprove, onDiscard }: QuarantinePanelProps) {
+  if (items.length === 0) {
+    return null
+  }
+
+  return (
+    <Card className="border-amber-400/30 bg-card/70">
+      <CardHeader className="border-b border-border pb-4">
+        <CardTitle className="flex items-center justify-between text-sm tracking-[0.14em] uppercase">
+          <span className="flex items-center gap-2">
+            <ShieldAlert className="size-4 text-amber-300" />
+            Quarantine
+          </span>
+          <Badge variant="outline" className="border-amber-400/40 bg-amber-400/10 text-amber-200">
+            {items.length} pending
+          </Badge>
+        </CardTitle>
+      </CardHeader>
+      <CardContent className="space-y-3 pt-4">
+        {items.map((item) => (
+          <div
+            key={item.id}
+            className="flex items-center justify-between gap-3 border border-border/80 bg-background/70 px-3 py-2"
+          >
+            <div className="min-w-0">
+              <p className="truncate text-sm font-medium">{item.original_path}</p>
+              <p className="text-xs text-muted-foreground">{item.reason}</p>
+            </div>
+            <div className="flex shrink-0 gap-2">
+              <Button
+                size="sm"
+                variant="outline"
+                className="gap-2"
+                disabled={isResolving}
+                onClick={() => onApprove(item.id)}
+              >
+                <ShieldCheck className="size-4" />
+                Approve
+              </Button>
+              <Button
+                size="sm"
+                variant="outline"
+                className="gap-2"
+                disabled={isResolving}
+                onClick={() => onDiscard(item.id)}
+              >
+                <Trash2 className="size-4" />
+                Discard
+              </Button>
+            </div>
+          </div>
+        ))}
+      </CardContent>
+    </Card>
+  )
+}
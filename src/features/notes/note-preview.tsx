@@ -1,4 +1,4 @@
-import { Check, FilePenLine } from "lucide-react"
+import { Check, FilePenLine, Sparkles } from "lucide-react"
 
 import { Button } from "@/components/ui/button"
 import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card"
@@ -9,11 +9,21 @@ interface NotePreviewProps {
   markdown: string
   canPublish: boolean
   isPublishing: boolean
+  isRegenerating: boolean
   onRefresh: () => void
+  onRegenerate: () => void
   onPublish: () => void
 }
 
-export function NotePreview({ markdown, canPublish, isPublishing, onRefresh, onPublish }: NotePreviewProps) {
+export function NotePreview({
+  markdown,
+  canPublish,
+  isPublishing,
+  isRegenerating,
+  onRefresh,
+  onRegenerate,
+  onPublish,
+}: NotePreviewProps) {
   return (
     <Card className="border-primary/30 bg-card/80">
       <CardHeader className="border-b border-border pb-4">
@@ -24,6 +34,16 @@ export function NotePreview({ markdown, canPublish, isPublishing, onRefresh, onP
               <FilePenLine className="size-4" />
               Refresh
             </Button>
+            <Button
+              size="sm"
+              variant="outline"
+              className="gap-2"
+              onClick={onRegenerate}
+              disabled={!canPublish || isRegenerating}
+            >
+              <Sparkles className="size-4" />
+              {isRegenerating ? "Regenerating..." : "Regenerate"}
+            </Button>
             <Button
               size="sm"
               className="gap-2 bg-primary/90 hover:bg-primary"
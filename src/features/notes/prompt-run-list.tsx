@@ -0,0 +1,60 @@
+import { Activity } from "lucide-react"
+
+import { Badge } from "@/components/ui/badge"
+import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card"
+import type { PromptRun } from "@/lib/tauri-contracts"
+import { formatUsd } from "@/lib/utils"
+
+interface PromptRunListProps {
+  runs: PromptRun[]
+}
+
+export function PromptRunList({ runs }: PromptRunListProps) {
+  if (runs.length === 0) {
+    return null
+  }
+
+  const totalCost = runs.reduce((sum, run) => sum + (run.cost_usd ?? 0), 0)
+
+  return (
+    <Card className="border-border bg-card/70">
+      <CardHeader className="border-b border-border pb-4">
+        <CardTitle className="flex items-center justify-between text-sm tracking-[0.14em] uppercase">
+          <span className="flex items-center gap-2">
+            <Activity className="size-4" />
+            Model Calls
+          </span>
+          <Badge variant="outline" className="border-primary/40 bg-primary/10 text-primary">
+            {runs.length} runs · {formatUsd(totalCost)}
+          </Badge>
+        </CardTitle>
+      </CardHeader>
+      <CardContent className="space-y-3 pt-4">
+        {runs.map((run) => (
+          <details key={run.id} className="border border-border/80 bg-background/70 px-3 py-2">
+            <summary className="flex cursor-pointer items-center justify-between gap-3 text-xs">
+              <span className="font-mono">
+                {new Date(run.created_at).toLocaleString()} · {run.model} · {run.prompt_template_key}
+              </span>
+              <span className={run.error ? "text-destructive" : "text-muted-foreground"}>
+                {run.error ? "failed" : `${run.total_tokens ?? "?"} tokens`}
+                {run.cached_tokens ? ` (${run.cached_tokens} cached)` : ""}
+                {run.cost_usd !== null ? ` · ${formatUsd(run.cost_usd)}` : ""}
+                {run.latency_ms !== null ? ` · ${run.latency_ms} ms` : ""}
+              </span>
+            </summary>
+            {run.error ? <p className="mt-2 text-xs text-destructive">{run.error}</p> : null}
+            <pre className="mt-2 max-h-48 overflow-auto whitespace-pre-wrap font-mono text-[11px] text-muted-foreground">
+              {run.prompt_body}
+            </pre>
+            {run.response_body ? (
+              <pre className="mt-2 max-h-48 overflow-auto whitespace-pre-wrap font-mono text-[11px]">
+                {run.response_body}
+              </pre>
+            ) : null}
+          </details>
+        ))}
+      </CardContent>
+    </Card>
+  )
+}
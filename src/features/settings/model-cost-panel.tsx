@@ -0,0 +1,156 @@
+import { useState } from "react"
+import { CircleDollarSign, Save, Trash2 } from "lucide-react"
+
+import { Badge } from "@/components/ui/badge"
+import { Button } from "@/components/ui/button"
+import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card"
+import { Input } from "@/components/ui/input"
+import { Label } from "@/components/ui/label"
+import type { BudgetStatus, ModelPrice, TokenUsageReport } from "@/lib/tauri-contracts"
+import { formatUsd } from "@/lib/utils"
+
+interface ModelCostPanelProps {
+  usage: TokenUsageReport | undefined
+  budget: BudgetStatus | undefined
+  prices: ModelPrice[]
+  isSaving: boolean
+  onSavePrice: (price: ModelPrice) => void
+  onDeletePrice: (model: string) => void
+}
+
+const emptyPrice: ModelPrice = {
+  model: "",
+  input_usd_per_mtok: 0,
+  output_usd_per_mtok: 0,
+  cached_input_usd_per_mtok: 0,
+  updated_at: 0,
+}
+
+export function ModelCostPanel({ usage, budget, prices, isSaving, onSavePrice, onDeletePrice }: ModelCostPanelProps) {
+  const [draft, setDraft] = useState<ModelPrice>(emptyPrice)
+
+  const rateInput = (field: keyof Omit<ModelPrice, "model" | "updated_at">, label: string) => (
+    <Input
+      aria-label={label}
+      type="number"
+      min={0}
+      step="0.001"
+      value={draft[field]}
+      onChange={(event) =>
+        setDraft((previous) => ({ ...previous, [field]: Math.max(0, Number(event.target.value) || 0) }))
+      }
+      className="font-mono text-xs"
+    />
+  )
+
+  return (
+    <Card className="border-border bg-card/70">
+      <CardHeader className="border-b border-border pb-4">
+        <CardTitle className="flex items-center justify-between text-sm tracking-[0.12em] uppercase">
+          <span className="flex items-center gap-2">
+            <CircleDollarSign className="size-4" />
+            Model Spend
+          </span>
+          <Badge variant="outline" className="border-primary/40 bg-primary/10 text-primary">
+            {formatUsd(usage?.totals.cost_usd ?? 0)}
+          </Badge>
+        </CardTitle>
+      </CardHeader>
+      <CardContent className="space-y-4 pt-4">
+        {budget ? (
+          <div className="space-y-1 font-mono text-xs">
+            <p className="flex justify-between gap-2">
+              <span>Today</span>
+              <span className="text-muted-foreground">
+                {budget.today.total_tokens} tokens · {formatUsd(budget.today.cost_usd)}
+              </span>
+            </p>
+            {budget.exceeded ? (
+              <p className="border border-amber-400/40 bg-amber-400/10 px-2 py-1 text-amber-200">
+                Model calls paused: {budget.exceeded}
+              </p>
+            ) : null}
+          </div>
+        ) : null}
+        <div className="space-y-1">
+          <Label className="text-xs uppercase tracking-[0.12em] text-muted-foreground">By Month</Label>
+          {(usage?.per_month ?? []).length === 0 ? (
+            <p className="text-xs text-muted-foreground">No model calls recorded yet.</p>
+          ) : (
+            <ul className="space-y-1 font-mono text-xs">
+              {usage?.per_month.slice(0, 6).map((month) => (
+                <li key={month.month} className="flex justify-between gap-2">
+                  <span>{month.month}</span>
+                  <span className="text-muted-foreground">
+                    {month.totals.calls} calls · {month.totals.total_tokens} tokens ·{" "}
+                    {formatUsd(month.totals.cost_usd)}
+                  </span>
+                </li>
+              ))}
+            </ul>
+          )}
+        </div>
+        {(usage?.per_job ?? []).length > 0 ? (
+          <div className="space-y-1">
+            <Label className="text-xs uppercase tracking-[0.12em] text-muted-foreground">Costliest Captures</Label>
+            <ul className="space-y-1 font-mono text-xs">
+              {usage?.per_job.slice(0, 5).map((job) => (
+                <li key={job.job_id} className="flex justify-between gap-2">
+                  <span className="truncate">{job.title}</span>
+                  <span className="text-muted-foreground">{formatUsd(job.totals.cost_usd)}</span>
+                </li>
+              ))}
+            </ul>
+          </div>
+        ) : null}
+        <div className="space-y-2 border border-border/80 bg-background/60 px-3 py-2">
+          <Label className="text-xs uppercase tracking-[0.12em] text-muted-foreground">
+            Prices (USD per 1M tokens)
+          </Label>
+          <ul className="space-y-1">
+            {prices.map((price) => (
+              <li key={price.model} className="flex items-center justify-between gap-2 font-mono text-xs">
+                <button type="button" className="truncate text-left" onClick={() => setDraft(price)}>
+                  {price.model}
+                </button>
+                <span className="flex items-center gap-1 text-muted-foreground">
+                  {price.input_usd_per_mtok} / {price.output_usd_per_mtok} / {price.cached_input_usd_per_mtok}
+                  <Button
+                    variant="ghost"
+                    size="icon-xs"
+                    aria-label={`Remove price for ${price.model}`}
+                    onClick={() => onDeletePrice(price.model)}
+                  >
+                    <Trash2 className="size-3.5" />
+                  </Button>
+                </span>
+              </li>
+            ))}
+          </ul>
+          <Input
+            aria-label="Priced model"
+            value={draft.model}
+            onChange={(event) => setDraft((previous) => ({ ...previous, model: event.target.value }))}
+            placeholder="gemini/gemini-2.5-flash"
+            className="font-mono text-xs"
+          />
+          <div className="grid grid-cols-3 gap-2">
+            {rateInput("input_usd_per_mtok", "Input price")}
+            {rateInput("output_usd_per_mtok", "Output price")}
+            {rateInput("cached_input_usd_per_mtok", "Cached input price")}
+          </div>
+          <p className="text-[11px] text-muted-foreground">Input / output / cached input. Applies to new calls.</p>
+          <Button
+            variant="outline"
+            className="w-full gap-2"
+            onClick={() => onSavePrice({ ...draft, model: draft.model.trim() })}
+            disabled={isSaving || draft.model.trim().length === 0}
+          >
+            <Save className="size-4" />
+            {isSaving ? "Saving..." : "Save Price"}
+          </Button>
+        </div>
+      </CardContent>
+    </Card>
+  )
+}
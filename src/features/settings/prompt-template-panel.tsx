@@ -0,0 +1,147 @@
+import { useEffect, useState } from "react"
+import { FilePlus2, Save, ScrollText, Trash2 } from "lucide-react"
+
+import { Badge } from "@/components/ui/badge"
+import { Button } from "@/components/ui/button"
+import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card"
+import { Input } from "@/components/ui/input"
+import { Label } from "@/components/ui/label"
+import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from "@/components/ui/select"
+import { Textarea } from "@/components/ui/textarea"
+import type { PromptTemplate } from "@/lib/tauri-contracts"
+
+const DEFAULT_TEMPLATE_KEY = "default_extraction"
+
+const promptVariables = [
+  "title",
+  "file_list",
+  "media_metadata",
+  "language",
+  "vault_context",
+  "text_captures",
+  "transcripts",
+]
+
+interface PromptTemplatePanelProps {
+  templates: PromptTemplate[]
+  activeKey: string
+  isSaving: boolean
+  isDeleting: boolean
+  onSave: (key: string, content: string) => void
+  onDelete: (key: string) => void
+}
+
+export function PromptTemplatePanel({
+  templates,
+  activeKey,
+  isSaving,
+  isDeleting,
+  onSave,
+  onDelete,
+}: PromptTemplatePanelProps) {
+  const [selectedKey, setSelectedKey] = useState(activeKey)
+  const [keyDraft, setKeyDraft] = useState("")
+  const [contentDraft, setContentDraft] = useState("")
+
+  useEffect(() => {
+    const selected = templates.find((template) => template.key === selectedKey)
+    if (selected) {
+      setKeyDraft(selected.key)
+      setContentDraft(selected.content)
+    }
+  }, [templates, selectedKey])
+
+  const onNew = () => {
+    setSelectedKey("")
+    setKeyDraft("")
+    setContentDraft("")
+  }
+
+  return (
+    <Card className="border-border bg-card/70">
+      <CardHeader className="border-b border-border pb-4">
+        <CardTitle className="flex items-center justify-between text-sm tracking-[0.12em] uppercase">
+          <span className="flex items-center gap-2">
+            <ScrollText className="size-4" />
+            Prompt Templates
+          </span>
+          <Badge variant="outline" className="border-primary/40 bg-primary/10 text-primary">
+            {templates.length} saved
+          </Badge>
+        </CardTitle>
+      </CardHeader>
+      <CardContent className="space-y-4 pt-4">
+        <div className="flex gap-2">
+          <Select value={selectedKey} onValueChange={setSelectedKey}>
+            <SelectTrigger className="flex-1 font-mono text-xs">
+              <SelectValue placeholder="New template" />
+            </SelectTrigger>
+            <SelectContent>
+              {templates.map((template) => (
+                <SelectItem key={template.key} value={template.key}>
+                  {template.key}
+                  {template.key === activeKey ? " (active)" : ""}
+                </SelectItem>
+              ))}
+            </SelectContent>
+          </Select>
+          <Button variant="outline" size="icon" aria-label="New template" onClick={onNew}>
+            <FilePlus2 className="size-4" />
+          </Button>
+        </div>
+        <div className="space-y-2">
+          <Label htmlFor="template-key" className="text-xs uppercase tracking-[0.12em] text-muted-foreground">
+            Key
+          </Label>
+          <Input
+            id="template-key"
+            value={keyDraft}
+            onChange={(event) => setKeyDraft(event.target.value)}
+            placeholder="meeting_notes"
+            className="font-mono text-xs"
+          />
+        </div>
+        <div className="space-y-2">
+          <Label htmlFor="template-content" className="text-xs uppercase tracking-[0.12em] text-muted-foreground">
+            Template
+          </Label>
+          <Textarea
+            id="template-content"
+            value={contentDraft}
+            onChange={(event) => setContentDraft(event.target.value)}
+            rows={8}
+            className="font-mono text-xs"
+          />
+          <p className="text-xs text-muted-foreground">
+            Variables: {promptVariables.map((name) => `{{${name}}}`).join(", ")}
+          </p>
+        </div>
+        <div className="flex gap-2">
+          <Button
+            className="flex-1 gap-2"
+            onClick={() => {
+              setSelectedKey(keyDraft.trim())
+              onSave(keyDraft.trim(), contentDraft)
+            }}
+            disabled={isSaving || keyDraft.trim().length === 0 || contentDraft.trim().length === 0}
+          >
+            <Save className="size-4" />
+            {isSaving ? "Saving..." : "Save Template"}
+          </Button>
+          <Button
+            variant="outline"
+            className="gap-2"
+            onClick={() => {
+              onDelete(selectedKey)
+              setSelectedKey(DEFAULT_TEMPLATE_KEY)
+            }}
+            disabled={isDeleting || !selectedKey || selectedKey === DEFAULT_TEMPLATE_KEY}
+          >
+            <Trash2 className="size-4" />
+            Delete
+          </Button>
+        </div>
+      </CardContent>
+    </Card>
+  )
+}
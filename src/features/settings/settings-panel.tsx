@@ -1,5 +1,5 @@
 import { useEffect, useState } from "react"
-import { KeyRound, Link2, Save, Sparkles } from "lucide-react"
+import { FolderPlus, KeyRound, Link2, Save, Sparkles, X } from "lucide-react"
 
 import { Badge } from "@/components/ui/badge"
 import { Button } from "@/components/ui/button"
@@ -7,7 +7,8 @@ import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card"
 import { Input } from "@/components/ui/input"
 import { Label } from "@/components/ui/label"
 import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from "@/components/ui/select"
-import type { SettingsPayload } from "@/lib/tauri-contracts"
+import { Switch } from "@/components/ui/switch"
+import type { GeminiModelInfo, KeyValidation, SettingsPayload } from "@/lib/tauri-contracts"
 
 interface SettingsPanelProps {
   settings: SettingsPayload
@@ -19,6 +20,17 @@ interface SettingsPanelProps {
   onSave: (payload: SettingsPayload) => void
   onSaveGeminiKey: (apiKey: string) => void
   onClearGeminiKey: () => void
+  geminiKeyValidation: KeyValidation | null
+  geminiModels: GeminiModelInfo[]
+  isValidatingGeminiKey: boolean
+  onValidateGeminiKey: () => void
+  openAiApiKeyConfigured: boolean
+  isSavingOpenAiKey: boolean
+  isClearingOpenAiKey: boolean
+  onSaveOpenAiKey: (apiKey: string) => void
+  onClearOpenAiKey: () => void
+  onPickSourceRoot: () => Promise<string | null>
+  promptTemplateKeys: string[]
 }
 
 const geminiSourceLabel: Record<SettingsPanelProps["geminiApiKeySource"], string> = {
@@ -27,6 +39,9 @@ const geminiSourceLabel: Record<SettingsPanelProps["geminiApiKeySource"], string
   missing: "No Gemini API key configured",
 }
 
+const formatTokenLimit = (limit: number | null) =>
+  limit === null ? "?" : limit >= 1_000_000 ? `${limit / 1_000_000}M` : `${Math.round(limit / 1000)}k`
+
 export function SettingsPanel({
   settings,
   isSaving,
@@ -37,9 +52,21 @@ export function SettingsPanel({
   onSave,
   onSaveGeminiKey,
   onClearGeminiKey,
+  geminiKeyValidation,
+  geminiModels,
+  isValidatingGeminiKey,
+  onValidateGeminiKey,
+  openAiApiKeyConfigured,
+  isSavingOpenAiKey,
+  isClearingOpenAiKey,
+  onSaveOpenAiKey,
+  onClearOpenAiKey,
+  onPickSourceRoot,
+  promptTemplateKeys,
 }: SettingsPanelProps) {
   const [draft, setDraft] = useState<SettingsPayload>(settings)
   const [geminiApiKeyDraft, setGeminiApiKeyDraft] = useState("")
+  const [openAiApiKeyDraft, setOpenAiApiKeyDraft] = useState("")
 
   useEffect(() => {
     setDraft(settings)
@@ -51,6 +78,20 @@ export function SettingsPanel({
     }
   }, [geminiApiKeyConfigured, geminiApiKeySource])
 
+  useEffect(() => {
+    if (openAiApiKeyConfigured) {
+      setOpenAiApiKeyDraft("")
+    }
+  }, [openAiApiKeyConfigured])
+
+  // The backend allowlists the picked folder itself; the draft only mirrors it so a later save keeps it.
+  const onAddSourceRoot = async () => {
+    const root = await onPickSourceRoot().catch(() => null)
+    if (root && !draft.allowed_source_roots.includes(root)) {
+      setDraft((previous) => ({ ...previous, allowed_source_roots: [...previous.allowed_source_roots, root] }))
+    }
+  }
+
   return (
     <Card className="border-border bg-card/70">
       <CardHeader className="border-b border-border pb-4">
@@ -91,21 +132,248 @@ export function SettingsPanel({
             </Button>
           </div>
         </div>
+        <div className="space-y-2">
+          <Label htmlFor="llm-provider" className="text-xs uppercase tracking-[0.12em] text-muted-foreground">
+            Model Provider
+          </Label>
+          <Select
+            value={draft.llm_provider}
+            onValueChange={(value: SettingsPayload["llm_provider"]) =>
+              setDraft((previous) => ({ ...previous, llm_provider: value }))
+            }
+          >
+            <SelectTrigger id="llm-provider" className="font-mono text-xs">
+              <SelectValue />
+            </SelectTrigger>
+            <SelectContent>
+              <SelectItem value="gemini">gemini</SelectItem>
+              <SelectItem value="openai_compatible">openai_compatible</SelectItem>
+            </SelectContent>
+          </Select>
+        </div>
+        {draft.llm_provider === "openai_compatible" ? (
+          <div className="space-y-2 border border-border/80 bg-background/60 px-3 py-2">
+            <Label htmlFor="openai-base-url" className="text-xs uppercase tracking-[0.12em] text-muted-foreground">
+              OpenAI-Compatible Server
+            </Label>
+            <Input
+              id="openai-base-url"
+              value={draft.openai_base_url}
+              onChange={(event) => setDraft((previous) => ({ ...previous, openai_base_url: event.target.value }))}
+              placeholder="http://localhost:11434/v1"
+              className="font-mono text-xs"
+            />
+            <Input
+              aria-label="OpenAI-compatible model"
+              value={draft.openai_model}
+              onChange={(event) => setDraft((previous) => ({ ...previous, openai_model: event.target.value }))}
+              placeholder="llama3.2"
+              className="font-mono text-xs"
+            />
+            <Input
+              aria-label="OpenAI-compatible API key"
+              type="password"
+              value={openAiApiKeyDraft}
+              onChange={(event) => setOpenAiApiKeyDraft(event.target.value)}
+              placeholder="API key (optional for local servers)"
+              className="font-mono text-xs"
+            />
+            <div className="flex gap-2">
+              <Button
+                variant="outline"
+                className="flex-1 gap-2"
+                onClick={() => onSaveOpenAiKey(openAiApiKeyDraft.trim())}
+                disabled={isSavingOpenAiKey || openAiApiKeyDraft.trim().length === 0}
+              >
+                <KeyRound className="size-4" />
+                {isSavingOpenAiKey ? "Saving Key..." : "Save Key"}
+              </Button>
+              <Button
+                variant="outline"
+                className="flex-1"
+                onClick={onClearOpenAiKey}
+                disabled={isClearingOpenAiKey || !openAiApiKeyConfigured}
+              >
+                {isClearingOpenAiKey ? "Clearing..." : "Clear Key"}
+              </Button>
+            </div>
+            <p className="text-[11px] text-muted-foreground">
+              {openAiApiKeyConfigured ? "API key configured" : "No API key; requests are sent unauthenticated"}
+            </p>
+          </div>
+        ) : null}
         <div className="space-y-2">
           <Label htmlFor="gemini-model" className="text-xs uppercase tracking-[0.12em] text-muted-foreground">
             Gemini Model
           </Label>
           <div className="flex gap-2">
+            {geminiModels.length > 0 ? (
+              <Select
+                value={draft.gemini_model}
+                onValueChange={(value) => setDraft((previous) => ({ ...previous, gemini_model: value }))}
+              >
+                <SelectTrigger id="gemini-model" className="flex-1 font-mono text-xs">
+                  <SelectValue />
+                </SelectTrigger>
+                <SelectContent>
+                  {geminiModels.some((model) => model.id === draft.gemini_model) ? null : (
+                    <SelectItem value={draft.gemini_model}>{draft.gemini_model} (not listed for this key)</SelectItem>
+                  )}
+                  {geminiModels.map((model) => (
+                    <SelectItem key={model.id} value={model.id}>
+                      {model.id} · {formatTokenLimit(model.input_token_limit)} in /{" "}
+                      {formatTokenLimit(model.output_token_limit)} out · likely{" "}
+                      {model.assumed_input_modalities.join(", ")}
+                    </SelectItem>
+                  ))}
+                </SelectContent>
+              </Select>
+            ) : (
+              <Input
+                id="gemini-model"
+                value={draft.gemini_model}
+                onChange={(event) => setDraft((previous) => ({ ...previous, gemini_model: event.target.value }))}
+                className="font-mono text-xs"
+              />
+            )}
+            <Button
+              variant="outline"
+              size="icon"
+              aria-label="Check Gemini API key and load models"
+              onClick={onValidateGeminiKey}
+              disabled={isValidatingGeminiKey || !geminiApiKeyConfigured}
+            >
+              <Sparkles className="size-4" />
+            </Button>
+          </div>
+          <Input
+            aria-label="Gemini endpoint"
+            value={draft.gemini_base_url}
+            onChange={(event) => setDraft((previous) => ({ ...previous, gemini_base_url: event.target.value }))}
+            placeholder="https://generativelanguage.googleapis.com"
+            className="font-mono text-xs"
+          />
+          <Input
+            aria-label="Gemini requests per minute"
+            type="number"
+            min={0}
+            max={10000}
+            value={draft.gemini_requests_per_minute}
+            onChange={(event) =>
+              setDraft((previous) => ({
+                ...previous,
+                gemini_requests_per_minute: Math.max(0, Math.round(Number(event.target.value) || 0)),
+              }))
+            }
+            className="font-mono text-xs"
+          />
+          <p className="text-[11px] text-muted-foreground">Requests per minute per model; 0 turns the limit off.</p>
+        </div>
+        <div className="space-y-2">
+          <Label className="text-xs uppercase tracking-[0.12em] text-muted-foreground">Usage Budgets</Label>
+          <div className="grid grid-cols-2 gap-2">
             <Input
-              id="gemini-model"
-              value={draft.gemini_model}
-              onChange={(event) => setDraft((previous) => ({ ...previous, gemini_model: event.target.value }))}
+              aria-label="Daily token budget"
+              type="number"
+              min={0}
+              value={draft.daily_token_budget}
+              onChange={(event) =>
+                setDraft((previous) => ({
+                  ...previous,
+                  daily_token_budget: Math.max(0, Math.round(Number(event.target.value) || 0)),
+                }))
+              }
+              className="font-mono text-xs"
+            />
+            <Input
+              aria-label="Monthly token budget"
+              type="number"
+              min={0}
+              value={draft.monthly_token_budget}
+              onChange={(event) =>
+                setDraft((previous) => ({
+                  ...previous,
+                  monthly_token_budget: Math.max(0, Math.round(Number(event.target.value) || 0)),
+                }))
+              }
+              className="font-mono text-xs"
+            />
+            <Input
+              aria-label="Daily cost budget (USD)"
+              type="number"
+              min={0}
+              step="0.01"
+              value={draft.daily_cost_budget_usd}
+              onChange={(event) =>
+                setDraft((previous) => ({
+                  ...previous,
+                  daily_cost_budget_usd: Math.max(0, Number(event.target.value) || 0),
+                }))
+              }
+              className="font-mono text-xs"
+            />
+            <Input
+              aria-label="Monthly cost budget (USD)"
+              type="number"
+              min={0}
+              step="0.01"
+              value={draft.monthly_cost_budget_usd}
+              onChange={(event) =>
+                setDraft((previous) => ({
+                  ...previous,
+                  monthly_cost_budget_usd: Math.max(0, Number(event.target.value) || 0),
+                }))
+              }
               className="font-mono text-xs"
             />
-            <Button variant="outline" size="icon" disabled>
-              <Sparkles className="size-4" />
-            </Button>
           </div>
+          <p className="text-[11px] text-muted-foreground">
+            Tokens and USD per day / month (UTC). 0 means unlimited; jobs wait when a cap is reached.
+          </p>
+        </div>
+        <div className="space-y-2">
+          <Label htmlFor="note-language" className="text-xs uppercase tracking-[0.12em] text-muted-foreground">
+            Note Language
+          </Label>
+          <Select
+            value={draft.note_language}
+            onValueChange={(value: SettingsPayload["note_language"]) =>
+              setDraft((previous) => ({ ...previous, note_language: value }))
+            }
+          >
+            <SelectTrigger id="note-language" className="font-mono text-xs">
+              <SelectValue />
+            </SelectTrigger>
+            <SelectContent>
+              <SelectItem value="source">same as source</SelectItem>
+              <SelectItem value="en">English</SelectItem>
+              <SelectItem value="pt">Portuguese</SelectItem>
+              <SelectItem value="es">Spanish</SelectItem>
+              <SelectItem value="fr">French</SelectItem>
+              <SelectItem value="de">German</SelectItem>
+              <SelectItem value="it">Italian</SelectItem>
+            </SelectContent>
+          </Select>
+        </div>
+        <div className="space-y-2">
+          <Label htmlFor="prompt-template" className="text-xs uppercase tracking-[0.12em] text-muted-foreground">
+            Prompt Template
+          </Label>
+          <Select
+            value={draft.prompt_template_key}
+            onValueChange={(value) => setDraft((previous) => ({ ...previous, prompt_template_key: value }))}
+          >
+            <SelectTrigger id="prompt-template" className="font-mono text-xs">
+              <SelectValue />
+            </SelectTrigger>
+            <SelectContent>
+              {promptTemplateKeys.map((key) => (
+                <SelectItem key={key} value={key}>
+                  {key}
+                </SelectItem>
+              ))}
+            </SelectContent>
+          </Select>
         </div>
         <div className="space-y-2">
           <Label htmlFor="write-mode" className="text-xs uppercase tracking-[0.12em] text-muted-foreground">
@@ -127,6 +395,152 @@ export function SettingsPanel({
             </SelectContent>
           </Select>
         </div>
+        <div className="space-y-2">
+          <Label htmlFor="ingest-mode" className="text-xs uppercase tracking-[0.12em] text-muted-foreground">
+            Ingest Mode
+          </Label>
+          <Select
+            value={draft.ingest_mode}
+            onValueChange={(value: SettingsPayload["ingest_mode"]) =>
+              setDraft((previous) => ({ ...previous, ingest_mode: value }))
+            }
+          >
+            <SelectTrigger id="ingest-mode" className="font-mono text-xs">
+              <SelectValue />
+            </SelectTrigger>
+            <SelectContent>
+              <SelectItem value="copy">copy</SelectItem>
+              <SelectItem value="move">move</SelectItem>
+              <SelectItem value="reference">reference</SelectItem>
+            </SelectContent>
+          </Select>
+        </div>
+        <div className="flex items-center justify-between border border-border/80 bg-background/60 px-3 py-2">
+          <Label htmlFor="encrypt-media" className="text-xs uppercase tracking-[0.12em] text-muted-foreground">
+            Encrypt Media At Rest
+          </Label>
+          <Switch
+            id="encrypt-media"
+            checked={draft.encrypt_media}
+            onCheckedChange={(checked) => setDraft((previous) => ({ ...previous, encrypt_media: checked }))}
+          />
+        </div>
+        <div className="space-y-2 border border-border/80 bg-background/60 px-3 py-2">
+          <div className="flex items-center justify-between">
+            <Label htmlFor="image-derivatives" className="text-xs uppercase tracking-[0.12em] text-muted-foreground">
+              Downscale Images
+            </Label>
+            <Switch
+              id="image-derivatives"
+              checked={draft.image_derivatives_enabled}
+              onCheckedChange={(checked) =>
+                setDraft((previous) => ({ ...previous, image_derivatives_enabled: checked }))
+              }
+            />
+          </div>
+          <div className="grid grid-cols-2 gap-2">
+            <Input
+              aria-label="Max image dimension"
+              type="number"
+              min={256}
+              max={8192}
+              value={draft.image_max_dimension}
+              disabled={!draft.image_derivatives_enabled}
+              onChange={(event) =>
+                setDraft((previous) => ({ ...previous, image_max_dimension: Number(event.target.value) || 2048 }))
+              }
+              className="font-mono text-xs"
+            />
+            <Input
+              aria-label="JPEG quality"
+              type="number"
+              min={1}
+              max={100}
+              value={draft.image_jpeg_quality}
+              disabled={!draft.image_derivatives_enabled}
+              onChange={(event) =>
+                setDraft((previous) => ({ ...previous, image_jpeg_quality: Number(event.target.value) || 82 }))
+              }
+              className="font-mono text-xs"
+            />
+          </div>
+        </div>
+        <div className="grid grid-cols-2 gap-2">
+          <div className="space-y-2">
+            <Label htmlFor="media-quota" className="text-xs uppercase tracking-[0.12em] text-muted-foreground">
+              Media Quota (MB)
+            </Label>
+            <Input
+              id="media-quota"
+              type="number"
+              min={0}
+              value={Math.round(draft.media_quota_bytes / (1024 * 1024))}
+              onChange={(event) =>
+                setDraft((previous) => ({
+                  ...previous,
+                  media_quota_bytes: Math.max(0, Number(event.target.value) || 0) * 1024 * 1024,
+                }))
+              }
+              className="font-mono text-xs"
+            />
+          </div>
+          <div className="space-y-2">
+            <Label htmlFor="media-retention" className="text-xs uppercase tracking-[0.12em] text-muted-foreground">
+              Retention (days)
+            </Label>
+            <Input
+              id="media-retention"
+              type="number"
+              min={0}
+              value={draft.media_retention_days}
+              onChange={(event) =>
+                setDraft((previous) => ({
+                  ...previous,
+                  media_retention_days: Math.max(0, Number(event.target.value) || 0),
+                }))
+              }
+              className="font-mono text-xs"
+            />
+          </div>
+        </div>
+        <div className="space-y-2 border border-border/80 bg-background/60 px-3 py-2">
+          <div className="flex items-center justify-between">
+            <Label className="text-xs uppercase tracking-[0.12em] text-muted-foreground">Allowed Source Folders</Label>
+            <Button variant="outline" size="sm" className="gap-1" onClick={onAddSourceRoot}>
+              <FolderPlus className="size-3.5" />
+              Add Folder
+            </Button>
+          </div>
+          {draft.allowed_source_roots.length === 0 ? (
+            <p className="text-xs text-muted-foreground">Only files picked in the file dialog can be ingested.</p>
+          ) : (
+            <>
+              <p className="text-xs text-muted-foreground">
+                Picked folders are allowed right away; removing one takes effect when settings are saved.
+              </p>
+              <ul className="space-y-1">
+                {draft.allowed_source_roots.map((root) => (
+                  <li key={root} className="flex items-center justify-between gap-2 font-mono text-xs">
+                    <span className="truncate">{root}</span>
+                    <Button
+                      variant="ghost"
+                      size="icon-xs"
+                      aria-label={`Remove ${root}`}
+                      onClick={() =>
+                        setDraft((previous) => ({
+                          ...previous,
+                          allowed_source_roots: previous.allowed_source_roots.filter((item) => item !== root),
+                        }))
+                      }
+                    >
+                      <X className="size-3.5" />
+                    </Button>
+                  </li>
+                ))}
+              </ul>
+            </>
+          )}
+        </div>
         <div className="space-y-2">
           <Label htmlFor="gemini-key" className="text-xs uppercase tracking-[0.12em] text-muted-foreground">
             Gemini API Key
@@ -160,6 +574,13 @@ export function SettingsPanel({
               </Button>
             </div>
             <p className="text-[11px] text-muted-foreground">{geminiSourceLabel[geminiApiKeySource]}</p>
+            {geminiKeyValidation ? (
+              <p className="text-[11px] text-muted-foreground">
+                {geminiKeyValidation.ok ? "Key valid" : `Key check failed: ${geminiKeyValidation.message ?? "unknown"}`}
+                {" · checked "}
+                {new Date(geminiKeyValidation.validated_at).toLocaleString()}
+              </p>
+            ) : null}
           </div>
         </div>
         <Button
@@ -170,6 +591,9 @@ export function SettingsPanel({
               vault_path: draft.vault_path.trim(),
               obsidian_cli_path: draft.obsidian_cli_path.trim(),
               gemini_model: draft.gemini_model.trim(),
+              openai_base_url: draft.openai_base_url.trim(),
+              openai_model: draft.openai_model.trim(),
+              gemini_base_url: draft.gemini_base_url.trim(),
             })
           }
           disabled={isSaving}
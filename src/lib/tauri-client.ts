@@ -1,15 +1,29 @@
 import { invoke } from "@tauri-apps/api/core"
 
 import type {
+  BudgetStatus,
   EnqueueIngestionRequest,
   EnqueueIngestionResponse,
+  GarbageCollectionReport,
   GeminiApiKeyStatus,
+  GeminiKeyCheck,
+  GeminiModelInfo,
   JobDetails,
+  JobEvent,
   JobSummary,
+  ModelPrice,
   PreviewNoteResponse,
+  PromptRun,
+  PromptTemplate,
   PublishNoteResponse,
+  QuarantineItem,
+  QuarantineStatus,
   SettingsPayload,
+  StorageUsageReport,
+  SubtitleCue,
+  TokenUsageReport,
   UpdateJobResponse,
+  VerifyMediaReport,
 } from "@/lib/tauri-contracts"
 
 const invokeCommand = <T>(command: string, payload?: Record<string, unknown>) =>
@@ -28,6 +42,45 @@ export const retryJob = (jobId: string) =>
 export const cancelJob = (jobId: string) =>
   invokeCommand<UpdateJobResponse>("cancel_job", { job_id: jobId })
 
+export const verifyMedia = (repair: boolean) =>
+  invokeCommand<VerifyMediaReport>("verify_media", { repair })
+
+export const getStorageUsage = () => invokeCommand<StorageUsageReport>("get_storage_usage")
+
+export const getTokenUsage = () => invokeCommand<TokenUsageReport>("get_token_usage")
+
+export const getBudgetStatus = () => invokeCommand<BudgetStatus>("get_budget_status")
+
+export const listJobEvents = (jobId: string) => invokeCommand<JobEvent[]>("list_job_events", { job_id: jobId })
+
+export const listModelPrices = () => invokeCommand<ModelPrice[]>("list_model_prices")
+
+export const saveModelPrice = (price: ModelPrice) => invokeCommand<ModelPrice>("save_model_price", { price })
+
+export const deleteModelPrice = (model: string) => invokeCommand<void>("delete_model_price", { model })
+
+export const collectMediaGarbage = (dryRun: boolean) =>
+  invokeCommand<GarbageCollectionReport>("collect_media_garbage", { dry_run: dryRun })
+
+export const getAssetThumbnail = (assetId: number) =>
+  invokeCommand<string | null>("get_asset_thumbnail", { asset_id: assetId })
+
+export const getSubtitleCues = (assetId: number) =>
+  invokeCommand<SubtitleCue[]>("get_subtitle_cues", { asset_id: assetId })
+
+export const listQuarantine = (status?: QuarantineStatus) =>
+  invokeCommand<QuarantineItem[]>("list_quarantine", { status })
+
+export const approveQuarantineItem = (itemId: number) =>
+  invokeCommand<UpdateJobResponse>("approve_quarantine_item", { item_id: itemId })
+
+export const discardQuarantineItem = (itemId: number) =>
+  invokeCommand<UpdateJobResponse>("discard_quarantine_item", { item_id: itemId })
+
+export const pickCaptureFiles = () => invokeCommand<string[]>("pick_capture_files")
+
+export const pickSourceRoot = () => invokeCommand<string | null>("pick_source_root")
+
 export const getSettings = () => invokeCommand<SettingsPayload>("get_settings")
 
 export const saveSettings = (payload: SettingsPayload) =>
@@ -42,8 +95,34 @@ export const saveGeminiApiKey = (apiKey: string) =>
 export const clearGeminiApiKey = () =>
   invokeCommand<void>("clear_gemini_api_key")
 
+export const validateGeminiApiKey = () => invokeCommand<GeminiKeyCheck>("validate_gemini_api_key")
+
+export const listGeminiModels = () => invokeCommand<GeminiModelInfo[]>("list_gemini_models")
+
+export const hasOpenAiApiKey = () => invokeCommand<boolean>("has_openai_api_key")
+
+export const saveOpenAiApiKey = (apiKey: string) =>
+  invokeCommand<void>("save_openai_api_key", { api_key: apiKey })
+
+export const clearOpenAiApiKey = () =>
+  invokeCommand<void>("clear_openai_api_key")
+
+export const listPromptTemplates = () => invokeCommand<PromptTemplate[]>("list_prompt_templates")
+
+export const savePromptTemplate = (key: string, content: string) =>
+  invokeCommand<PromptTemplate>("save_prompt_template", { key, content })
+
+export const deletePromptTemplate = (key: string) =>
+  invokeCommand<UpdateJobResponse>("delete_prompt_template", { key })
+
+export const listPromptRuns = (jobId: string) =>
+  invokeCommand<PromptRun[]>("list_prompt_runs", { job_id: jobId })
+
 export const previewNote = (jobId: string) =>
   invokeCommand<PreviewNoteResponse>("preview_note", { job_id: jobId })
 
+export const regenerateNote = (jobId: string) =>
+  invokeCommand<PreviewNoteResponse>("regenerate_note", { job_id: jobId })
+
 export const publishNote = (jobId: string) =>
   invokeCommand<PublishNoteResponse>("publish_note", { job_id: jobId })
@@ -1,8 +1,15 @@
+export type IngestMode = "copy" | "move" | "reference"
+
 export interface EnqueueIngestionRequest {
   file_paths: string[]
   note_title?: string
+  text_content?: string
+  ingest_mode?: IngestMode
+  note_language?: NoteLanguage
 }
 
+export type NoteLanguage = "source" | "en" | "pt" | "es" | "fr" | "de" | "it"
+
 export interface EnqueueIngestionResponse {
   job_id: string
 }
@@ -14,6 +21,8 @@ export interface JobSummary {
   created_at: number
   updated_at: number
   asset_count: number
+  thumbnail_asset_id: number | null
+  note_language: NoteLanguage | null
 }
 
 export interface JobAsset {
@@ -25,27 +34,234 @@ export interface JobAsset {
   mime_type: string
   size_bytes: number
   sha256: string
+  ingest_mode: IngestMode
+  encrypted: boolean
+  archive_path: string | null
+  archive_entry: string | null
+  derivative_path: string | null
+  derivative_mime_type: string | null
+  derivative_size_bytes: number | null
+  thumbnail_path: string | null
+  perceptual_hash: number | null
+  near_duplicate_of: number | null
+  duration_ms: number | null
+  parent_asset_id: number | null
+}
+
+export interface MediaChunk {
+  id: number
+  asset_id: number
+  chunk_index: number
+  start_ms: number
+  end_ms: number
+  storage_path: string
+  size_bytes: number
+}
+
+export interface SubtitleCue {
+  id: number
+  asset_id: number
+  cue_index: number
+  start_ms: number
+  end_ms: number
+  text: string
+}
+
+export type QuarantineStatus = "pending" | "approved" | "discarded"
+
+export interface QuarantineItem {
+  id: number
+  job_id: string
+  original_path: string
+  storage_path: string
+  media_type: string
+  mime_type: string
+  size_bytes: number
+  sha256: string
+  ingest_mode: IngestMode
+  encrypted: boolean
+  reason: string
+  status: QuarantineStatus
+  created_at: number
+  resolved_at: number | null
 }
 
 export interface JobDetails {
   job: JobSummary
   assets: JobAsset[]
+  chunks: MediaChunk[]
+}
+
+export interface MediaIssue {
+  asset_id: number
+  job_id: string
+  storage_path: string
+  kind: "missing" | "hash_mismatch" | "zero_byte" | "unreadable"
+  detail: string
+  repaired: boolean
+}
+
+export interface VerifyMediaReport {
+  checked: number
+  healthy: number
+  repaired: number
+  issues: MediaIssue[]
+}
+
+export interface JobStorageUsage {
+  job_id: string
+  title: string
+  bytes: number
+}
+
+export interface MonthlyStorageUsage {
+  month: string
+  bytes: number
+}
+
+export interface StorageUsageReport {
+  total_bytes: number
+  quota_bytes: number
+  per_job: JobStorageUsage[]
+  per_month: MonthlyStorageUsage[]
+}
+
+export interface GarbageCollectionReport {
+  dry_run: boolean
+  orphaned_files: number
+  orphaned_bytes: number
+  evicted_assets: number
+  evicted_bytes: number
 }
 
 export interface UpdateJobResponse {
   ok: boolean
 }
 
+export type LlmProvider = "gemini" | "openai_compatible"
+
 export interface SettingsPayload {
   vault_path: string
   obsidian_cli_path: string
   gemini_model: string
   write_mode: "cli_only" | "filesystem_only" | "cli_fallback"
+  media_quota_bytes: number
+  media_retention_days: number
+  ingest_mode: IngestMode
+  encrypt_media: boolean
+  image_derivatives_enabled: boolean
+  image_max_dimension: number
+  image_jpeg_quality: number
+  allowed_source_roots: string[]
+  prompt_template_key: string
+  note_language: NoteLanguage
+  llm_provider: LlmProvider
+  openai_base_url: string
+  openai_model: string
+  gemini_base_url: string
+  daily_token_budget: number
+  monthly_token_budget: number
+  daily_cost_budget_usd: number
+  monthly_cost_budget_usd: number
+  gemini_requests_per_minute: number
+}
+
+export interface PromptRun {
+  id: number
+  job_id: string
+  prompt_template_key: string
+  model: string
+  prompt_body: string
+  response_body: string | null
+  latency_ms: number | null
+  prompt_tokens: number | null
+  response_tokens: number | null
+  total_tokens: number | null
+  error: string | null
+  created_at: number
+  cached_tokens: number | null
+  cost_usd: number | null
+}
+
+export interface ModelPrice {
+  model: string
+  input_usd_per_mtok: number
+  output_usd_per_mtok: number
+  cached_input_usd_per_mtok: number
+  updated_at: number
+}
+
+export interface TokenTotals {
+  calls: number
+  prompt_tokens: number
+  response_tokens: number
+  cached_tokens: number
+  total_tokens: number
+  cost_usd: number
+}
+
+export interface JobTokenUsage {
+  job_id: string
+  title: string
+  totals: TokenTotals
+}
+
+export interface MonthlyTokenUsage {
+  month: string
+  totals: TokenTotals
+}
+
+export interface TokenUsageReport {
+  totals: TokenTotals
+  per_job: JobTokenUsage[]
+  per_month: MonthlyTokenUsage[]
+}
+
+export interface BudgetStatus {
+  today: TokenTotals
+  this_month: TokenTotals
+  exceeded: string | null
+}
+
+export interface JobEvent {
+  id: number
+  job_id: string
+  kind: string
+  message: string
+  created_at: number
+}
+
+export interface PromptTemplate {
+  id: number
+  key: string
+  content: string
+  updated_at: number
+}
+
+export interface KeyValidation {
+  ok: boolean
+  message: string | null
+  validated_at: number
 }
 
 export interface GeminiApiKeyStatus {
   configured: boolean
   source: "os_keychain" | "environment" | "missing"
+  last_validation: KeyValidation | null
+}
+
+export interface GeminiModelInfo {
+  id: string
+  display_name: string
+  description: string | null
+  input_token_limit: number | null
+  output_token_limit: number | null
+  assumed_input_modalities: string[]
+}
+
+export interface GeminiKeyCheck {
+  validation: KeyValidation
+  models: GeminiModelInfo[]
 }
 
 export interface PreviewNoteResponse {
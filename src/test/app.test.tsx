@@ -9,6 +9,25 @@ describe("App", () => {
       obsidian_cli_path: "obsidian",
       gemini_model: "gemini-2.5-flash",
       write_mode: "cli_fallback",
+      media_quota_bytes: 0,
+      media_retention_days: 0,
+      ingest_mode: "copy",
+      encrypt_media: false,
+      image_derivatives_enabled: false,
+      image_max_dimension: 2048,
+      image_jpeg_quality: 82,
+      allowed_source_roots: [],
+      prompt_template_key: "default_extraction",
+      note_language: "pt",
+      llm_provider: "gemini",
+      openai_base_url: "http://localhost:11434/v1",
+      openai_model: "llama3.2",
+      gemini_base_url: "https://generativelanguage.googleapis.com",
+      daily_token_budget: 0,
+      monthly_token_budget: 0,
+      daily_cost_budget_usd: 0,
+      monthly_cost_budget_usd: 0,
+      gemini_requests_per_minute: 10,
     }
 
     expect(payload.write_mode).toBe("cli_fallback")